@@ -0,0 +1,3 @@
+-- Migration number: 0002
+-- unix timestamp in milliseconds, set while an account deletion is pending
+ALTER TABLE user_account ADD COLUMN delete_after INTEGER;
@@ -0,0 +1,11 @@
+-- Migration number: 0003
+CREATE TABLE user_identity (
+	provider TEXT NOT NULL,
+	subject TEXT NOT NULL,
+	uid TEXT NOT NULL,
+	email TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (provider, subject)
+) WITHOUT ROWID;
+
+CREATE INDEX user_identity_uid ON user_identity (uid);
@@ -0,0 +1,4 @@
+-- Migration number: 0008
+-- per-account salt for the client side password hash
+-- NULL for accounts from before this, which still derive it from their email until their next signin
+ALTER TABLE user_account ADD COLUMN client_salt TEXT;
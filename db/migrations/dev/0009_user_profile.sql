@@ -0,0 +1,12 @@
+-- Migration number: 0009
+-- display details, filled in from openid profile claims until the user edits them (see user_edited)
+CREATE TABLE user_profile (
+	uid TEXT PRIMARY KEY,
+	name TEXT,
+	given_name TEXT,
+	family_name TEXT,
+	locale TEXT,
+	picture_url TEXT,
+	user_edited BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,15 @@
+-- Migration number: 0010
+-- registration invites, see REGISTRATION_POLICY
+CREATE TABLE invite (
+	code TEXT PRIMARY KEY NOT NULL,
+	inviter_uid TEXT NOT NULL,
+	-- if set, only this email can register with it
+	email TEXT,
+	max_uses INTEGER NOT NULL,
+	uses INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	-- unix timestamp in milliseconds
+	expires_at INTEGER
+) WITHOUT ROWID;
+
+CREATE INDEX invite_inviter_uid ON invite (inviter_uid);
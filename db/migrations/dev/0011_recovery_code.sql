@@ -0,0 +1,9 @@
+-- Migration number: 0011
+-- single-use account recovery codes, deleted as they're used
+-- only the sha256 hash is stored, the codes are shown to the user once
+CREATE TABLE recovery_code (
+	uid TEXT NOT NULL,
+	code_hash TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (uid, code_hash)
+) WITHOUT ROWID;
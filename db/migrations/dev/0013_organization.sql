@@ -0,0 +1,36 @@
+-- Migration number: 0013
+-- organizations, their members (with a role within the organization) and pending email invites
+-- a user may belong to many organizations, active_org_id is the one their requests act on
+CREATE TABLE organization (
+	id TEXT PRIMARY KEY NOT NULL,
+	name TEXT NOT NULL,
+	created_by TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+) WITHOUT ROWID;
+
+CREATE TABLE membership (
+	org_id TEXT NOT NULL,
+	uid TEXT NOT NULL,
+	-- owner, admin or member
+	role TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (org_id, uid)
+) WITHOUT ROWID;
+
+CREATE INDEX membership_uid ON membership (uid);
+
+-- the id is the id of the oob token sent in the invite email
+CREATE TABLE organization_invite (
+	id TEXT PRIMARY KEY NOT NULL,
+	org_id TEXT NOT NULL,
+	email TEXT NOT NULL,
+	role TEXT NOT NULL,
+	invited_by TEXT NOT NULL,
+	-- unix timestamp in milliseconds
+	expires_at INTEGER NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+) WITHOUT ROWID;
+
+CREATE INDEX organization_invite_org_id ON organization_invite (org_id);
+
+ALTER TABLE user_account ADD COLUMN active_org_id TEXT;
@@ -0,0 +1,18 @@
+-- Migration number: 0014
+-- email domains claimed by an organization, whose emails must then sign in through its OpenID provider
+-- claims are only enforced once verified (through a DNS TXT record), and only one organization can hold a verified claim
+CREATE TABLE sso_domain (
+	domain TEXT NOT NULL,
+	org_id TEXT NOT NULL,
+	-- the OpenID provider slug
+	provider TEXT NOT NULL,
+	-- the value the TXT record must have
+	verification_token TEXT NOT NULL,
+	-- unix timestamp in milliseconds, NULL until verified
+	verified_at INTEGER,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (domain, org_id)
+) WITHOUT ROWID;
+
+CREATE INDEX sso_domain_org_id ON sso_domain (org_id);
+CREATE UNIQUE INDEX sso_domain_verified ON sso_domain (domain) WHERE verified_at IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Migration number: 0015
+-- auth events are also removed by email when an account is deleted,
+-- since failed signins for an existing account can be logged before its uid is known
+CREATE INDEX auth_event_email ON auth_event (email);
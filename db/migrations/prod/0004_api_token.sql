@@ -0,0 +1,16 @@
+-- Migration number: 0004
+-- personal api tokens, only the sha256 hash of the secret is stored
+CREATE TABLE api_token (
+	id TEXT PRIMARY KEY NOT NULL,
+	uid TEXT NOT NULL,
+	name TEXT NOT NULL,
+	token_hash TEXT NOT NULL,
+	-- JSON array of scopes
+	scopes TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	-- unix timestamps in milliseconds
+	last_used_at INTEGER,
+	expires_at INTEGER
+) WITHOUT ROWID;
+
+CREATE INDEX api_token_uid ON api_token (uid);
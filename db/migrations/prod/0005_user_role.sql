@@ -0,0 +1,8 @@
+-- Migration number: 0005
+-- roles granted to users, see shared::user::Role for what each one allows
+CREATE TABLE user_role (
+	uid TEXT NOT NULL,
+	role TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (uid, role)
+) WITHOUT ROWID;
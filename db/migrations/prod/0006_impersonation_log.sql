@@ -0,0 +1,15 @@
+-- Migration number: 0006
+-- audit trail of admins impersonating users
+-- kept when either account is deleted, it's a record of what the admin did
+CREATE TABLE impersonation_log (
+	id INTEGER PRIMARY KEY,
+	actor_uid TEXT NOT NULL,
+	target_uid TEXT NOT NULL,
+	-- start, request, or stop
+	event TEXT NOT NULL,
+	route TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX impersonation_log_actor_uid ON impersonation_log (actor_uid);
+CREATE INDEX impersonation_log_target_uid ON impersonation_log (target_uid);
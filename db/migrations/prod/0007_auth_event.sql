@@ -0,0 +1,19 @@
+-- Migration number: 0007
+-- security audit log of authentication events
+-- uid is null for failed signins that don't match an account, the attempted email is kept instead
+CREATE TABLE auth_event (
+	id INTEGER PRIMARY KEY,
+	uid TEXT,
+	email TEXT,
+	-- register, signin_success, signin_failure, etc.
+	kind TEXT NOT NULL,
+	-- e.g. the failure reason, or the signin method
+	detail TEXT,
+	ip TEXT,
+	user_agent TEXT,
+	-- cloudflare's cf-ray
+	request_id TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX auth_event_uid ON auth_event (uid);
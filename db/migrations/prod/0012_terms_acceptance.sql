@@ -0,0 +1,10 @@
+-- Migration number: 0012
+-- proof of which terms version each user accepted, and when and from where
+-- a row per version, so older acceptances stay on record after the terms change
+CREATE TABLE terms_acceptance (
+	uid TEXT NOT NULL,
+	version INTEGER NOT NULL,
+	ip TEXT,
+    accepted_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY (uid, version)
+) WITHOUT ROWID;
@@ -0,0 +1,113 @@
+// Generates the Cloudflare Pages _headers file for a frontend build
+// run after `trunk build`, since the Content-Security-Policy needs the hashes of
+// the inline scripts trunk writes into index.html (the wasm bootstrap)
+// usage: cargo run -p frontend-headers -- <dist dir>
+
+use std::{fs, path::PathBuf};
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+// _headers only goes out with a Pages deploy, which is always the production config
+use shared::frontend::config::FRONTEND_DEPLOY_CONFIG_PROD as CONFIG;
+
+// fonts are loaded from google, see index.html
+const FONT_STYLE_ORIGIN: &str = "https://fonts.googleapis.com";
+const FONT_ORIGIN: &str = "https://fonts.gstatic.com";
+// the bot challenge script and its iframe, only if there's a site key, see atoms::challenge
+const CHALLENGE_ORIGIN: &str = "https://challenges.cloudflare.com";
+
+fn main() {
+    let dist_dir = PathBuf::from(std::env::args().nth(1).unwrap_or_else(|| "dist".to_string()));
+
+    let index = fs::read_to_string(dist_dir.join("index.html")).expect("missing index.html, run trunk build first");
+    let script_hashes = inline_script_hashes(&index);
+    if script_hashes.is_empty() {
+        panic!("no inline scripts in index.html, is this a trunk build?");
+    }
+
+    let headers = [
+        ("Content-Security-Policy", content_security_policy(&script_hashes)),
+        ("Strict-Transport-Security", "max-age=63072000; includeSubDomains".to_string()),
+        ("X-Content-Type-Options", "nosniff".to_string()),
+        // frontend urls can have oob tokens in them (e.g. password reset), which shouldn't leak to other sites
+        ("Referrer-Policy", "no-referrer".to_string()),
+        ("Permissions-Policy", "accelerometer=(), camera=(), geolocation=(), gyroscope=(), magnetometer=(), microphone=(), payment=(), usb=()".to_string()),
+        ("X-Frame-Options", "DENY".to_string()),
+    ];
+
+    let mut output = String::from("/*\n");
+    for (name, value) in headers {
+        output.push_str(&format!("  {name}: {value}\n"));
+    }
+
+    fs::write(dist_dir.join("_headers"), output).expect("failed to write _headers");
+}
+
+fn content_security_policy(script_hashes: &[String]) -> String {
+    // the api and media may be on other domains, or relative to the frontend (which 'self' covers)
+    let api_origin = url_origin(CONFIG.api_domain);
+    let media_origin = url_origin(CONFIG.media_root);
+    let challenge_origin = CONFIG.turnstile_site_key.map(|_| CHALLENGE_ORIGIN.to_string());
+
+    let script_src = ["'self'", "'wasm-unsafe-eval'"]
+        .into_iter()
+        .map(String::from)
+        .chain(script_hashes.iter().map(|hash| format!("'sha256-{hash}'")))
+        .chain(challenge_origin.clone())
+        .collect::<Vec<_>>();
+
+    let connect_src = ["'self'"].into_iter().map(String::from).chain(api_origin.clone()).chain(media_origin.clone()).collect::<Vec<_>>();
+    // profile pictures are hotlinked from whichever host the openid provider uses
+    let img_src = ["'self'", "data:", "https:"].into_iter().map(String::from).chain(media_origin).collect::<Vec<_>>();
+
+    let directives = [
+        ("default-src", vec!["'self'".to_string()]),
+        ("script-src", script_src),
+        // styles are set through the cssom by dominator, which isn't affected by this
+        ("style-src", vec!["'self'".to_string(), FONT_STYLE_ORIGIN.to_string()]),
+        ("font-src", vec![FONT_ORIGIN.to_string()]),
+        ("connect-src", connect_src),
+        ("img-src", img_src),
+        ("frame-src", ["'self'".to_string()].into_iter().chain(challenge_origin).collect::<Vec<_>>()),
+        ("object-src", vec!["'none'".to_string()]),
+        ("base-uri", vec!["'self'".to_string()]),
+        ("form-action", vec!["'self'".to_string()]),
+        ("frame-ancestors", vec!["'none'".to_string()]),
+    ];
+
+    directives
+        .into_iter()
+        .map(|(name, sources)| format!("{name} {}", sources.join(" ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// base64 sha256 of every inline <script> (i.e. without a src), exactly as written
+fn inline_script_hashes(html: &str) -> Vec<String> {
+    let mut hashes = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find("<script") {
+        let after_start = &rest[start..];
+        let Some(tag_end) = after_start.find('>') else { break };
+        let tag = &after_start[..tag_end];
+        let body_start = &after_start[tag_end + 1..];
+        let Some(body_end) = body_start.find("</script>") else { break };
+
+        if !tag.contains(" src=") {
+            let body = &body_start[..body_end];
+            hashes.push(base64::engine::general_purpose::STANDARD.encode(Sha256::digest(body.as_bytes())));
+        }
+
+        rest = &body_start[body_end + "</script>".len()..];
+    }
+
+    hashes
+}
+
+// e.g. "https://example.com:8080/foo" -> "https://example.com:8080", and None for relative paths
+fn url_origin(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let host = rest.split('/').next()?;
+    Some(format!("{scheme}://{host}"))
+}
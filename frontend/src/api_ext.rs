@@ -4,7 +4,7 @@ use std::ops::Deref;
 use async_trait::async_trait;
 use awsm_web::{loaders::fetch::{fetch_url, fetch_with_data, fetch_with_headers, fetch_with_headers_and_data, Response}, prelude::UnwrapExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use shared::{api::{ApiEmpty, ApiBoth, ApiReq, ApiRes}, auth::AUTH_TOKEN_KEY_NAME, backend::{
+use shared::{api::{ApiEmpty, ApiBoth, ApiReq, ApiRes}, auth::{AUTH_IMPERSONATION_TOKEN_ID_NAME, AUTH_TOKEN_KEY_NAME}, backend::{
     result::{ApiError, ApiResult, AuthError}, route::{AuthRoute, Route as ApiRoute, RouteAuthKind}
 }};
 use crate::{CONFIG, LOCALE};
@@ -131,7 +131,14 @@ where T: ApiEmpty
 }
 
 // helpers
-fn auth_headers() -> ApiResult<[(&'static str, String);2]> {
+fn auth_headers() -> ApiResult<Vec<(&'static str, String)>> {
+    let lang = ("Content-Language", LOCALE.current.lock_ref().lang_id.to_string());
+
+    // the impersonation token takes priority over the signin cookie, which is still sent
+    if let Some(impersonation) = AUTH.impersonation() {
+        return Ok(vec![(AUTH_IMPERSONATION_TOKEN_ID_NAME, impersonation.token_id), (AUTH_TOKEN_KEY_NAME, impersonation.token_key), lang]);
+    }
+
     let token = match AUTH.try_clone_token_key() {
         Some(token) => Some(token),
         None => {
@@ -146,7 +153,7 @@ fn auth_headers() -> ApiResult<[(&'static str, String);2]> {
     };
 
     match token {
-        Some(token) => Ok([(AUTH_TOKEN_KEY_NAME, token), ("Content-Language", LOCALE.current.lock_ref().lang_id.to_string())]),
+        Some(token) => Ok(vec![(AUTH_TOKEN_KEY_NAME, token), lang]),
         None => Err(ApiError::Auth(AuthError::NotAuthorized))
     }
 }
@@ -199,7 +206,13 @@ async fn map_bad_status(res: Response) -> ApiError {
         ApiError::Auth(auth_error) => {
             match auth_error {
                 AuthError::NotAuthorized => {
-                    AUTH.clear();
+                    // an expired impersonation shouldn't sign the admin out of their own session
+                    if AUTH.impersonation().is_some() {
+                        AUTH.clear_impersonation();
+                        let _ = web_sys::window().unwrap_ext().location().reload();
+                    } else {
+                        AUTH.clear();
+                    }
                 },
                 // do not clear the auth in these cases, it's part of the auth flow itself
                 // and clearing it would just cause a page refresh
@@ -208,7 +221,11 @@ async fn map_bad_status(res: Response) -> ApiError {
                     // in auth_check()... but better safe than sorry!
                     AUTH.phase.set_neq(AuthPhase::EmailNotVerified);
                 },
-                AuthError::InvalidSignin | AuthError::NoUserPasswordReset | AuthError::EmailAlreadyExists => {
+                AuthError::TermsNotAccepted => {
+                    // same as above, e.g. if the terms changed while the user was on a page
+                    AUTH.phase.set_neq(AuthPhase::TermsNotAccepted);
+                },
+                AuthError::InvalidSignin | AuthError::NoUserPasswordReset | AuthError::EmailAlreadyExists | AuthError::ConfirmationRequired | AuthError::RecentSigninRequired | AuthError::InvalidOrigin | AuthError::OpenIdEmailCollision | AuthError::OpenIdIdentityAlreadyLinked | AuthError::RegistrationClosed | AuthError::InviteRequired | AuthError::InvalidInvite | AuthError::EmailDomainNotAllowed | AuthError::EmailNotAllowed | AuthError::DisposableEmail | AuthError::ChallengeFailed | AuthError::NoActiveOrganization | AuthError::OrganizationInviteWrongAccount | AuthError::SsoRequired { .. } | AuthError::InvalidVerificationCode { .. } | AuthError::TooManyAttempts { .. } | AuthError::Forbidden => {
                     // do nothing
                 },
 
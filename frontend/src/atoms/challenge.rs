@@ -0,0 +1,105 @@
+use futures::channel::oneshot;
+use gloo_events::EventListener;
+use wasm_bindgen_futures::spawn_local;
+use crate::prelude::*;
+
+const SCRIPT_ID: &str = "turnstile-script";
+// explicit rendering, since the widgets come and go with the page rather than being in index.html
+const SCRIPT_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/api.js?render=explicit";
+
+#[wasm_bindgen]
+extern "C" {
+    // returns the widget id
+    #[wasm_bindgen(js_namespace = turnstile, js_name = render)]
+    fn turnstile_render(container: &web_sys::HtmlElement, params: &JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = turnstile, js_name = reset)]
+    fn turnstile_reset(widget_id: &str);
+}
+
+// The bot challenge (Cloudflare Turnstile) for the routes the backend's CHALLENGE config protects
+// without a site key in CONFIG it renders nothing and the token stays None
+// which is fine as long as the backend doesn't require one either
+#[derive(Clone)]
+pub struct ChallengeWidget {
+    pub token: Mutable<Option<String>>,
+    widget_id: Mutable<Option<String>>,
+}
+
+impl ChallengeWidget {
+    pub fn new() -> Self {
+        Self {
+            token: Mutable::new(None),
+            widget_id: Mutable::new(None),
+        }
+    }
+
+    // a token can only be verified once, so every attempt needs a new one
+    pub fn reset(&self) {
+        self.token.set(None);
+        if let Some(widget_id) = self.widget_id.get_cloned() {
+            turnstile_reset(&widget_id);
+        }
+    }
+
+    pub fn render(&self) -> Dom {
+        let token = self.token.clone();
+        let widget_id = self.widget_id.clone();
+
+        html!("div", {
+            .apply(|dom| match CONFIG.turnstile_site_key {
+                None => dom,
+                Some(site_key) => dom.after_inserted(move |elem| {
+                    spawn_local(async move {
+                        if let Err(err) = load_script().await {
+                            log::error!("couldn't load the challenge script: {:?}", err);
+                            return;
+                        }
+
+                        let on_token = Closure::<dyn FnMut(String)>::new(clone!(token => move |value: String| {
+                            token.set(Some(value));
+                        }));
+                        let on_expired = Closure::<dyn FnMut()>::new(clone!(token => move || {
+                            token.set(None);
+                        }));
+
+                        let params = js_sys::Object::new();
+                        js_sys::Reflect::set(&params, &"sitekey".into(), &site_key.into()).unwrap_ext();
+                        js_sys::Reflect::set(&params, &"callback".into(), &on_token.into_js_value()).unwrap_ext();
+                        js_sys::Reflect::set(&params, &"expired-callback".into(), &on_expired.into_js_value()).unwrap_ext();
+
+                        widget_id.set(turnstile_render(&elem, &params).as_string());
+                    });
+                })
+            })
+        })
+    }
+}
+
+async fn load_script() -> std::result::Result<(), JsValue> {
+    let window = web_sys::window().unwrap_ext();
+    if js_sys::Reflect::has(&window, &"turnstile".into())? {
+        return Ok(());
+    }
+
+    let document = window.document().unwrap_ext();
+
+    // another widget may have started loading it already
+    let script = match document.get_element_by_id(SCRIPT_ID) {
+        Some(script) => script,
+        None => {
+            let script = document.create_element("script")?;
+            script.set_id(SCRIPT_ID);
+            script.set_attribute("src", SCRIPT_URL)?;
+            document.head().unwrap_ext().append_child(&script)?;
+            script
+        }
+    };
+
+    let (tx, rx) = oneshot::channel();
+    let _listener = EventListener::once(&script, "load", move |_| {
+        let _ = tx.send(());
+    });
+
+    rx.await.map_err(|_| JsValue::from_str("challenge script didn't load"))
+}
@@ -1,3 +1,4 @@
 pub mod buttons;
+pub mod challenge;
 pub mod dynamic_svg;
 pub mod input;
\ No newline at end of file
@@ -14,12 +14,12 @@ use argon2::{
 use dominator::clone;
 use futures_signals::signal::{Mutable, Signal, SignalExt};
 use once_cell::sync::Lazy;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use shared::{
-    api::auth::{AuthCheck, AuthCheckResetPassword, AuthCheckResetPasswordRequest, AuthCheckResetPasswordResponse, AuthConfirmResetPassword, AuthConfirmResetPasswordRequest, AuthConfirmResetPasswordResponse, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailRequest, AuthOpenIdConnect, AuthOpenIdConnectRequest, AuthRegister, AuthRegisterRequest, AuthRegisterResponse, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendResetPasswordRequestAny, AuthSendVerifyEmail, AuthSignin, AuthSigninRequest, AuthSigninResponse, AuthSignout}, auth::FRONTEND_ROUTE_AFTER_SIGNIN, backend::{
+    api::{admin::{AdminAccountRequest, AdminImpersonationStart}, auth::{AuthCheck, AuthCheckResetPassword, AuthCheckResetPasswordRequest, AuthCheckResetPasswordResponse, AuthConfirmResetPassword, AuthConfirmResetPasswordRequest, AuthConfirmResetPasswordResponse, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailRequest, AuthOpenIdConnect, AuthOpenIdConnectRequest, AuthRegister, AuthRegisterRequest, AuthRegisterResponse, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendResetPasswordRequestAny, AuthSendVerifyEmail, AuthImpersonationStop, AuthSignin, AuthSigninRequest, AuthSigninResponse, AuthSignout}}, auth::FRONTEND_ROUTE_AFTER_SIGNIN, backend::{
         result::{ApiError, ApiResult, AuthError}, 
         route::{AuthRoute as ApiAuthRoute, OpenIdProvider, Route as ApiRoute}
-    }, user::UserId
+    }, user::{Permission, Role, UserId}
 };
 
 use crate::{page::landing::auth::send_email_validation, prelude::*};
@@ -31,8 +31,9 @@ pub static AUTH:Lazy<Auth> = Lazy::new(|| {
     let loader = Arc::new(AsyncLoader::new());
     let token_key = Arc::new(RwLock::new(None));
     let uid = Arc::new(RwLock::new(None));
+    let roles = Arc::new(RwLock::new(Vec::new()));
 
-    let _auth = Auth{phase, loader, token_key, uid};
+    let _auth = Auth{phase, loader, token_key, uid, roles};
 
     _auth.loader.load(clone!(_auth => async move {_auth.check().await}));
     _auth
@@ -43,6 +44,8 @@ pub struct Auth {
     pub phase: Mutable<AuthPhase>,
     pub token_key: Arc<RwLock<Option<String>>>,
     pub uid: Arc<RwLock<Option<UserId>>>,
+    // only used to decide which pages to show, the backend checks permissions itself
+    pub roles: Arc<RwLock<Vec<Role>>>,
     loader: Arc<AsyncLoader>
 }
 
@@ -51,6 +54,8 @@ pub enum AuthPhase {
     Init,
     Authenticated,
     EmailNotVerified,
+    // signed in, but the terms changed since they were last accepted
+    TermsNotAccepted,
     Unauthenticated,
 }
 
@@ -58,14 +63,16 @@ impl Auth {
     pub fn clear(&self) {
         *self.token_key.write().unwrap() = None;
         *self.uid.write().unwrap() = None;
+        self.roles.write().unwrap().clear();
         self.phase.set_neq(AuthPhase::Unauthenticated);
         let _ = web_sys::window().unwrap_ext().local_storage().unwrap_ext().unwrap_ext().delete(CONFIG.auth_signin_key_storage_name);
     }
 
-    pub async fn signout(&self) -> ApiResult<()> {
-        AuthSignout::fetch().await?;
+    // returns where to go to sign out of the openid provider too, if anywhere
+    pub async fn signout(&self) -> ApiResult<Option<String>> {
+        let res = AuthSignout::fetch().await?;
         self.clear();
-        Ok(())
+        Ok(res.logout_url)
     }
 
     pub fn try_clone_uid(&self) -> Option<UserId>
@@ -77,6 +84,10 @@ impl Auth {
         self.token_key.read().unwrap().clone()
     }
 
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        Role::has_permission(&self.roles.read().unwrap(), permission)
+    }
+
     pub async fn on_signin(&self, uid: UserId, email_verified: bool, auth_key: String) -> ApiResult<()> {
         web_sys::window().unwrap_ext().local_storage().unwrap_ext().unwrap_ext().set_item(CONFIG.auth_signin_key_storage_name, &auth_key).unwrap_ext();
         *self.uid.write().unwrap() = Some(uid);
@@ -87,7 +98,8 @@ impl Auth {
             send_email_validation().await?;
             self.phase.set_neq(AuthPhase::EmailNotVerified);
         } else {
-            self.phase.set_neq(AuthPhase::Authenticated);
+            // the signin responses don't include roles, so load them the same way as on startup
+            self.check().await;
         }
 
         Ok(())
@@ -98,6 +110,7 @@ impl Auth {
         match res {
             Ok(res) => {
                 *self.uid.write().unwrap() = Some(res.uid);
+                *self.roles.write().unwrap() = res.roles;
                 self.phase.set_neq(AuthPhase::Authenticated);
             },
             Err(err) => {
@@ -110,6 +123,10 @@ impl Auth {
                     ApiError::Auth(AuthError::EmailNotVerified) => {
                         self.phase.set_neq(AuthPhase::EmailNotVerified);
                     },
+                    // same idea, the terms are checked after the email
+                    ApiError::Auth(AuthError::TermsNotAccepted) => {
+                        self.phase.set_neq(AuthPhase::TermsNotAccepted);
+                    },
                     _ => {
                         log::error!("{:?}", err);
                         self.phase.set_neq(AuthPhase::Unauthenticated);
@@ -118,4 +135,48 @@ impl Auth {
             }
         }
     }
+
+    // set while an admin is impersonating a user in this tab
+    pub fn impersonation(&self) -> Option<Impersonation> {
+        web_sys::window().unwrap_ext().session_storage().unwrap_ext().unwrap_ext()
+            .get_item(CONFIG.auth_impersonation_storage_name).ok().flatten()
+            .and_then(|impersonation| serde_json::from_str(&impersonation).ok())
+    }
+
+    // the caller should reload the page afterwards, so everything is fetched as the user
+    pub async fn start_impersonation(&self, uid: UserId) -> ApiResult<()> {
+        let res = AdminImpersonationStart::fetch(AdminAccountRequest { uid: uid.clone() }).await?;
+        let impersonation = Impersonation {
+            uid,
+            token_id: res.token_id,
+            token_key: res.token_key,
+            expires_at: res.expires_at,
+        };
+
+        web_sys::window().unwrap_ext().session_storage().unwrap_ext().unwrap_ext()
+            .set_item(CONFIG.auth_impersonation_storage_name, &serde_json::to_string(&impersonation).unwrap_ext()).unwrap_ext();
+
+        Ok(())
+    }
+
+    pub async fn stop_impersonation(&self) {
+        // it may have expired already, either way we're done with it
+        if let Err(err) = AuthImpersonationStop::fetch().await {
+            log::error!("{:?}", err);
+        }
+        self.clear_impersonation();
+    }
+
+    pub fn clear_impersonation(&self) {
+        let _ = web_sys::window().unwrap_ext().session_storage().unwrap_ext().unwrap_ext().delete(CONFIG.auth_impersonation_storage_name);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Impersonation {
+    pub uid: UserId,
+    pub token_id: String,
+    pub token_key: String,
+    // unix timestamp in milliseconds
+    pub expires_at: u64,
 }
\ No newline at end of file
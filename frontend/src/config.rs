@@ -1,5 +1,6 @@
 use awsm_web::env::env_var;
 use once_cell::sync::Lazy;
+use shared::frontend::config::{FRONTEND_DEPLOY_CONFIG_DEV, FRONTEND_DEPLOY_CONFIG_PROD};
 
 use crate::prelude::*;
 
@@ -10,6 +11,8 @@ pub struct Config {
     // all parsing to start from /bar
     // it's helpful in shared hosting environments where the app is not at the root
     pub root_path: &'static str,
+    // media_root, api_domain, and turnstile_site_key are set in shared (FrontendDeployConfig)
+    // so frontend/headers can put them in the Content-Security-Policy without depending on this crate
     pub media_root: &'static str,
     pub default_lang: Option<&'static str>,
     pub api_domain: &'static str,
@@ -17,6 +20,13 @@ pub struct Config {
     // see usage and comments in auth, this is fine
     pub argon2_global_salt: &'static [u8],
     pub auth_signin_key_storage_name: &'static str,
+    // in session storage, so only the tab that started impersonating is affected
+    pub auth_impersonation_storage_name: &'static str,
+    // linked wherever the user agrees to the terms, the version itself is the backend's TERMS_VERSION
+    pub terms_url: &'static str,
+    // the Cloudflare Turnstile site key, see the backend's CHALLENGE config
+    // None means no challenge widget is shown
+    pub turnstile_site_key: Option<&'static str>,
 }
 
 impl Config {
@@ -30,24 +40,30 @@ cfg_if::cfg_if! {
         pub const CONFIG: Lazy<Config> = Lazy::new(|| {
             Config {
                 root_path: "",
-                media_root: "http://localhost:9000",
+                media_root: FRONTEND_DEPLOY_CONFIG_DEV.media_root,
                 default_lang: None,
-                api_domain: "http://localhost:8787",
+                api_domain: FRONTEND_DEPLOY_CONFIG_DEV.api_domain,
                 api_root_path: "",
                 argon2_global_salt: b"example",
                 auth_signin_key_storage_name: "auth_signin_key",
+                auth_impersonation_storage_name: "auth_impersonation",
+                terms_url: "/terms",
+                turnstile_site_key: FRONTEND_DEPLOY_CONFIG_DEV.turnstile_site_key,
             }
         });
     } else {
         pub const CONFIG: Lazy<Config> = Lazy::new(|| {
             Config {
                 root_path: "",
-                media_root: "/media",
+                media_root: FRONTEND_DEPLOY_CONFIG_PROD.media_root,
                 default_lang: None,
-                api_domain: "https://api-prod.example.workers.dev",
+                api_domain: FRONTEND_DEPLOY_CONFIG_PROD.api_domain,
                 api_root_path: "",
                 argon2_global_salt: b"example",
                 auth_signin_key_storage_name: "auth_signin_key",
+                auth_impersonation_storage_name: "auth_impersonation",
+                terms_url: "/terms",
+                turnstile_site_key: FRONTEND_DEPLOY_CONFIG_PROD.turnstile_site_key,
             }
         });
     }
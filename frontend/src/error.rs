@@ -1,7 +1,7 @@
 use futures_signals::signal::{option, Mutable, Signal, SignalExt};
 use shared::backend::result::{ApiError, AuthError};
 
-use crate::{get_text, LOCALE};
+use crate::{get_text, text_args, LOCALE};
 
 pub trait ApiErrorExt {
     fn get_text(self) -> String;
@@ -15,8 +15,29 @@ impl ApiErrorExt for ApiError {
                 AuthError::EmailAlreadyExists => ("error-api-register-email-already-exists", None),
                 AuthError::EmailNotVerified => ("error-api-register-email-unverified", None),
                 AuthError::NotAuthorized => ("error-api-not-authorized", None),
+                AuthError::Forbidden => ("error-api-forbidden", None),
                 AuthError::InvalidSignin => ("error-api-signin-invalid", None),
                 AuthError::NoUserPasswordReset => ("error-api-password-reset-no-user", None),
+                AuthError::ConfirmationRequired => ("error-api-confirmation-required", None),
+                AuthError::RecentSigninRequired => ("error-api-recent-signin-required", None),
+                AuthError::InvalidOrigin => ("error-api-invalid-origin", None),
+                AuthError::OpenIdEmailCollision => ("error-api-openid-email-collision", None),
+                AuthError::OpenIdIdentityAlreadyLinked => ("error-api-openid-identity-already-linked", None),
+                AuthError::RegistrationClosed => ("error-api-registration-closed", None),
+                AuthError::InviteRequired => ("error-api-invite-required", None),
+                AuthError::InvalidInvite => ("error-api-invalid-invite", None),
+                AuthError::EmailDomainNotAllowed => ("error-api-email-domain-not-allowed", None),
+                AuthError::EmailNotAllowed => ("error-api-email-not-allowed", None),
+                AuthError::DisposableEmail => ("error-api-disposable-email", None),
+                AuthError::ChallengeFailed => ("error-api-challenge-failed", None),
+                AuthError::TermsNotAccepted => ("error-api-terms-not-accepted", None),
+                AuthError::NoActiveOrganization => ("error-api-no-active-organization", None),
+                AuthError::OrganizationInviteWrongAccount => ("error-api-organization-invite-wrong-account", None),
+                // normally the pages redirect to the provider instead of showing this
+                AuthError::SsoRequired { .. } => ("error-api-sso-required", None),
+                // rounded up, so it never says 0
+                AuthError::InvalidVerificationCode { attempts_left } => ("error-api-invalid-verification-code", Some(text_args!("attemptsLeft" => attempts_left))),
+                AuthError::TooManyAttempts { retry_after_ms } => ("error-api-too-many-attempts", Some(text_args!("minutes" => retry_after_ms.div_ceil(1000 * 60)))),
             },
             Self::Unknown(_) => ("error-api-unknown", None),
         };
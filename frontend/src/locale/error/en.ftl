@@ -1,8 +1,34 @@
 error-api-unknown = Unknown error
 error-api-not-authorized = Not authorized 
+error-api-forbidden = You don't have permission to do that
 error-api-register-email-already-exists = Email already exists
 error-api-register-email-unverified = Email unverified
 error-api-signin-invalid = Invalid email or password
 error-api-password-reset-no-user = No user with that email
 error-api-password-reset-invalid-link = Invalid password reset link 
-error-api-openid-invalid = Unable to verify your account, please try again 
\ No newline at end of file
+error-api-confirmation-required = Please confirm with your password, or sign in again
+error-api-recent-signin-required = For your security, please sign out and sign in again to do this
+error-api-invalid-origin = This request didn't come from our site, please try again from there
+error-api-too-many-attempts = Too many attempts, please try again in { $minutes ->
+    [one] 1 minute
+   *[other] { $minutes } minutes
+}
+error-api-invalid-verification-code = { $attemptsLeft ->
+    [0] That code didn't work, please send a new one
+    [one] Wrong code, 1 attempt left
+   *[other] Wrong code, { $attemptsLeft } attempts left
+}
+error-api-openid-invalid = Unable to verify your account, please try again 
+error-api-openid-email-collision = An account with this email already exists, please enter its password to link them
+error-api-openid-identity-already-linked = This account is already linked to another user
+error-api-registration-closed = Registration is closed at the moment
+error-api-invite-required = Registration is by invite only, please use the link from your invite
+error-api-invalid-invite = This invite is invalid, expired, or has already been used
+error-api-email-domain-not-allowed = Registration isn't open to this email address, you'll need an invite
+error-api-email-not-allowed = This email address can't be used here
+error-api-disposable-email = Disposable email addresses can't be used, please use a permanent one
+error-api-challenge-failed = Please complete the check that you're not a robot, then try again
+error-api-terms-not-accepted = Our terms have changed, please accept them to continue
+error-api-no-active-organization = Please create or switch to an organization first
+error-api-organization-invite-wrong-account = This invite was sent to a different email address, please sign in with that account to accept it
+error-api-sso-required = Your organization requires signing in through its single sign-on provider
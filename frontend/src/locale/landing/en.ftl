@@ -10,24 +10,39 @@ landing-welcome-byline = Source code is on GitHub
 landing-signin-password-reset-sent = We've sent you an email with a link to reset your password
 landing-signin-form-email = Enter your email
 landing-signin-form-password = Enter your password
+landing-signin-form-remember-me = Keep me signed in
 landing-signin-button = Sign in 
 landing-create-account-button = Create account
 landing-reset-password-button = Reset password
 landing-forgot-password-button = Forgot password
-landing-signin-facebook-button = Signin with Facebook
-landing-signin-google-button = Signin with Google
+landing-magic-link-button = Email me a signin link
+landing-signin-magic-link-sent = If there's an account with that email, we've sent it a signin link
+landing-magic-link-error = This signin link is invalid or has expired, please request a new one
+landing-signin-openid-button = Signin with { $name }
 landing-signin-show-password = Show password
 landing-signin-hide-password = Hide password
 landing-signin-email-not-verified = "Email is not verified"
-landing-register-facebook-button = Register with Facebook
-landing-register-google-button = Register with Google
+landing-register-openid-button = Register with { $name }
 landing-reset-password-header = Reset password 
 landing-go-verify-email = Go verify your email 
 landing-resend-button = Resend 
+landing-recovery-code-link-button = Use a recovery code
+landing-recovery-code-instructions = Enter your email and one of your recovery codes. You'll be signed out everywhere and asked to choose a new password
+landing-recovery-code-form-code = Recovery code
+landing-recovery-code-button = Continue
+landing-recovery-code-back-button = Back to signin
+landing-verify-email-code = Or, enter the code from the email
+landing-verify-email-code-button = Verify
 landing-verify-email-error = Error verifying your email 
 landing-verify-email-success = Email verified! Redirecting to dashboard... 
 landing-signing-in = Signing in...
+landing-openid-linking = Linking your account...
+landing-openid-email-collision = An account with { $email } already exists. Enter its password to link them
 landing-agree-terms = By signing up, you agree to our terms and conditions 
+landing-terms-link = Read the terms and conditions
+landing-terms-updated = Our terms and conditions have changed. Please review and accept them to continue
+landing-terms-accept-button = I accept
+landing-terms-decline-button = Decline and sign out
 landing-register-footer = Already have an account?
 landing-signin-footer-no-account = First time here?
 landing-signin-footer-forgot-password = Trouble signing in?
@@ -36,6 +51,122 @@ landing-or = or
 # dashboard
 dashboard-user-id = User Id is: {$userId}
 dashboard-signout-button = Sign out
+dashboard-profile-header = Profile
+dashboard-profile-name = Display name
+dashboard-profile-given-name = First name
+dashboard-profile-family-name = Last name
+dashboard-profile-locale = Locale (e.g. en-US)
+dashboard-profile-picture-url = Picture url (https)
+dashboard-profile-save-button = Save profile
+dashboard-profile-saved = Profile saved
+dashboard-identities-header = Linked accounts
+dashboard-identity = { $provider }: { $email }
+dashboard-identity-unlink-button = Unlink
+dashboard-identity-link-button = Link { $name }
+dashboard-api-tokens-header = API tokens
+dashboard-api-token = { $name } ({ $scopes }), last used: { $lastUsed }
+dashboard-api-token-expires = Expires { $expiresAt }
+dashboard-api-token-never-used = never
+dashboard-api-token-revoke-button = Revoke
+dashboard-api-token-name = Token name
+dashboard-api-token-expires-in-days = Expires in days (optional)
+dashboard-api-token-create-button = Create token
+dashboard-api-token-created = Copy this token now, it won't be shown again:
+dashboard-api-token-scope-account-read = Read account
+dashboard-api-token-scope-admin = Admin
+dashboard-api-token-missing-name = The token needs a name
+dashboard-api-token-missing-scopes = Choose at least one scope
+dashboard-api-token-invalid-expires = Expiry must be a whole number of days
+dashboard-admin-button = Admin
+dashboard-admin-header = Admin
+dashboard-admin-uid = User id
+dashboard-admin-export-button = Export
+dashboard-admin-delete-button = Delete account
+dashboard-admin-grant-button = Grant { $role }
+dashboard-admin-roles-header = Roles
+dashboard-admin-role = { $uid }: { $role }
+dashboard-admin-revoke-button = Revoke
+dashboard-admin-back-button = Back
+dashboard-admin-impersonate-button = Impersonate
+dashboard-admin-impersonation-log-header = Impersonation log
+dashboard-admin-impersonation-log-entry = { $createdAt }: { $actor } → { $target } { $event } { $route }
+dashboard-invites-header = Invites
+dashboard-invite = For { $email }, used { $uses } of { $maxUses }
+dashboard-invite-any-email = anyone
+dashboard-invite-expires = Expires { $expiresAt }
+dashboard-invite-revoke-button = Revoke
+dashboard-invite-email = Email (optional)
+dashboard-invite-max-uses = Number of uses (default 1)
+dashboard-invite-expires-in-days = Expires in days (optional)
+dashboard-invite-create-button = Create invite
+dashboard-invite-invalid-max-uses = Number of uses must be a whole number
+dashboard-invite-invalid-expires = Expiry must be a whole number of days
+dashboard-security-button = Security activity
+dashboard-security-header = Recent security activity
+dashboard-security-event = { $createdAt }: { $kind } { $detail }
+dashboard-security-event-client = { $ip }, { $userAgent }
+dashboard-security-back-button = Back
+dashboard-security-kind-register = Account created
+dashboard-security-kind-signin-success = Signed in
+dashboard-security-kind-signin-failure = Failed signin
+dashboard-security-kind-signout = Signed out
+dashboard-security-kind-password-reset-requested = Password reset requested
+dashboard-security-kind-password-reset-completed = Password changed
+dashboard-security-kind-email-verified = Email verified
+dashboard-security-kind-openid-linked = Account linked
+dashboard-security-kind-openid-unlinked = Account unlinked
+dashboard-security-kind-session-revoked = Signed out elsewhere
+dashboard-security-kind-recovery-codes-generated = Recovery codes generated
+dashboard-security-kind-recovery-code-used = Recovery code used
+dashboard-recovery-codes-button = Recovery codes
+dashboard-recovery-codes-header = Recovery codes
+dashboard-recovery-codes-none = You don't have any recovery codes yet
+dashboard-recovery-codes-remaining = { $remaining ->
+    [one] 1 unused code left
+   *[other] { $remaining } unused codes left
+}, generated { $createdAt }
+dashboard-recovery-codes-explainer = If you lose access to your email, a recovery code lets you set a new password. Each code works once. Generating new codes replaces the old ones
+dashboard-recovery-codes-generate-button = Generate new codes
+dashboard-recovery-codes-save-now = Save these codes somewhere safe, they won't be shown again
+dashboard-recovery-codes-download = Download
+dashboard-recovery-codes-done-button = I've saved them
+dashboard-recovery-codes-back-button = Back
+dashboard-organizations-button = Organizations
+dashboard-organizations-header = Organizations
+dashboard-organizations-back-button = Back
+dashboard-organization = { $name } ({ $role })
+dashboard-organization-switch-button = Switch to
+dashboard-organization-unset-button = Switch away
+dashboard-organization-name = Organization name
+dashboard-organization-create-button = Create organization
+dashboard-organization-members-header = Members of { $name }
+dashboard-organization-member = { $email } ({ $role })
+dashboard-organization-remove-button = Remove
+dashboard-organization-leave-button = Leave
+dashboard-organization-invite-email = Email to invite
+dashboard-organization-invite-button = Invite as { $role }
+dashboard-organization-invites-header = Pending invites
+dashboard-organization-invite = { $email } as { $role }, expires { $expiresAt }
+dashboard-organization-invite-revoke-button = Revoke
+dashboard-organization-role-owner = owner
+dashboard-organization-role-admin = admin
+dashboard-organization-role-member = member
+dashboard-organization-sso-domains-header = Single sign-on domains
+dashboard-organization-sso-domain = Email domain
+dashboard-organization-sso-domain-add-button = Require { $provider }
+dashboard-organization-sso-domain-pending = { $domain } (through { $provider }), not verified yet
+dashboard-organization-sso-domain-verified = { $domain } (through { $provider }), verified { $verifiedAt }
+dashboard-organization-sso-domain-txt-record = Add a TXT record named { $name } with the value { $value }, then verify
+dashboard-organization-sso-domain-verify-button = Verify
+dashboard-organization-sso-domain-remove-button = Remove
+dashboard-organization-invite-page-text = You're invited to join { $name } as { $role }
+dashboard-organization-invite-page-accept-button = Accept
+dashboard-organization-invite-page-decline-button = Decline
+dashboard-organization-invite-page-back-button = Back
+
+# impersonation
+impersonation-banner = You're viewing the app as { $uid }
+impersonation-stop-button = Stop impersonating
 
 # general
 button-submit = Submit
\ No newline at end of file
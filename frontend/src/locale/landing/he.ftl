@@ -10,23 +10,38 @@ landing-welcome-byline = קוד המקור נמצא ב-GitHub
 landing-signin-password-reset-sent = שלחנו אליך דוא"ל עם קישור לאיפוס הסיסמה שלך
 landing-signin-form-email = הזן את הדוא"ל שלך
 landing-signin-form-password = הזן את הסיסמה שלך
+landing-signin-form-remember-me = השאר אותי מחובר
 landing-signin-button = התחברות
 landing-register-button = הרשמה
 landing-forgot-password-button = שכחתי סיסמה
-landing-signin-facebook-button = התחבר עם פייסבוק
-landing-signin-google-button = התחבר עם Google
+landing-signin-openid-button = התחבר עם { $name }
 landing-signin-show-password = הצג סיסמה
 landing-signin-hide-password = הסתר סיסמה
 landing-signin-email-not-verified = "הדוא"ל אינו מאומת"
-landing-register-facebook-button = הרשם עם פייסבוק
-landing-register-google-button = הרשם עם Google
+landing-register-openid-button = הרשם עם { $name }
 landing-reset-password-header = איפוס סיסמה
 landing-go-verify-email = לך ואמת את הדוא"ל שלך
 landing-resend-button = שלח שוב
+landing-recovery-code-link-button = השתמש בקוד שחזור
+landing-recovery-code-instructions = הזן את הדוא"ל שלך ואחד מקודי השחזור שלך. תנותק מכל המכשירים ותתבקש לבחור סיסמה חדשה
+landing-recovery-code-form-code = קוד שחזור
+landing-recovery-code-button = המשך
+landing-recovery-code-back-button = חזרה להתחברות
+landing-verify-email-code = או, הזן את הקוד מהדוא"ל
+landing-verify-email-code-button = אמת
 landing-verify-email-error = שגיאה באימות הדוא"ל שלך
 landing-verify-email-success = דוא"ל מאומת! מעביר ללוח מחוונים...
 landing-signing-in = מתחבר...
+landing-magic-link-button = שלחו לי קישור התחברות
+landing-signin-magic-link-sent = אם קיים חשבון עם הדוא"ל הזה, שלחנו אליו קישור התחברות
+landing-magic-link-error = קישור ההתחברות אינו תקין או שפג תוקפו, אנא בקש קישור חדש
+landing-openid-linking = מקשר את החשבון...
+landing-openid-email-collision = כבר קיים חשבון עם { $email }. הזן את הסיסמה שלו כדי לקשר ביניהם
 landing-agree-terms = בהרשמה, אתה מסכים לתנאים ולהגבלות שלנו
+landing-terms-link = קרא את התנאים וההגבלות
+landing-terms-updated = התנאים וההגבלות שלנו השתנו. אנא עיין בהם ואשר אותם כדי להמשיך
+landing-terms-accept-button = אני מסכים
+landing-terms-decline-button = סרב והתנתק
 landing-register-footer = יש לך כבר חשבון?
 landing-signin-footer = אין לך חשבון?
 landing-signin-footer-no-account = פעם ראשונה כאן?
@@ -36,6 +51,122 @@ landing-or = או
 # dashboard
 dashboard-user-id = זיהוי המשתמש הוא: {$userId}
 dashboard-signout-button = התנתק
+dashboard-profile-header = פרופיל
+dashboard-profile-name = שם תצוגה
+dashboard-profile-given-name = שם פרטי
+dashboard-profile-family-name = שם משפחה
+dashboard-profile-locale = שפה ואזור (לדוגמה he-IL)
+dashboard-profile-picture-url = כתובת תמונה (https)
+dashboard-profile-save-button = שמור פרופיל
+dashboard-profile-saved = הפרופיל נשמר
+dashboard-identities-header = חשבונות מקושרים
+dashboard-identity = { $provider }: { $email }
+dashboard-identity-unlink-button = בטל קישור
+dashboard-identity-link-button = קשר את { $name }
+dashboard-api-tokens-header = אסימוני API
+dashboard-api-token = { $name } ({ $scopes }), שימוש אחרון: { $lastUsed }
+dashboard-api-token-expires = פג תוקף ב-{ $expiresAt }
+dashboard-api-token-never-used = אף פעם
+dashboard-api-token-revoke-button = בטל
+dashboard-api-token-name = שם האסימון
+dashboard-api-token-expires-in-days = תוקף בימים (לא חובה)
+dashboard-api-token-create-button = צור אסימון
+dashboard-api-token-created = העתק את האסימון עכשיו, הוא לא יוצג שוב:
+dashboard-api-token-scope-account-read = קריאת החשבון
+dashboard-api-token-scope-admin = ניהול
+dashboard-api-token-missing-name = לאסימון חייב להיות שם
+dashboard-api-token-missing-scopes = בחר לפחות הרשאה אחת
+dashboard-api-token-invalid-expires = התוקף חייב להיות מספר שלם של ימים
+dashboard-admin-button = ניהול
+dashboard-admin-header = ניהול
+dashboard-admin-uid = זיהוי משתמש
+dashboard-admin-export-button = ייצוא
+dashboard-admin-delete-button = מחק חשבון
+dashboard-admin-grant-button = הענק { $role }
+dashboard-admin-roles-header = תפקידים
+dashboard-admin-role = { $uid }: { $role }
+dashboard-admin-revoke-button = בטל
+dashboard-admin-back-button = חזרה
+dashboard-admin-impersonate-button = התחזה
+dashboard-admin-impersonation-log-header = יומן התחזות
+dashboard-admin-impersonation-log-entry = { $createdAt }: { $actor } → { $target } { $event } { $route }
+dashboard-invites-header = הזמנות
+dashboard-invite = עבור { $email }, נוצלה { $uses } מתוך { $maxUses }
+dashboard-invite-any-email = כל אחד
+dashboard-invite-expires = פג תוקף ב-{ $expiresAt }
+dashboard-invite-revoke-button = בטל
+dashboard-invite-email = אימייל (לא חובה)
+dashboard-invite-max-uses = מספר שימושים (ברירת מחדל 1)
+dashboard-invite-expires-in-days = תוקף בימים (לא חובה)
+dashboard-invite-create-button = צור הזמנה
+dashboard-invite-invalid-max-uses = מספר השימושים חייב להיות מספר שלם
+dashboard-invite-invalid-expires = התוקף חייב להיות מספר שלם של ימים
+dashboard-security-button = פעילות אבטחה
+dashboard-security-header = פעילות אבטחה אחרונה
+dashboard-security-event = { $createdAt }: { $kind } { $detail }
+dashboard-security-event-client = { $ip }, { $userAgent }
+dashboard-security-back-button = חזרה
+dashboard-security-kind-register = החשבון נוצר
+dashboard-security-kind-signin-success = התחברות
+dashboard-security-kind-signin-failure = התחברות שנכשלה
+dashboard-security-kind-signout = התנתקות
+dashboard-security-kind-password-reset-requested = התבקש איפוס סיסמה
+dashboard-security-kind-password-reset-completed = הסיסמה שונתה
+dashboard-security-kind-email-verified = האימייל אומת
+dashboard-security-kind-openid-linked = חשבון קושר
+dashboard-security-kind-openid-unlinked = קישור חשבון בוטל
+dashboard-security-kind-session-revoked = התנתקות ממכשירים אחרים
+dashboard-security-kind-recovery-codes-generated = נוצרו קודי שחזור
+dashboard-security-kind-recovery-code-used = נעשה שימוש בקוד שחזור
+dashboard-recovery-codes-button = קודי שחזור
+dashboard-recovery-codes-header = קודי שחזור
+dashboard-recovery-codes-none = עדיין אין לך קודי שחזור
+dashboard-recovery-codes-remaining = { $remaining ->
+    [one] נותר קוד אחד שלא נוצל
+   *[other] נותרו { $remaining } קודים שלא נוצלו
+}, נוצרו ב-{ $createdAt }
+dashboard-recovery-codes-explainer = אם תאבד גישה לדוא"ל שלך, קוד שחזור יאפשר לך לבחור סיסמה חדשה. כל קוד עובד פעם אחת. יצירת קודים חדשים מחליפה את הישנים
+dashboard-recovery-codes-generate-button = צור קודים חדשים
+dashboard-recovery-codes-save-now = שמור את הקודים האלה במקום בטוח, הם לא יוצגו שוב
+dashboard-recovery-codes-download = הורד
+dashboard-recovery-codes-done-button = שמרתי אותם
+dashboard-recovery-codes-back-button = חזור
+dashboard-organizations-button = ארגונים
+dashboard-organizations-header = ארגונים
+dashboard-organizations-back-button = חזור
+dashboard-organization = { $name } ({ $role })
+dashboard-organization-switch-button = עבור לארגון
+dashboard-organization-unset-button = צא מהארגון הפעיל
+dashboard-organization-name = שם הארגון
+dashboard-organization-create-button = צור ארגון
+dashboard-organization-members-header = חברי { $name }
+dashboard-organization-member = { $email } ({ $role })
+dashboard-organization-remove-button = הסר
+dashboard-organization-leave-button = עזוב
+dashboard-organization-invite-email = מייל להזמנה
+dashboard-organization-invite-button = הזמן כ{ $role }
+dashboard-organization-invites-header = הזמנות ממתינות
+dashboard-organization-invite = { $email } כ{ $role }, בתוקף עד { $expiresAt }
+dashboard-organization-invite-revoke-button = בטל
+dashboard-organization-role-owner = בעלים
+dashboard-organization-role-admin = מנהל
+dashboard-organization-role-member = חבר
+dashboard-organization-sso-domains-header = דומיינים עם כניסה יחידה
+dashboard-organization-sso-domain = דומיין אימייל
+dashboard-organization-sso-domain-add-button = לחייב { $provider }
+dashboard-organization-sso-domain-pending = { $domain } (דרך { $provider }), עדיין לא אומת
+dashboard-organization-sso-domain-verified = { $domain } (דרך { $provider }), אומת { $verifiedAt }
+dashboard-organization-sso-domain-txt-record = יש להוסיף רשומת TXT בשם { $name } עם הערך { $value }, ואז לאמת
+dashboard-organization-sso-domain-verify-button = אימות
+dashboard-organization-sso-domain-remove-button = הסרה
+dashboard-organization-invite-page-text = הוזמנת להצטרף ל-{ $name } כ{ $role }
+dashboard-organization-invite-page-accept-button = אשר
+dashboard-organization-invite-page-decline-button = דחה
+dashboard-organization-invite-page-back-button = חזור
+
+# impersonation
+impersonation-banner = אתה צופה באפליקציה בתור { $uid }
+impersonation-stop-button = הפסק התחזות
 
 # general
 button-submit = שלח
@@ -1,7 +1,26 @@
+mod admin;
+mod api_tokens;
+mod identities;
+mod invites;
+mod organizations;
+mod organization_invite;
+mod profile;
+mod recovery_codes;
+mod security;
+
 use std::sync::Mutex;
 
 use wasm_bindgen_futures::spawn_local;
 
+use admin::AdminPage;
+use api_tokens::ApiTokens;
+use identities::Identities;
+use organizations::OrganizationsPage;
+use organization_invite::OrganizationInvitePage;
+use profile::Profile;
+use recovery_codes::RecoveryCodesPage;
+use security::SecurityPage;
+use shared::user::Permission;
 use crate::{atoms::buttons::Squareish1Button, prelude::*};
 
 pub struct DashboardPage {
@@ -15,6 +34,7 @@ impl DashboardPage {
     }
 
     pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
         html!("div", {
             .style("display", "flex")
             .style("flex-direction", "column")
@@ -27,26 +47,86 @@ impl DashboardPage {
                 .style("flex-direction", "column")
                 .style("align-items", "center")
                 .style("gap", "1rem")
-                .child(html!("div", {
-                    .class(&*TEXT_SIZE_LG)
-                    .text(&get_text!("dashboard-user-id", {
-                        "userId" => AUTH.try_clone_uid().map(|uid| uid.to_string()).unwrap_or_else(|| "none".to_string())
-                    }))
+                .child_signal(Route::signal().map(clone!(state => move |route| {
+                    match route {
+                        Route::Dashboard(Dashboard::Browse) => Some(state.render_browse()),
+                        Route::Dashboard(Dashboard::Admin) => Some(AdminPage::new().render()),
+                        Route::Dashboard(Dashboard::Security) => Some(SecurityPage::new().render()),
+                        Route::Dashboard(Dashboard::RecoveryCodes) => Some(RecoveryCodesPage::new().render()),
+                        Route::Dashboard(Dashboard::Organizations) => Some(OrganizationsPage::new().render()),
+                        Route::Dashboard(Dashboard::OrganizationInvite { oob_token_id, oob_token_key }) => Some(OrganizationInvitePage::new(oob_token_id, oob_token_key).render()),
+                        _ => None
+                    }
+                })))
+            }))
+            .child(LanguageSelector::render())
+        })
+    }
+
+    fn render_browse(&self) -> Dom {
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .text(&get_text!("dashboard-user-id", {
+                    "userId" => AUTH.try_clone_uid().map(|uid| uid.to_string()).unwrap_or_else(|| "none".to_string())
                 }))
-                .child(Squareish1Button::new().render(
+            }))
+            .apply_if(AUTH.has_permission(Permission::AccountsRead), |dom| {
+                dom.child(Squareish1Button::new().render(
+                    get_text!("dashboard-admin-button"),
+                    || {
+                        Route::Dashboard(Dashboard::Admin).go_to_url();
+                    }
+                ))
+            })
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-security-button"),
+                || {
+                    Route::Dashboard(Dashboard::Security).go_to_url();
+                }
+            ))
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-recovery-codes-button"),
+                || {
+                    Route::Dashboard(Dashboard::RecoveryCodes).go_to_url();
+                }
+            ))
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-organizations-button"),
+                || {
+                    Route::Dashboard(Dashboard::Organizations).go_to_url();
+                }
+            ))
+            .child(Profile::new().render())
+            .child(Identities::new().render())
+            .child(ApiTokens::new().render())
+            // while impersonating, the banner's stop button is used instead
+            .apply_if(AUTH.impersonation().is_none(), |dom| {
+                dom.child(Squareish1Button::new().render(
                     get_text!("dashboard-signout-button"),
                     || {
                         spawn_local(async {
-                            if let Err(err) = AUTH.signout().await { 
-                                log::error!("signout failed");
-                                log::error!("{:?}", err);
+                            match AUTH.signout().await {
+                                // the provider redirects back to the welcome page
+                                Ok(Some(logout_url)) => {
+                                    web_sys::window().unwrap_ext().location().assign(&logout_url).unwrap_ext();
+                                    return;
+                                },
+                                Ok(None) => {},
+                                Err(err) => {
+                                    log::error!("signout failed");
+                                    log::error!("{:?}", err);
+                                }
                             }
                             Route::Landing(Landing::Welcome).go_to_url();
                         });
                     }
                 ))
-            }))
-            .child(LanguageSelector::render())
+            })
         })
     }
 }
\ No newline at end of file
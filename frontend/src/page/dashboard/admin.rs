@@ -0,0 +1,232 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::{api::admin::{AdminAccountDelete, AdminAccountExport, AdminAccountRequest, AdminImpersonationLog, AdminImpersonationLogEntry, AdminRoleGrant, AdminRoleInfo, AdminRoleList, AdminRoleRequest, AdminRoleRevoke}, backend::result::ApiError, user::{Permission, Role, UserId}};
+use super::invites::Invites;
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton, Squareish1Button}, input::{TextInput, TextInputKind}}, prelude::*};
+
+// The admin area, what's shown depends on the user's permissions
+// support staff can only look accounts up, admins can also delete them, manage roles, and impersonate
+pub(super) struct AdminPage {
+    pub uid: TextInput,
+    pub export: Mutable<Option<String>>,
+    pub roles: MutableVec<AdminRoleInfo>,
+    pub impersonation_log: MutableVec<AdminImpersonationLogEntry>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl AdminPage {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            uid: TextInput::new(TextInputKind::Any),
+            export: Mutable::new(None),
+            roles: MutableVec::new(),
+            impersonation_log: MutableVec::new(),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-admin-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .child(state.uid.render(Some(&get_text!("dashboard-admin-uid"))))
+            .child(html!("div", {
+                .style("display", "flex")
+                .style("gap", "1rem")
+                .child(state.render_action_button(get_text!("dashboard-admin-export-button"), AdminAction::Export))
+                .apply_if(AUTH.has_permission(Permission::AccountsWrite), |dom| {
+                    dom.child(state.render_action_button(get_text!("dashboard-admin-delete-button"), AdminAction::Delete))
+                })
+                .apply_if(AUTH.has_permission(Permission::Impersonate), |dom| {
+                    dom.child(state.render_action_button(get_text!("dashboard-admin-impersonate-button"), AdminAction::Impersonate))
+                })
+                .apply_if(AUTH.has_permission(Permission::RolesManage), |dom| {
+                    dom.children(Role::all().into_iter().map(|role| {
+                        state.render_action_button(get_text!("dashboard-admin-grant-button", {"role" => role.as_str().to_string()}), AdminAction::Grant(role))
+                    }))
+                })
+            }))
+            .child_signal(state.export.signal_cloned().map(|export| {
+                export.map(|export| {
+                    html!("pre", {
+                        .style("max-width", "100%")
+                        .style("overflow", "auto")
+                        .text(&export)
+                    })
+                })
+            }))
+            .apply_if(AUTH.has_permission(Permission::RolesManage), |dom| {
+                dom
+                    .future(clone!(state => async move {
+                        state.reload_roles().await;
+                    }))
+                    .child(html!("div", {
+                        .class(&*TEXT_SIZE_LG)
+                        .class(&*TEXT_WEIGHT_BOLD)
+                        .text(&get_text!("dashboard-admin-roles-header"))
+                    }))
+                    .children_signal_vec(state.roles.signal_vec_cloned().map(clone!(state => move |info| {
+                        state.render_role(info)
+                    })))
+            })
+            .apply_if(AUTH.has_permission(Permission::Impersonate), |dom| {
+                dom
+                    .future(clone!(state => async move {
+                        state.reload_impersonation_log().await;
+                    }))
+                    .child(html!("div", {
+                        .class(&*TEXT_SIZE_LG)
+                        .class(&*TEXT_WEIGHT_BOLD)
+                        .text(&get_text!("dashboard-admin-impersonation-log-header"))
+                    }))
+                    .children_signal_vec(state.impersonation_log.signal_vec_cloned().map(|entry| {
+                        html!("div", {
+                            .text(&get_text!("dashboard-admin-impersonation-log-entry", {
+                                "createdAt" => entry.created_at,
+                                "actor" => entry.actor_uid.to_string(),
+                                "target" => entry.target_uid.to_string(),
+                                "event" => entry.event.as_str().to_string(),
+                                "route" => entry.route.unwrap_or_default()
+                            }))
+                        })
+                    }))
+            })
+            .apply_if(AUTH.has_permission(Permission::InvitesManage), |dom| {
+                dom.child(Invites::new().render())
+            })
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-admin-back-button"),
+                || {
+                    Route::Dashboard(Dashboard::Browse).go_to_url();
+                }
+            ))
+        })
+    }
+
+    async fn reload_impersonation_log(&self) {
+        match AdminImpersonationLog::fetch().await {
+            Ok(res) => {
+                self.impersonation_log.lock_mut().replace_cloned(res.entries);
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+    }
+
+    async fn reload_roles(&self) {
+        match AdminRoleList::fetch().await {
+            Ok(res) => {
+                self.roles.lock_mut().replace_cloned(res.roles);
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+    }
+
+    fn render_action_button(self: &Arc<Self>, text: String, action: AdminAction) -> Dom {
+        let state = self;
+
+        OutlineButton::new(action == AdminAction::Delete).set_size(ButtonSize::Sm).render(
+            None,
+            text,
+            clone!(state => move || {
+                state.error.clear();
+                state.export.set(None);
+
+                let uid = match state.uid.value.get_cloned().and_then(|uid| UserId::try_from(uid.trim()).ok()) {
+                    Some(uid) => uid,
+                    None => {
+                        state.error.set(ApiError::Unknown("invalid uid".to_string()));
+                        return;
+                    }
+                };
+
+                state.loader.load(clone!(state => async move {
+                    let res = match action {
+                        AdminAction::Export => AdminAccountExport::fetch(AdminAccountRequest { uid }).await.map(|export| {
+                            state.export.set(serde_json::to_string_pretty(&export).ok());
+                        }),
+                        AdminAction::Delete => AdminAccountDelete::fetch(AdminAccountRequest { uid }).await,
+                        AdminAction::Grant(role) => AdminRoleGrant::fetch(AdminRoleRequest { uid, role }).await,
+                        AdminAction::Impersonate => AUTH.start_impersonation(uid).await.map(|_| {
+                            // reloaded so that everything is fetched as the user
+                            Route::Dashboard(Dashboard::Browse).hard_redirect();
+                        }),
+                    };
+
+                    match res {
+                        Ok(_) => {
+                            if let AdminAction::Grant(_) = action {
+                                state.reload_roles().await;
+                            }
+                        },
+                        Err(e) => {
+                            state.error.set(e);
+                        }
+                    }
+                }));
+            })
+        )
+    }
+
+    fn render_role(self: &Arc<Self>, info: AdminRoleInfo) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .text(&get_text!("dashboard-admin-role", {
+                    "uid" => info.uid.to_string(),
+                    "role" => info.role.as_str().to_string()
+                }))
+            }))
+            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-admin-revoke-button"),
+                clone!(state => move || {
+                    let req = AdminRoleRequest {
+                        uid: info.uid.clone(),
+                        role: info.role,
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match AdminRoleRevoke::fetch(req).await {
+                            Ok(_) => {
+                                state.reload_roles().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum AdminAction {
+    Export,
+    Delete,
+    Grant(Role),
+    Impersonate,
+}
@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+
+use dominator_helpers::futures::AsyncLoader;
+use shared::{api::api_token::{ApiTokenCreate, ApiTokenCreateRequest, ApiTokenInfo, ApiTokenList, ApiTokenRevoke, ApiTokenRevokeRequest}, backend::route::ApiTokenScope};
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton}, input::{TextInput, TextInputKind}}, prelude::*};
+
+// Personal api tokens, for scripts and other non-browser clients
+// a new token is only shown once, right after it's created
+pub(super) struct ApiTokens {
+    pub tokens: MutableVec<ApiTokenInfo>,
+    pub name: TextInput,
+    pub expires_in_days: TextInput,
+    pub scopes: Mutex<HashSet<ApiTokenScope>>,
+    pub created_token: Mutable<Option<String>>,
+    // checked before sending, the backend errors for these aren't user-facing
+    pub form_error: Mutable<Option<String>>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl ApiTokens {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            tokens: MutableVec::new(),
+            name: TextInput::new(TextInputKind::Any),
+            expires_in_days: TextInput::new(TextInputKind::Any),
+            scopes: Mutex::new(HashSet::new()),
+            created_token: Mutable::new(None),
+            form_error: Mutable::new(None),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                state.reload().await;
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-api-tokens-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.form_error.signal_cloned().map(|err| err.unwrap_or_default()))
+            }))
+            .child_signal(state.created_token.signal_cloned().map(|token| {
+                token.map(|token| {
+                    html!("div", {
+                        .style("display", "flex")
+                        .style("flex-direction", "column")
+                        .style("align-items", "center")
+                        .child(html!("div", {
+                            .text(&get_text!("dashboard-api-token-created"))
+                        }))
+                        .child(html!("code", {
+                            .style("user-select", "all")
+                            .text(&token)
+                        }))
+                    })
+                })
+            }))
+            .children_signal_vec(state.tokens.signal_vec_cloned().map(clone!(state => move |token| {
+                state.render_token(token)
+            })))
+            .child(state.render_create())
+        })
+    }
+
+    async fn reload(&self) {
+        match ApiTokenList::fetch().await {
+            Ok(res) => {
+                self.tokens.lock_mut().replace_cloned(res.tokens);
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+    }
+
+    fn render_create(self: &Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(state.name.render(Some(&get_text!("dashboard-api-token-name"))))
+            .child(state.expires_in_days.render(Some(&get_text!("dashboard-api-token-expires-in-days"))))
+            .child(html!("div", {
+                .style("display", "flex")
+                .style("gap", "1rem")
+                .children(ApiTokenScope::all().into_iter().map(|scope| {
+                    html!("label", {
+                        .child(html!("input" => web_sys::HtmlInputElement, {
+                            .attr("type", "checkbox")
+                            .with_node!(elem => {
+                                .event(clone!(state => move |_:events::Change| {
+                                    let mut scopes = state.scopes.lock().unwrap();
+                                    if elem.checked() {
+                                        scopes.insert(scope);
+                                    } else {
+                                        scopes.remove(&scope);
+                                    }
+                                }))
+                            })
+                        }))
+                        .text(&scope_text(scope))
+                    })
+                }))
+            }))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-api-token-create-button"),
+                clone!(state => move || {
+                    state.error.clear();
+                    state.form_error.set(None);
+
+                    let name = state.name.value.get_cloned().unwrap_or_default();
+                    let scopes = state.scopes.lock().unwrap().iter().copied().collect::<Vec<_>>();
+                    let expires_in_days = match state.expires_in_days.value.get_cloned() {
+                        None => None,
+                        Some(days) => match days.trim().parse::<u32>() {
+                            Ok(days) if days > 0 => Some(days),
+                            _ => {
+                                state.form_error.set(Some(get_text!("dashboard-api-token-invalid-expires")));
+                                return;
+                            }
+                        }
+                    };
+                    if name.trim().is_empty() {
+                        state.form_error.set(Some(get_text!("dashboard-api-token-missing-name")));
+                        return;
+                    }
+                    if scopes.is_empty() {
+                        state.form_error.set(Some(get_text!("dashboard-api-token-missing-scopes")));
+                        return;
+                    }
+
+                    let req = ApiTokenCreateRequest {
+                        name,
+                        scopes,
+                        expires_in_days,
+                    };
+                    state.loader.load(clone!(state => async move {
+                        match ApiTokenCreate::fetch(req).await {
+                            Ok(res) => {
+                                state.created_token.set(Some(res.token));
+                                state.name.value.set(None);
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    fn render_token(self: &Arc<Self>, token: ApiTokenInfo) -> Dom {
+        let state = self;
+
+        let scopes = token.scopes.iter().map(|scope| scope_text(*scope)).collect::<Vec<_>>().join(", ");
+        let last_used = token.last_used_at.map(format_ms).unwrap_or_else(|| get_text!("dashboard-api-token-never-used"));
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .text(&get_text!("dashboard-api-token", {
+                    "name" => token.name.clone(),
+                    "scopes" => scopes,
+                    "lastUsed" => last_used
+                }))
+            }))
+            .apply_if(token.expires_at.is_some(), |dom| {
+                dom.child(html!("div", {
+                    .text(&get_text!("dashboard-api-token-expires", {
+                        "expiresAt" => format_ms(token.expires_at.unwrap_ext())
+                    }))
+                }))
+            })
+            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-api-token-revoke-button"),
+                clone!(state => move || {
+                    let req = ApiTokenRevokeRequest {
+                        id: token.id.clone(),
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match ApiTokenRevoke::fetch(req).await {
+                            Ok(_) => {
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+}
+
+fn scope_text(scope: ApiTokenScope) -> String {
+    match scope {
+        ApiTokenScope::AccountRead => get_text!("dashboard-api-token-scope-account-read"),
+        ApiTokenScope::Admin => get_text!("dashboard-api-token-scope-admin"),
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(ms as f64)).to_locale_string("default", &JsValue::UNDEFINED).into()
+}
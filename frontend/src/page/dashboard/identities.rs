@@ -0,0 +1,137 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::api::auth::{AuthIdentities, AuthIdentity, AuthIdentityUnlink, AuthIdentityUnlinkRequest, AuthOpenIdConnectRequest, AuthOpenIdLinkConnect, AuthOpenIdProviderInfo, AuthOpenIdProviders};
+use crate::{atoms::buttons::{ButtonSize, OutlineButton}, prelude::*};
+
+// The external identities linked to this account
+// with buttons to unlink them, or to link any other configured provider
+pub(super) struct Identities {
+    pub identities: MutableVec<AuthIdentity>,
+    pub providers: MutableVec<AuthOpenIdProviderInfo>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl Identities {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            identities: MutableVec::new(),
+            providers: MutableVec::new(),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                state.reload().await;
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-identities-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .children_signal_vec(state.identities.signal_vec_cloned().map(clone!(state => move |identity| {
+                state.render_identity(identity)
+            })))
+            .child(html!("div", {
+                .style("display", "flex")
+                .style("gap", "1rem")
+                .children_signal_vec(state.providers.signal_vec_cloned().map(clone!(state => move |info| {
+                    state.render_link_button(info)
+                })))
+            }))
+        })
+    }
+
+    async fn reload(&self) {
+        match AuthIdentities::fetch().await {
+            Ok(res) => {
+                self.identities.lock_mut().replace_cloned(res.identities);
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+
+        match AuthOpenIdProviders::fetch().await {
+            Ok(res) => {
+                self.providers.lock_mut().replace_cloned(res.providers);
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+    }
+
+    fn render_identity(self: &Arc<Self>, identity: AuthIdentity) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .text(&get_text!("dashboard-identity", {
+                    "provider" => identity.provider.as_str().to_string(),
+                    "email" => identity.email.clone()
+                }))
+            }))
+            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-identity-unlink-button"),
+                clone!(state => move || {
+                    let req = AuthIdentityUnlinkRequest {
+                        provider: identity.provider.clone(),
+                        subject: identity.subject.clone(),
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match AuthIdentityUnlink::fetch(req).await {
+                            Ok(_) => {
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    fn render_link_button(self: &Arc<Self>, info: AuthOpenIdProviderInfo) -> Dom {
+        let state = self;
+        let provider = info.provider;
+
+        OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+            None,
+            get_text!("dashboard-identity-link-button", {"name" => info.name}),
+            clone!(state => move || {
+                state.error.clear();
+                state.loader.load(clone!(state, provider => async move {
+                    match AuthOpenIdLinkConnect::fetch(AuthOpenIdConnectRequest{provider}).await {
+                        Ok(res) => {
+                            // comes back to the openid finalize page, which completes the link
+                            web_sys::window().unwrap_ext().location().replace(&res.url).unwrap_ext();
+                        },
+                        Err(e) => {
+                            state.error.set(e);
+                        }
+                    }
+                }));
+            })
+        )
+    }
+}
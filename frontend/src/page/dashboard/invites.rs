@@ -0,0 +1,184 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::api::invite::{InviteCreate, InviteCreateRequest, InviteInfo, InviteList, InviteRevoke, InviteRevokeRequest};
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton}, input::{TextInput, TextInputKind}}, prelude::*};
+
+// Registration invites, for when registration is invite-only (or limited to some domains)
+// the link is what gets sent to whoever is invited
+pub(super) struct Invites {
+    pub invites: MutableVec<InviteInfo>,
+    pub email: TextInput,
+    pub max_uses: TextInput,
+    pub expires_in_days: TextInput,
+    // checked before sending, the backend errors for these aren't user-facing
+    pub form_error: Mutable<Option<String>>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl Invites {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            invites: MutableVec::new(),
+            email: TextInput::new(TextInputKind::Email),
+            max_uses: TextInput::new(TextInputKind::Any),
+            expires_in_days: TextInput::new(TextInputKind::Any),
+            form_error: Mutable::new(None),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                state.reload().await;
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-invites-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.form_error.signal_cloned().map(|err| err.unwrap_or_default()))
+            }))
+            .children_signal_vec(state.invites.signal_vec_cloned().map(clone!(state => move |invite| {
+                state.render_invite(invite)
+            })))
+            .child(state.render_create())
+        })
+    }
+
+    async fn reload(&self) {
+        match InviteList::fetch().await {
+            Ok(res) => {
+                self.invites.lock_mut().replace_cloned(res.invites);
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+    }
+
+    fn render_create(self: &Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(state.email.render(Some(&get_text!("dashboard-invite-email"))))
+            .child(state.max_uses.render(Some(&get_text!("dashboard-invite-max-uses"))))
+            .child(state.expires_in_days.render(Some(&get_text!("dashboard-invite-expires-in-days"))))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-invite-create-button"),
+                clone!(state => move || {
+                    state.error.clear();
+                    state.form_error.set(None);
+
+                    let max_uses = match state.max_uses.value.get_cloned() {
+                        None => 1,
+                        Some(uses) => match uses.trim().parse::<u32>() {
+                            Ok(uses) if uses > 0 => uses,
+                            _ => {
+                                state.form_error.set(Some(get_text!("dashboard-invite-invalid-max-uses")));
+                                return;
+                            }
+                        }
+                    };
+                    let expires_in_days = match state.expires_in_days.value.get_cloned() {
+                        None => None,
+                        Some(days) => match days.trim().parse::<u32>() {
+                            Ok(days) if days > 0 => Some(days),
+                            _ => {
+                                state.form_error.set(Some(get_text!("dashboard-invite-invalid-expires")));
+                                return;
+                            }
+                        }
+                    };
+
+                    let req = InviteCreateRequest {
+                        email: state.email.value.get_cloned(),
+                        max_uses,
+                        expires_in_days,
+                    };
+                    state.loader.load(clone!(state => async move {
+                        match InviteCreate::fetch(req).await {
+                            Ok(_) => {
+                                state.email.value.set(None);
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    fn render_invite(self: &Arc<Self>, invite: InviteInfo) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("code", {
+                .style("user-select", "all")
+                .text(&invite.url)
+            }))
+            .child(html!("div", {
+                .text(&get_text!("dashboard-invite", {
+                    "email" => invite.email.clone().unwrap_or_else(|| get_text!("dashboard-invite-any-email")),
+                    "uses" => invite.uses.to_string(),
+                    "maxUses" => invite.max_uses.to_string()
+                }))
+            }))
+            .apply_if(invite.expires_at.is_some(), |dom| {
+                dom.child(html!("div", {
+                    .text(&get_text!("dashboard-invite-expires", {
+                        "expiresAt" => format_ms(invite.expires_at.unwrap_ext())
+                    }))
+                }))
+            })
+            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-invite-revoke-button"),
+                clone!(state => move || {
+                    let req = InviteRevokeRequest {
+                        code: invite.code.clone(),
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match InviteRevoke::fetch(req).await {
+                            Ok(_) => {
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(ms as f64)).to_locale_string("default", &JsValue::UNDEFINED).into()
+}
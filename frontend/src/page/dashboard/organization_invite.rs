@@ -0,0 +1,123 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::api::organization::{OrganizationInviteAccept, OrganizationInviteCheck, OrganizationInviteCheckResponse, OrganizationInviteDecline, OrganizationInviteTokenRequest};
+use crate::{atoms::buttons::{ButtonSize, OutlineButton, Squareish1Button}, prelude::*};
+use super::organizations::role_text;
+
+// The page the organization invite email links to
+// the user must be signed in with the invited email to accept it
+pub(super) struct OrganizationInvitePage {
+    pub oob_token_id: String,
+    pub oob_token_key: String,
+    pub invite: Mutable<Option<OrganizationInviteCheckResponse>>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl OrganizationInvitePage {
+    pub fn new(oob_token_id: String, oob_token_key: String) -> Arc<Self> {
+        Arc::new(Self {
+            oob_token_id,
+            oob_token_key,
+            invite: Mutable::new(None),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                match OrganizationInviteCheck::fetch(state.token_request()).await {
+                    Ok(res) => {
+                        state.invite.set(Some(res));
+                    },
+                    Err(e) => {
+                        state.error.set(e);
+                    }
+                }
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .child_signal(state.invite.signal_ref(clone!(state => move |invite| {
+                invite.as_ref().map(|invite| state.render_invite(invite))
+            })))
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-organization-invite-page-back-button"),
+                || {
+                    Route::Dashboard(Dashboard::Browse).go_to_url();
+                }
+            ))
+        })
+    }
+
+    fn render_invite(self: &Arc<Self>, invite: &OrganizationInviteCheckResponse) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .text(&get_text!("dashboard-organization-invite-page-text", {
+                    "name" => invite.organization_name.clone(),
+                    "role" => role_text(invite.role)
+                }))
+            }))
+            .child(html!("div", {
+                .style("display", "flex")
+                .style("gap", "1rem")
+                .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("dashboard-organization-invite-page-accept-button"),
+                    clone!(state => move || {
+                        state.error.clear();
+                        state.loader.load(clone!(state => async move {
+                            match OrganizationInviteAccept::fetch(state.token_request()).await {
+                                Ok(_) => {
+                                    Route::Dashboard(Dashboard::Organizations).go_to_url();
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                ))
+                .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("dashboard-organization-invite-page-decline-button"),
+                    clone!(state => move || {
+                        state.error.clear();
+                        state.loader.load(clone!(state => async move {
+                            match OrganizationInviteDecline::fetch(state.token_request()).await {
+                                Ok(_) => {
+                                    Route::Dashboard(Dashboard::Browse).go_to_url();
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                ))
+            }))
+        })
+    }
+
+    fn token_request(&self) -> OrganizationInviteTokenRequest {
+        OrganizationInviteTokenRequest {
+            oob_token_id: self.oob_token_id.clone(),
+            oob_token_key: self.oob_token_key.clone(),
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::{api::{auth::{AuthOpenIdProviderInfo, AuthOpenIdProviders}, organization::{OrganizationCreate, OrganizationCreateRequest, OrganizationInfo, OrganizationInviteCreate, OrganizationInviteCreateRequest, OrganizationInviteInfo, OrganizationInviteList, OrganizationInviteRevoke, OrganizationInviteRevokeRequest, OrganizationList, OrganizationMemberInfo, OrganizationMemberList, OrganizationMemberRemove, OrganizationMemberRemoveRequest, OrganizationSsoDomainAdd, OrganizationSsoDomainAddRequest, OrganizationSsoDomainList, OrganizationSsoDomainRemove, OrganizationSsoDomainRequest, OrganizationSsoDomainVerify, OrganizationSwitch, OrganizationSwitchRequest, SsoDomainInfo}}, user::OrganizationRole};
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton, Squareish1Button}, input::{TextInput, TextInputKind}}, prelude::*};
+
+// The user's organizations, and the members and invites of the active one
+// what can be done with members depends on the user's role in the active organization
+// owners also manage the email domains that must sign in through sso
+pub(super) struct OrganizationsPage {
+    pub organizations: MutableVec<OrganizationInfo>,
+    pub active: Mutable<Option<OrganizationInfo>>,
+    pub members: MutableVec<OrganizationMemberInfo>,
+    pub invites: MutableVec<OrganizationInviteInfo>,
+    pub sso_domains: MutableVec<SsoDomainInfo>,
+    pub providers: MutableVec<AuthOpenIdProviderInfo>,
+    pub name: TextInput,
+    pub invite_email: TextInput,
+    pub sso_domain: TextInput,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl OrganizationsPage {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            organizations: MutableVec::new(),
+            active: Mutable::new(None),
+            members: MutableVec::new(),
+            invites: MutableVec::new(),
+            sso_domains: MutableVec::new(),
+            providers: MutableVec::new(),
+            name: TextInput::new(TextInputKind::Any),
+            invite_email: TextInput::new(TextInputKind::Email),
+            sso_domain: TextInput::new(TextInputKind::Any),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                state.reload().await;
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-organizations-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .children_signal_vec(state.organizations.signal_vec_cloned().map(clone!(state => move |info| {
+                state.render_organization(info)
+            })))
+            .child(state.render_create())
+            .child_signal(state.active.signal_cloned().map(clone!(state => move |active| {
+                active.map(|active| state.render_active(active))
+            })))
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-organizations-back-button"),
+                || {
+                    Route::Dashboard(Dashboard::Browse).go_to_url();
+                }
+            ))
+        })
+    }
+
+    async fn reload(&self) {
+        let organizations = match OrganizationList::fetch().await {
+            Ok(res) => res.organizations,
+            Err(e) => {
+                self.error.set(e);
+                return;
+            }
+        };
+
+        let active = organizations.iter().find(|info| info.active).cloned();
+        self.organizations.lock_mut().replace_cloned(organizations);
+
+        self.members.lock_mut().clear();
+        self.invites.lock_mut().clear();
+        self.sso_domains.lock_mut().clear();
+
+        if let Some(active) = &active {
+            match OrganizationMemberList::fetch().await {
+                Ok(res) => {
+                    self.members.lock_mut().replace_cloned(res.members);
+                },
+                Err(e) => {
+                    self.error.set(e);
+                }
+            }
+
+            if active.role.can_manage_members() {
+                match OrganizationInviteList::fetch().await {
+                    Ok(res) => {
+                        self.invites.lock_mut().replace_cloned(res.invites);
+                    },
+                    Err(e) => {
+                        self.error.set(e);
+                    }
+                }
+            }
+
+            if active.role == OrganizationRole::Owner {
+                match OrganizationSsoDomainList::fetch().await {
+                    Ok(res) => {
+                        self.sso_domains.lock_mut().replace_cloned(res.domains);
+                    },
+                    Err(e) => {
+                        self.error.set(e);
+                    }
+                }
+
+                match AuthOpenIdProviders::fetch().await {
+                    Ok(res) => {
+                        self.providers.lock_mut().replace_cloned(res.providers);
+                    },
+                    Err(e) => {
+                        self.error.set(e);
+                    }
+                }
+            }
+        }
+
+        self.active.set(active);
+    }
+
+    fn render_organization(self: &Arc<Self>, info: OrganizationInfo) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .apply_if(info.active, |dom| dom.class(&*TEXT_WEIGHT_BOLD))
+                .text(&get_text!("dashboard-organization", {
+                    "name" => info.name.clone(),
+                    "role" => role_text(info.role)
+                }))
+            }))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                if info.active { get_text!("dashboard-organization-unset-button") } else { get_text!("dashboard-organization-switch-button") },
+                clone!(state => move || {
+                    let req = OrganizationSwitchRequest {
+                        id: if info.active { None } else { Some(info.id.clone()) },
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match OrganizationSwitch::fetch(req).await {
+                            Ok(_) => {
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    fn render_create(self: &Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(state.name.render(Some(&get_text!("dashboard-organization-name"))))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-organization-create-button"),
+                clone!(state => move || {
+                    let name = match state.name.value.get_cloned() {
+                        Some(name) => name,
+                        None => return
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match OrganizationCreate::fetch(OrganizationCreateRequest { name }).await {
+                            Ok(_) => {
+                                state.name.value.set(None);
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    fn render_active(self: &Arc<Self>, active: OrganizationInfo) -> Dom {
+        let state = self;
+        let own_role = active.role;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-organization-members-header", {
+                    "name" => active.name.clone()
+                }))
+            }))
+            .children_signal_vec(state.members.signal_vec_cloned().map(clone!(state => move |member| {
+                state.render_member(own_role, member)
+            })))
+            .apply_if(own_role.can_manage_members(), |dom| {
+                dom
+                    .child(state.render_invite_create(own_role))
+                    .child(html!("div", {
+                        .class(&*TEXT_WEIGHT_BOLD)
+                        .text(&get_text!("dashboard-organization-invites-header"))
+                    }))
+                    .children_signal_vec(state.invites.signal_vec_cloned().map(clone!(state => move |invite| {
+                        state.render_invite(invite)
+                    })))
+            })
+            .apply_if(own_role == OrganizationRole::Owner, |dom| {
+                dom
+                    .child(html!("div", {
+                        .class(&*TEXT_WEIGHT_BOLD)
+                        .text(&get_text!("dashboard-organization-sso-domains-header"))
+                    }))
+                    .children_signal_vec(state.sso_domains.signal_vec_cloned().map(clone!(state => move |info| {
+                        state.render_sso_domain(info)
+                    })))
+                    .child(state.render_sso_domain_add())
+            })
+        })
+    }
+
+    fn render_member(self: &Arc<Self>, own_role: OrganizationRole, member: OrganizationMemberInfo) -> Dom {
+        let state = self;
+        let is_self = AUTH.try_clone_uid().as_ref() == Some(&member.uid);
+        // the backend has the final say, e.g. the last owner can't leave
+        let can_remove = is_self || own_role.can_manage_role(member.role);
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .text(&get_text!("dashboard-organization-member", {
+                    "email" => member.email.clone(),
+                    "role" => role_text(member.role)
+                }))
+            }))
+            .apply_if(can_remove, |dom| {
+                dom.child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                    None,
+                    if is_self { get_text!("dashboard-organization-leave-button") } else { get_text!("dashboard-organization-remove-button") },
+                    clone!(state => move || {
+                        let req = OrganizationMemberRemoveRequest {
+                            uid: member.uid.clone(),
+                        };
+                        state.error.clear();
+                        state.loader.load(clone!(state => async move {
+                            match OrganizationMemberRemove::fetch(req).await {
+                                Ok(_) => {
+                                    state.reload().await;
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                ))
+            })
+        })
+    }
+
+    fn render_invite_create(self: &Arc<Self>, own_role: OrganizationRole) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(state.invite_email.render(Some(&get_text!("dashboard-organization-invite-email"))))
+            // a button per role the user may invite as
+            .children(OrganizationRole::all().into_iter().filter(|role| own_role.can_manage_role(*role)).map(|role| {
+                OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("dashboard-organization-invite-button", {"role" => role_text(role)}),
+                    clone!(state => move || {
+                        let email = match state.invite_email.value.get_cloned() {
+                            Some(email) => email,
+                            None => return
+                        };
+                        state.error.clear();
+                        state.loader.load(clone!(state => async move {
+                            match OrganizationInviteCreate::fetch(OrganizationInviteCreateRequest { email, role }).await {
+                                Ok(_) => {
+                                    state.invite_email.value.set(None);
+                                    state.reload().await;
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                )
+            }))
+        })
+    }
+
+    fn render_invite(self: &Arc<Self>, invite: OrganizationInviteInfo) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .child(html!("div", {
+                .text(&get_text!("dashboard-organization-invite", {
+                    "email" => invite.email.clone(),
+                    "role" => role_text(invite.role),
+                    "expiresAt" => format_ms(invite.expires_at)
+                }))
+            }))
+            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-organization-invite-revoke-button"),
+                clone!(state => move || {
+                    let req = OrganizationInviteRevokeRequest {
+                        id: invite.id.clone(),
+                    };
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match OrganizationInviteRevoke::fetch(req).await {
+                            Ok(_) => {
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    fn render_sso_domain_add(self: &Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(state.sso_domain.render(Some(&get_text!("dashboard-organization-sso-domain"))))
+            // a button per provider the domain could be claimed for
+            .children_signal_vec(state.providers.signal_vec_cloned().map(clone!(state => move |info| {
+                OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("dashboard-organization-sso-domain-add-button", {"provider" => info.name.clone()}),
+                    clone!(state => move || {
+                        let domain = match state.sso_domain.value.get_cloned() {
+                            Some(domain) => domain,
+                            None => return
+                        };
+                        let req = OrganizationSsoDomainAddRequest {
+                            domain,
+                            provider: info.provider.clone(),
+                        };
+                        state.error.clear();
+                        state.loader.load(clone!(state => async move {
+                            match OrganizationSsoDomainAdd::fetch(req).await {
+                                Ok(_) => {
+                                    state.sso_domain.value.set(None);
+                                    state.reload().await;
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                )
+            })))
+        })
+    }
+
+    fn render_sso_domain(self: &Arc<Self>, info: SsoDomainInfo) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(html!("div", {
+                .text(&match info.verified_at {
+                    Some(verified_at) => get_text!("dashboard-organization-sso-domain-verified", {
+                        "domain" => info.domain.clone(),
+                        "provider" => info.provider.as_str().to_string(),
+                        "verifiedAt" => format_ms(verified_at)
+                    }),
+                    None => get_text!("dashboard-organization-sso-domain-pending", {
+                        "domain" => info.domain.clone(),
+                        "provider" => info.provider.as_str().to_string()
+                    }),
+                })
+            }))
+            // until it's verified, the owner needs to know what to put in DNS
+            .apply_if(info.verified_at.is_none(), |dom| {
+                dom.child(html!("div", {
+                    .text(&get_text!("dashboard-organization-sso-domain-txt-record", {
+                        "name" => info.txt_record_name.clone(),
+                        "value" => info.txt_record_value.clone()
+                    }))
+                }))
+            })
+            .child(html!("div", {
+                .style("display", "flex")
+                .style("gap", "1rem")
+                .apply_if(info.verified_at.is_none(), |dom| {
+                    dom.child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                        None,
+                        get_text!("dashboard-organization-sso-domain-verify-button"),
+                        clone!(state, info => move || {
+                            let req = OrganizationSsoDomainRequest {
+                                domain: info.domain.clone(),
+                            };
+                            state.error.clear();
+                            state.loader.load(clone!(state => async move {
+                                match OrganizationSsoDomainVerify::fetch(req).await {
+                                    Ok(_) => {
+                                        state.reload().await;
+                                    },
+                                    Err(e) => {
+                                        state.error.set(e);
+                                    }
+                                }
+                            }));
+                        })
+                    ))
+                })
+                .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("dashboard-organization-sso-domain-remove-button"),
+                    clone!(state => move || {
+                        let req = OrganizationSsoDomainRequest {
+                            domain: info.domain.clone(),
+                        };
+                        state.error.clear();
+                        state.loader.load(clone!(state => async move {
+                            match OrganizationSsoDomainRemove::fetch(req).await {
+                                Ok(_) => {
+                                    state.reload().await;
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                ))
+            }))
+        })
+    }
+}
+
+pub(super) fn role_text(role: OrganizationRole) -> String {
+    match role {
+        OrganizationRole::Owner => get_text!("dashboard-organization-role-owner"),
+        OrganizationRole::Admin => get_text!("dashboard-organization-role-admin"),
+        OrganizationRole::Member => get_text!("dashboard-organization-role-member"),
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(ms as f64)).to_locale_string("default", &JsValue::UNDEFINED).into()
+}
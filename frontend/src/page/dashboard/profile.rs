@@ -0,0 +1,128 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::{api::account::{AccountProfile, AccountProfileUpdate, AccountProfileUpdateRequest}, user::UserProfile};
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton}, input::{TextInput, TextInputKind}}, prelude::*};
+
+// The user's name, locale and picture
+// filled in from the openid provider if it shares them, until the user saves their own
+pub(super) struct Profile {
+    pub name: TextInput,
+    pub given_name: TextInput,
+    pub family_name: TextInput,
+    pub locale: TextInput,
+    pub picture_url: TextInput,
+    pub saved: Mutable<bool>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl Profile {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            name: TextInput::new(TextInputKind::Any),
+            given_name: TextInput::new(TextInputKind::Any),
+            family_name: TextInput::new(TextInputKind::Any),
+            locale: TextInput::new(TextInputKind::Any),
+            picture_url: TextInput::new(TextInputKind::Any),
+            saved: Mutable::new(false),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .future(clone!(state => async move {
+                match AccountProfile::fetch().await {
+                    Ok(res) => {
+                        state.set_profile(res.profile.unwrap_or_default());
+                    },
+                    Err(e) => {
+                        state.error.set(e);
+                    }
+                }
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-profile-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .child_signal(state.picture_url.value.signal_cloned().map(|picture_url| {
+                picture_url.filter(|x| x.starts_with("https://")).map(|picture_url| {
+                    html!("img", {
+                        .attr("src", &picture_url)
+                        .attr("alt", "")
+                        // provider avatar hosts don't need to know where it's shown
+                        .attr("referrerpolicy", "no-referrer")
+                        .style("width", "4rem")
+                        .style("height", "4rem")
+                        .style("border-radius", "50%")
+                        .style("object-fit", "cover")
+                    })
+                })
+            }))
+            .child(state.name.render(Some(&get_text!("dashboard-profile-name"))))
+            .child(state.given_name.render(Some(&get_text!("dashboard-profile-given-name"))))
+            .child(state.family_name.render(Some(&get_text!("dashboard-profile-family-name"))))
+            .child(state.locale.render(Some(&get_text!("dashboard-profile-locale"))))
+            .child(state.picture_url.render(Some(&get_text!("dashboard-profile-picture-url"))))
+            .child_signal(state.saved.signal().map(|saved| {
+                saved.then(|| html!("div", {
+                    .text(&get_text!("dashboard-profile-saved"))
+                }))
+            }))
+            // the backend refuses it anyway, it's the user's own profile
+            .apply_if(AUTH.impersonation().is_none(), |dom| {
+                dom.child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("dashboard-profile-save-button"),
+                    clone!(state => move || {
+                        state.error.clear();
+                        state.saved.set(false);
+
+                        let req = AccountProfileUpdateRequest {
+                            profile: state.get_profile(),
+                        };
+                        state.loader.load(clone!(state => async move {
+                            match AccountProfileUpdate::fetch(req).await {
+                                Ok(_) => {
+                                    state.saved.set(true);
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                ))
+            })
+        })
+    }
+
+    fn set_profile(&self, profile: UserProfile) {
+        self.name.value.set(profile.name);
+        self.given_name.value.set(profile.given_name);
+        self.family_name.value.set(profile.family_name);
+        self.locale.value.set(profile.locale);
+        self.picture_url.value.set(profile.picture_url);
+    }
+
+    fn get_profile(&self) -> UserProfile {
+        UserProfile {
+            name: self.name.value.get_cloned(),
+            given_name: self.given_name.value.get_cloned(),
+            family_name: self.family_name.value.get_cloned(),
+            locale: self.locale.value.get_cloned(),
+            picture_url: self.picture_url.value.get_cloned(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::api::recovery_code::{RecoveryCodeRegenerate, RecoveryCodeStatus, RecoveryCodeStatusResponse};
+use crate::{atoms::buttons::{ButtonSize, OutlineButton, Squareish1Button}, prelude::*};
+
+// Single-use codes for getting back into the account without the email
+// a new set is only ever shown here, right after it's generated
+pub(super) struct RecoveryCodesPage {
+    pub status: Mutable<Option<RecoveryCodeStatusResponse>>,
+    pub new_codes: Mutable<Option<Vec<String>>>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl RecoveryCodesPage {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            status: Mutable::new(None),
+            new_codes: Mutable::new(None),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                state.reload().await;
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-recovery-codes-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .child(html!("div", {
+                .text_signal(state.status.signal_ref(|status| {
+                    match status {
+                        None => String::new(),
+                        Some(status) if status.created_at.is_none() => get_text!("dashboard-recovery-codes-none"),
+                        Some(status) => get_text!("dashboard-recovery-codes-remaining", {
+                            "remaining" => status.remaining,
+                            "createdAt" => status.created_at.clone().unwrap_or_default()
+                        }),
+                    }
+                }))
+            }))
+            .child_signal(state.new_codes.signal_cloned().map(clone!(state => move |codes| {
+                Some(match codes {
+                    Some(codes) => state.render_new_codes(codes),
+                    None => state.render_regenerate(),
+                })
+            })))
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-recovery-codes-back-button"),
+                || {
+                    Route::Dashboard(Dashboard::Browse).go_to_url();
+                }
+            ))
+        })
+    }
+
+    async fn reload(&self) {
+        match RecoveryCodeStatus::fetch().await {
+            Ok(res) => {
+                self.status.set(Some(res));
+            },
+            Err(e) => {
+                self.error.set(e);
+            }
+        }
+    }
+
+    fn render_regenerate(self: &Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_SM)
+                .text(&get_text!("dashboard-recovery-codes-explainer"))
+            }))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-recovery-codes-generate-button"),
+                clone!(state => move || {
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        match RecoveryCodeRegenerate::fetch().await {
+                            Ok(res) => {
+                                state.new_codes.set(Some(res.codes));
+                                state.reload().await;
+                            },
+                            Err(e) => {
+                                state.error.set(e);
+                            }
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+
+    // shown once, leaving the page loses them for good
+    fn render_new_codes(self: &Arc<Self>, codes: Vec<String>) -> Dom {
+        let state = self;
+        let download_href = format!("data:text/plain;charset=utf-8,{}", String::from(js_sys::encode_uri_component(&codes.join("\n"))));
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "0.5rem")
+            .child(html!("div", {
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-recovery-codes-save-now"))
+            }))
+            .child(html!("code", {
+                .style("display", "flex")
+                .style("flex-direction", "column")
+                .style("user-select", "all")
+                .children(codes.iter().map(|code| {
+                    html!("div", {
+                        .text(code)
+                    })
+                }))
+            }))
+            .child(html!("a", {
+                .attr("href", &download_href)
+                .attr("download", "recovery-codes.txt")
+                .text(&get_text!("dashboard-recovery-codes-download"))
+            }))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("dashboard-recovery-codes-done-button"),
+                clone!(state => move || {
+                    state.new_codes.set(None);
+                })
+            ))
+        })
+    }
+}
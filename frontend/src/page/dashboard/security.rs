@@ -0,0 +1,94 @@
+use shared::api::account::{AccountSecurityEvents, AuthEventInfo, AuthEventKind};
+use crate::{atoms::buttons::Squareish1Button, prelude::*};
+
+// The user's own recent security activity, so they can spot anything they don't recognize
+pub(super) struct SecurityPage {
+    pub events: MutableVec<AuthEventInfo>,
+    pub error: ApiErrorDisplay,
+}
+
+impl SecurityPage {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            events: MutableVec::new(),
+            error: ApiErrorDisplay::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("align-items", "center")
+            .style("gap", "1rem")
+            .future(clone!(state => async move {
+                match AccountSecurityEvents::fetch().await {
+                    Ok(res) => {
+                        state.events.lock_mut().replace_cloned(res.events);
+                    },
+                    Err(e) => {
+                        state.error.set(e);
+                    }
+                }
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("dashboard-security-header"))
+            }))
+            .child(html!("div", {
+                .style("color", ColorSemantic::Error.to_str())
+                .text_signal(state.error.text_signal())
+            }))
+            .children_signal_vec(state.events.signal_vec_cloned().map(|event| {
+                html!("div", {
+                    .style("display", "flex")
+                    .style("flex-direction", "column")
+                    .style("align-items", "center")
+                    .child(html!("div", {
+                        .apply_if(event.kind == AuthEventKind::SigninFailure, |dom| {
+                            dom.style("color", ColorSemantic::Error.to_str())
+                        })
+                        .text(&get_text!("dashboard-security-event", {
+                            "createdAt" => event.created_at.clone(),
+                            "kind" => kind_text(event.kind),
+                            "detail" => event.detail.clone().unwrap_or_default()
+                        }))
+                    }))
+                    .child(html!("div", {
+                        .class(&*TEXT_SIZE_SM)
+                        .text(&get_text!("dashboard-security-event-client", {
+                            "ip" => event.ip.clone().unwrap_or_default(),
+                            "userAgent" => event.user_agent.clone().unwrap_or_default()
+                        }))
+                    }))
+                })
+            }))
+            .child(Squareish1Button::new().render(
+                get_text!("dashboard-security-back-button"),
+                || {
+                    Route::Dashboard(Dashboard::Browse).go_to_url();
+                }
+            ))
+        })
+    }
+}
+
+fn kind_text(kind: AuthEventKind) -> String {
+    match kind {
+        AuthEventKind::Register => get_text!("dashboard-security-kind-register"),
+        AuthEventKind::SigninSuccess => get_text!("dashboard-security-kind-signin-success"),
+        AuthEventKind::SigninFailure => get_text!("dashboard-security-kind-signin-failure"),
+        AuthEventKind::Signout => get_text!("dashboard-security-kind-signout"),
+        AuthEventKind::PasswordResetRequested => get_text!("dashboard-security-kind-password-reset-requested"),
+        AuthEventKind::PasswordResetCompleted => get_text!("dashboard-security-kind-password-reset-completed"),
+        AuthEventKind::EmailVerified => get_text!("dashboard-security-kind-email-verified"),
+        AuthEventKind::OpenIdLinked => get_text!("dashboard-security-kind-openid-linked"),
+        AuthEventKind::OpenIdUnlinked => get_text!("dashboard-security-kind-openid-unlinked"),
+        AuthEventKind::SessionRevoked => get_text!("dashboard-security-kind-session-revoked"),
+        AuthEventKind::RecoveryCodesGenerated => get_text!("dashboard-security-kind-recovery-codes-generated"),
+        AuthEventKind::RecoveryCodeUsed => get_text!("dashboard-security-kind-recovery-code-used"),
+    }
+}
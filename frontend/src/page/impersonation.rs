@@ -0,0 +1,43 @@
+use wasm_bindgen_futures::spawn_local;
+
+use crate::{atoms::buttons::{ButtonSize, OutlineButton}, auth::Impersonation, prelude::*};
+
+// Always shown on top while an admin is impersonating a user, so it's never mistaken for their own session
+pub struct ImpersonationBanner {
+    pub impersonation: Impersonation,
+}
+
+impl ImpersonationBanner {
+    pub fn new(impersonation: Impersonation) -> Self {
+        Self {
+            impersonation,
+        }
+    }
+
+    pub fn render(&self) -> Dom {
+        html!("div", {
+            .style("display", "flex")
+            .style("align-items", "center")
+            .style("justify-content", "center")
+            .style("gap", "1rem")
+            .style("padding", "0.5rem")
+            .style("background-color", ColorSemantic::Error.to_str())
+            .style("color", "white")
+            .child(html!("div", {
+                .class(&*TEXT_WEIGHT_BOLD)
+                .text(&get_text!("impersonation-banner", {"uid" => self.impersonation.uid.to_string()}))
+            }))
+            .child(OutlineButton::new(false).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("impersonation-stop-button"),
+                || {
+                    spawn_local(async {
+                        AUTH.stop_impersonation().await;
+                        // back to the admin's own session
+                        Route::Dashboard(Dashboard::Admin).hard_redirect();
+                    });
+                }
+            ))
+        })
+    }
+}
@@ -1,7 +1,11 @@
+mod magic_link;
 mod openid;
+mod openid_buttons;
 mod password_reset;
+mod recovery_code;
 mod register;
 mod signin;
+mod terms;
 mod verify_email;
 
 use std::sync::atomic::AtomicBool;
@@ -17,10 +21,10 @@ use argon2::{
         rand_core::OsRng,
         PasswordHash, PasswordHasher, PasswordVerifier, SaltString
     },
-    Argon2
+    Algorithm, Argon2, Params, Version
 };
 use shared::{
-    api::auth::{AuthCheck, AuthCheckResetPassword, AuthCheckResetPasswordRequest, AuthCheckResetPasswordResponse, AuthConfirmResetPassword, AuthConfirmResetPasswordRequest, AuthConfirmResetPasswordResponse, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailRequest, AuthOpenIdConnect, AuthOpenIdConnectRequest, AuthOpenIdFinalizeExec, AuthOpenIdFinalizeExecResponse, AuthOpenIdFinalizeQuery, AuthOpenIdFinalizeQueryResponse, AuthOpenIdFinalizeRequest, AuthRegister, AuthRegisterRequest, AuthRegisterResponse, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendResetPasswordRequestAny, AuthSendVerifyEmail, AuthSignin, AuthSigninRequest, AuthSigninResponse, AuthSignout}, auth::FRONTEND_ROUTE_AFTER_SIGNIN, backend::{
+    api::auth::{AuthCheck, AuthCheckResetPassword, AuthCheckResetPasswordRequest, AuthCheckResetPasswordResponse, AuthConfirmMagicLink, AuthConfirmMagicLinkRequest, AuthConfirmMagicLinkResponse, AuthConfirmResetPassword, AuthConfirmResetPasswordRequest, AuthConfirmResetPasswordResponse, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailCode, AuthConfirmVerifyEmailCodeRequest, AuthConfirmVerifyEmailRequest, AuthInviteCheck, AuthInviteCheckRequest, AuthOpenIdConnect, AuthOpenIdConnectRequest, AuthOpenIdFinalizeExec, AuthOpenIdFinalizeExecResponse, AuthOpenIdFinalizeQuery, AuthOpenIdFinalizeQueryResponse, AuthOpenIdFinalizeRequest, AuthOpenIdLinkFinalize, AuthPasswordParams, AuthPasswordParamsRequest, AuthRegister, AuthRegisterRequest, AuthRegisterResponse, AuthSendMagicLink, AuthSendMagicLinkRequest, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendResetPasswordRequestAny, AuthSendVerifyEmail, AuthSignin, AuthSigninRecoveryCode, AuthSigninRecoveryCodeRequest, AuthSigninRecoveryCodeResponse, AuthSigninRequest, AuthSigninResponse, AuthSignout, AuthTermsAccept, AuthTermsAcceptRequest, AuthTermsStatus, AuthTermsStatusResponse}, auth::{PasswordKdf, PasswordParams, FRONTEND_ROUTE_AFTER_SIGNIN, PASSWORD_SALT_LENGTH}, backend::{
         result::{ApiError, ApiResult, AuthError}, 
         route::{AuthRoute as ApiAuthRoute, OpenIdProvider, Route as ApiRoute}
     }, user::UserId
@@ -30,6 +34,9 @@ use signin::Signin;
 use register::Register;
 use verify_email::{VerifyEmailWaiting, VerifyEmailConfirm};
 use password_reset::VerifyPasswordResetConfirm;
+use magic_link::MagicLinkConfirm;
+use recovery_code::RecoveryCodeSignin;
+use terms::TermsAccept;
 use openid::OpenIdFinalize;
 
 use crate::{prelude::*, atoms::input::TextInput};
@@ -39,42 +46,63 @@ pub fn render(auth_route: AuthRoute) -> Dom {
         AuthRoute::Signin => {
             Signin::new().render()
         },
+        AuthRoute::RecoveryCode => {
+            RecoveryCodeSignin::new().render()
+        },
         AuthRoute::Register => {
-            Register::new().render()
+            Register::new(None).render()
+        },
+        AuthRoute::RegisterInvite { invite_code } => {
+            Register::new(Some(invite_code)).render()
         },
         AuthRoute::VerifyEmailWaiting => {
             VerifyEmailWaiting::new().render()
         },
+        AuthRoute::TermsAccept => {
+            TermsAccept::new().render()
+        },
         AuthRoute::VerifyEmailConfirm {oob_token_id, oob_token_key} => {
             VerifyEmailConfirm::new(oob_token_id.clone(), oob_token_key.to_string()).render()
         },
         AuthRoute::PasswordResetConfirm { oob_token_id, oob_token_key} => {
             VerifyPasswordResetConfirm::new(oob_token_id.clone(), oob_token_key.to_string()).render()
         },
+        AuthRoute::MagicLinkConfirm { oob_token_id, oob_token_key} => {
+            MagicLinkConfirm::new(oob_token_id.clone(), oob_token_key.to_string()).render()
+        },
         AuthRoute::OpenIdFinalize{ session_id, session_key} => {
             OpenIdFinalize::new(session_id.clone(), session_key.to_string()).render()
         },
     }
 }
 
-pub(super) fn hash_password(email: &str, password: &str) -> Result<String> {
-    // salt is composed of email (unique to this record) and global salt
-    // idea is this makes it generally globally unique across the internet
-    // it does not need to be secret, just unique enough to not match some other product's breach
-    // by deriving it from known values, the client doesn't need to ask the server for the salt value
-    // and it's maybe a little bit of an extra protection that the attacker needs to know the email address too
-    // if the user changes their email, they'll need to reset their password too - which is likely a good thing
-    // however, argon2 salts shouldn't be larger than 64 bytes, so we hash the salt itself to get a sha256 hash
-    let salt = [email.as_bytes(), CONFIG.argon2_global_salt].concat();
-    let salt = Sha256::digest(&salt);
-    let salt = SaltString::encode_b64(&salt).map_err(|err| anyhow!("{:?}", err))?;
+pub(super) fn hash_password(email: &str, password: &str, params: &PasswordParams) -> Result<String> {
+    let salt = match &params.salt {
+        // a random salt per account, looked up by email before signin (see AuthPasswordParams)
+        // since it isn't derived from the email, the email can change without invalidating the password
+        Some(salt) => {
+            let salt = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(salt).map_err(|err| anyhow!("{:?}", err))?;
+            SaltString::encode_b64(&salt).map_err(|err| anyhow!("{:?}", err))?
+        },
+        // accounts from before that still use the old salt, composed of email and global salt
+        // it's replaced with a random one on their next signin
+        // argon2 salts shouldn't be larger than 64 bytes, so we hash the salt itself to get a sha256 hash
+        None => {
+            let salt = [email.as_bytes(), CONFIG.argon2_global_salt].concat();
+            let salt = Sha256::digest(&salt);
+            SaltString::encode_b64(&salt).map_err(|err| anyhow!("{:?}", err))?
+        }
+    };
+    let kdf = &params.kdf;
 
     // derive the argon2 hash, which takes some time to compute from the user's password
     // this makes it much harder to brute force the password, even if the database is breached
     // it's computed clientside to avoid denial-of-service attacks on the server
     // and there's simply no need for the server to know the real password
     // on the server, it will be hashed again but with a simpler sha256 hash merely to avoid data breaches
-    let hash = Argon2::default().hash_password(password.as_bytes(), &salt).map_err(|err| anyhow!("{:?}", err))?;
+    // the params are versioned per account (see AuthPasswordParams), so they can be raised over time
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, None).map_err(|err| anyhow!("{:?}", err))?;
+    let hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password(password.as_bytes(), &salt).map_err(|err| anyhow!("{:?}", err))?;
     let hash = hash.hash.expect("hash should be present");
 
     // now encode this hash into a string that can be sent over the wire and decoded serverside 
@@ -84,26 +112,66 @@ pub(super) fn hash_password(email: &str, password: &str) -> Result<String> {
 
 /////// Api calls
 
-pub(super) async fn openid_connect(provider: OpenIdProvider) -> ApiResult<()> {
-    let res = AuthOpenIdConnect::fetch(AuthOpenIdConnectRequest{provider}).await?;
+// the invite code goes along with the openid session, in case it registers a new account
+pub(super) async fn openid_connect(provider: OpenIdProvider, invite_code: Option<String>) -> ApiResult<()> {
+    let res = AuthOpenIdConnect::fetch(AuthOpenIdConnectRequest{provider, invite_code}).await?;
 
     web_sys::window().unwrap_ext().location().replace(&res.url).unwrap_ext();
 
     Ok(())
 }
-pub(super) async fn signin(email: &str, password: &str) -> ApiResult<()> {
-    let password = hash_password(email, password).map_err(|err| ApiError::Unknown(err.to_string()))?;
 
-    let AuthSigninResponse{uid, email_verified, auth_key} = AuthSignin::fetch(AuthSigninRequest { email: email.to_string(), password }).await?;
+// emails on a domain claimed for sso can't use passwords, so they're sent off to its provider instead
+// the error is still passed on, for the page to show while the redirect happens (or if it fails)
+async fn redirect_if_sso_required<T>(res: ApiResult<T>, invite_code: Option<String>) -> ApiResult<T> {
+    if let Err(ApiError::Auth(AuthError::SsoRequired { provider })) = &res {
+        openid_connect(provider.clone(), invite_code).await?;
+    }
+
+    res
+}
+
+// the params the account's password was hashed with, must be used for anything that checks it
+pub(super) async fn password_params(email: &str) -> ApiResult<PasswordParams> {
+    Ok(AuthPasswordParams::fetch(AuthPasswordParamsRequest { email: email.to_string() }).await?.params)
+}
+
+// new passwords are always stored with the current kdf and a fresh random salt
+fn new_password_params() -> PasswordParams {
+    let salt = rand::thread_rng().gen::<[u8; PASSWORD_SALT_LENGTH]>();
+    PasswordParams {
+        kdf: PasswordKdf::current(),
+        salt: Some(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(salt)),
+    }
+}
+
+pub(super) async fn signin(email: &str, password: &str, remember_me: bool) -> ApiResult<()> {
+    let params = AuthPasswordParams::fetch(AuthPasswordParamsRequest { email: email.to_string() }).await?;
+
+    // if the account's params are outdated, the server can only upgrade it with a hash made from the new ones
+    let (upgrade_password, upgrade_salt) = match params.upgrade {
+        Some(upgrade) => (
+            Some(hash_password(email, password, &upgrade).map_err(|err| ApiError::Unknown(err.to_string()))?),
+            upgrade.salt
+        ),
+        None => (None, None)
+    };
+    let password = hash_password(email, password, &params.params).map_err(|err| ApiError::Unknown(err.to_string()))?;
+
+    let res = AuthSignin::fetch(AuthSigninRequest { email: email.to_string(), password, remember_me, upgrade_password, upgrade_salt }).await;
+    let AuthSigninResponse{uid, email_verified, auth_key} = redirect_if_sso_required(res, None).await?;
 
 
     AUTH.on_signin(uid, email_verified, auth_key).await
 }
 
-pub(super) async fn register(email: &str, password: &str) -> ApiResult<()> {
-    let password = hash_password(email, password).map_err(|err| ApiError::Unknown(err.to_string()))?;
+pub(super) async fn register(email: &str, password: &str, invite_code: Option<String>, challenge_token: Option<String>) -> ApiResult<()> {
+    let params = new_password_params();
+    let password = hash_password(email, password, &params).map_err(|err| ApiError::Unknown(err.to_string()))?;
+    let salt = params.salt.unwrap_ext();
 
-    let AuthRegisterResponse{uid, email_verified, auth_key} = AuthRegister::fetch(AuthRegisterRequest { email: email.to_string(), password }).await?;
+    let res = AuthRegister::fetch(AuthRegisterRequest { email: email.to_string(), password, salt, invite_code: invite_code.clone(), challenge_token }).await;
+    let AuthRegisterResponse{uid, email_verified, auth_key} = redirect_if_sso_required(res, invite_code).await?;
 
     AUTH.on_signin(uid, email_verified, auth_key).await?;
     FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
@@ -111,10 +179,17 @@ pub(super) async fn register(email: &str, password: &str) -> ApiResult<()> {
     Ok(())
 }
 
-pub(super) async fn send_password_reset(email: Option<&str>) -> ApiResult<()> {
+// the email the invite is for, if it's only for one
+pub(super) async fn check_invite(code: String) -> ApiResult<Option<String>> {
+    Ok(AuthInviteCheck::fetch(AuthInviteCheckRequest { code }).await?.email)
+}
+
+// the challenge token is only for the signed-out variant, signed-in users aren't challenged
+pub(super) async fn send_password_reset(email: Option<&str>, challenge_token: Option<String>) -> ApiResult<()> {
     match email {
         Some(email) => {
-            AuthSendResetPasswordAny::fetch(AuthSendResetPasswordRequestAny { email: email.to_string() }).await
+            let res = AuthSendResetPasswordAny::fetch(AuthSendResetPasswordRequestAny { email: email.to_string(), challenge_token }).await;
+            redirect_if_sso_required(res, None).await
         },
         None => {
             AuthSendResetPasswordMe::fetch().await
@@ -127,9 +202,11 @@ pub(super) async fn check_password_reset(oob_token_id: String, oob_token_key: St
 }
 
 pub(super) async fn confirm_password_reset(oob_token_id: String, oob_token_key: String, email: &str, password: &str) -> ApiResult<()> {
-    let password = hash_password(email, password).map_err(|err| ApiError::Unknown(err.to_string()))?;
+    let params = new_password_params();
+    let password = hash_password(email, password, &params).map_err(|err| ApiError::Unknown(err.to_string()))?;
+    let salt = params.salt.unwrap_ext();
 
-    let res = AuthConfirmResetPassword::fetch(AuthConfirmResetPasswordRequest{ oob_token_id, oob_token_key, password }).await?;
+    let res = AuthConfirmResetPassword::fetch(AuthConfirmResetPasswordRequest{ oob_token_id, oob_token_key, password, salt }).await?;
     let AuthConfirmResetPasswordResponse{uid, email_verified, auth_key} = res;
 
     AUTH.on_signin(uid, email_verified, auth_key).await?;
@@ -138,6 +215,20 @@ pub(super) async fn confirm_password_reset(oob_token_id: String, oob_token_key:
     Ok(())
 }
 
+pub(super) async fn send_magic_link(email: &str) -> ApiResult<()> {
+    let res = AuthSendMagicLink::fetch(AuthSendMagicLinkRequest { email: email.to_string() }).await;
+    redirect_if_sso_required(res, None).await
+}
+
+pub(super) async fn confirm_magic_link(oob_token_id: String, oob_token_key: String) -> ApiResult<()> {
+    let AuthConfirmMagicLinkResponse{uid, email_verified, auth_key} = AuthConfirmMagicLink::fetch(AuthConfirmMagicLinkRequest{ oob_token_id, oob_token_key }).await?;
+
+    AUTH.on_signin(uid, email_verified, auth_key).await?;
+    FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
+
+    Ok(())
+}
+
 // this is used on the root page
 pub async fn send_email_validation() -> ApiResult<()> {
     AuthSendVerifyEmail::fetch().await
@@ -152,16 +243,60 @@ pub(super) async fn confirm_email_validation(oob_token_id: String, oob_token_key
     Ok(())
 }
 
+// unlike the link, this is on the page the user is already on, so a wrong code is shown there
+pub(super) async fn confirm_email_validation_code(code: String) -> ApiResult<()> {
+    AuthConfirmVerifyEmailCode::fetch(AuthConfirmVerifyEmailCodeRequest { code }).await?;
+
+    AUTH.check().await;
+    FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
+
+    Ok(())
+}
+
+pub(super) async fn terms_status() -> ApiResult<AuthTermsStatusResponse> {
+    AuthTermsStatus::fetch().await
+}
+
+pub(super) async fn terms_accept(version: u32) -> ApiResult<()> {
+    AuthTermsAccept::fetch(AuthTermsAcceptRequest { version }).await?;
+
+    AUTH.check().await;
+    FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
+
+    Ok(())
+}
+
+pub(super) async fn signin_recovery_code(email: String, code: String) -> ApiResult<AuthSigninRecoveryCodeResponse> {
+    AuthSigninRecoveryCode::fetch(AuthSigninRecoveryCodeRequest { email, code }).await
+}
+
 pub(super) async fn openid_session_query(session_id: String, session_key: String) -> ApiResult<AuthOpenIdFinalizeQueryResponse> {
-    AuthOpenIdFinalizeQuery::fetch(AuthOpenIdFinalizeRequest{ session_id, session_key}).await
+    AuthOpenIdFinalizeQuery::fetch(AuthOpenIdFinalizeRequest{ session_id, session_key, password: None}).await
 }
 
-pub(super) async fn openid_session_finalize(session_id: String, session_key: String) -> ApiResult<()> {
-    let res = AuthOpenIdFinalizeExec::fetch(AuthOpenIdFinalizeRequest{ session_id, session_key}).await?;
+// existing is the email and password of the account with the same email, only needed on an email collision
+pub(super) async fn openid_session_finalize(session_id: String, session_key: String, existing: Option<(&str, &str)>) -> ApiResult<()> {
+    let password = match existing {
+        Some((email, password)) => {
+            let params = password_params(email).await?;
+            Some(hash_password(email, password, &params).map_err(|err| ApiError::Unknown(err.to_string()))?)
+        },
+        None => None
+    };
+
+    let res = AuthOpenIdFinalizeExec::fetch(AuthOpenIdFinalizeRequest{ session_id, session_key, password}).await?;
     let AuthOpenIdFinalizeExecResponse{uid, email_verified, auth_key} = res;
 
     AUTH.on_signin(uid, email_verified, auth_key).await?;
     FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// the session was started from the dashboard, by an already signed-in user
+pub(super) async fn openid_session_link(session_id: String, session_key: String) -> ApiResult<()> {
+    AuthOpenIdLinkFinalize::fetch(AuthOpenIdFinalizeRequest{ session_id, session_key, password: None}).await?;
+    FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
+
+    Ok(())
+}
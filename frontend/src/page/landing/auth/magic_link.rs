@@ -0,0 +1,89 @@
+use super::confirm_magic_link;
+use crate::{atoms::buttons::OutlineButton, prelude::*};
+
+pub(super) struct MagicLinkConfirm {
+    pub oob_token_id: String,
+    pub oob_token_key: String,
+    pub phase: Mutable<MagicLinkConfirmPhase>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MagicLinkConfirmPhase {
+    Confirming,
+    Fail
+}
+
+impl MagicLinkConfirm {
+    pub fn new(oob_token_id: String, oob_token_key: String) -> Arc<Self> {
+        Arc::new(Self {
+            oob_token_id,
+            oob_token_key,
+            phase: Mutable::new(MagicLinkConfirmPhase::Confirming),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+        html!("div", {
+            .child_signal(state.phase.signal().map(clone!(state => move |phase| {
+                Some(match phase {
+                    MagicLinkConfirmPhase::Confirming => {
+                        state.render_confirming()
+                    },
+                    MagicLinkConfirmPhase::Fail => {
+                        state.render_fail()
+                    },
+                })
+            })))
+        })
+    }
+
+    fn render_confirming(self: &Arc<Self>) -> Dom {
+        let state = self;
+        html!("div", {
+            .future(clone!(state => async move {
+                // on success, confirm_magic_link redirects
+                if confirm_magic_link(state.oob_token_id.clone(), state.oob_token_key.clone()).await.is_err() {
+                    state.phase.set_neq(MagicLinkConfirmPhase::Fail);
+                }
+            }))
+            .class(&*TEXT_SIZE_LG)
+            .text(&get_text!("landing-signing-in"))
+        })
+    }
+
+    fn render_fail(&self) -> Dom {
+        static ERROR_MESSAGE:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("color", ColorSemantic::Error.to_str())
+                .style("padding", "5.19rem 0 4.81rem 0")
+            }
+        });
+
+        static CONTAINER:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("display", "flex")
+                .style("flex-direction", "column")
+                .style("align-items", "center")
+                .style("justify-content", "center")
+            }
+        });
+
+        html!("div", {
+            .class(&*CONTAINER)
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*ERROR_MESSAGE)
+                .text(&get_text!("landing-magic-link-error"))
+            }))
+            // the links are one-time and short-lived, so the way forward is just to ask for another one
+            .child(OutlineButton::new(true).render(
+                None,
+                get_text!("landing-signin-button"),
+                || {
+                    Route::Landing(Landing::Auth(AuthRoute::Signin)).go_to_url();
+                }
+            ))
+        })
+    }
+}
@@ -1,13 +1,19 @@
 use std::sync::atomic::AtomicBool;
 
-use super::{openid_session_query, openid_session_finalize};
-use crate::{atoms::buttons::Squareish1Button, prelude::*};
+use dominator_helpers::futures::AsyncLoader;
+use shared::{api::auth::AuthOpenIdFinalizeStatus, backend::result::ApiError};
+use super::{openid_session_query, openid_session_finalize, openid_session_link};
+use crate::{atoms::{buttons::Squareish1Button, input::{TextInput, TextInputKind}}, prelude::*};
 
 pub(super) struct OpenIdFinalize {
     pub session_id: String,
     pub session_key: String,
     pub phase: Mutable<OpenIdFinalizePhase>,
     pub register_terms: AtomicBool,
+    pub email: Mutable<String>,
+    pub password: TextInput,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -16,6 +22,8 @@ enum OpenIdFinalizePhase {
     Submitting,
     Invalid,
     AskForRegister,
+    AskForPassword,
+    Linking,
 }
 
 impl OpenIdFinalize {
@@ -25,6 +33,10 @@ impl OpenIdFinalize {
             session_key,
             phase: Mutable::new(OpenIdFinalizePhase::Loading),
             register_terms: AtomicBool::new(false),
+            email: Mutable::new(String::new()),
+            password: TextInput::new(TextInputKind::Password),
+            error: ApiErrorDisplay::new(),
+            loader: AsyncLoader::new(),
         })
     }
 
@@ -34,16 +46,23 @@ impl OpenIdFinalize {
             .future(clone!(state => async move {
                 match openid_session_query(state.session_id.clone(), state.session_key.clone()).await {
                     Ok(resp) => {
-                        if resp.user_exists {
-                            // user already exists - just sign them in
-                            // this distinction is handled automatically in the backend
+                        state.email.set(resp.email);
+                        state.phase.set_neq(match resp.status {
+                            // identity is already linked - just sign them in
+                            AuthOpenIdFinalizeStatus::Signin => OpenIdFinalizePhase::Submitting,
                             // we're just making sure we get the user to agree to registration terms on the frontend
-                            state.phase.set_neq(OpenIdFinalizePhase::Submitting);
-                        } else {
-                            state.phase.set_neq(OpenIdFinalizePhase::AskForRegister);
-                        }
+                            AuthOpenIdFinalizeStatus::Register => OpenIdFinalizePhase::AskForRegister,
+                            // another account has this email, the user must prove it's theirs before it's linked
+                            AuthOpenIdFinalizeStatus::EmailCollision => OpenIdFinalizePhase::AskForPassword,
+                            // started from the dashboard, the user is already signed in
+                            AuthOpenIdFinalizeStatus::Link => OpenIdFinalizePhase::Linking,
+                        });
                     },
                     Err(e) => {
+                        // e.g. registration is closed, which is worth saying
+                        if let ApiError::Auth(_) = e {
+                            state.error.set(e);
+                        }
                         state.phase.set_neq(OpenIdFinalizePhase::Invalid);
                     }
                 }
@@ -58,12 +77,17 @@ impl OpenIdFinalize {
                     OpenIdFinalizePhase::AskForRegister => {
                         Some(state.render_ask_for_register())
                     },
+                    OpenIdFinalizePhase::AskForPassword => {
+                        Some(state.render_ask_for_password())
+                    },
+                    OpenIdFinalizePhase::Linking => Some(state.render_linking()),
                 }
             })))
         })
     }
 
     fn render_invalid(&self) -> Dom {
+        let error = self.error.clone();
         static ERROR_MESSAGE:Lazy<String> = Lazy::new(|| {
             class! {
                 .style("color", ColorSemantic::Error.to_str())
@@ -75,7 +99,13 @@ impl OpenIdFinalize {
             .class(&*ERROR_MESSAGE)
             .class(&*TEXT_SIZE_LG)
             .style("text-align", "center")
-            .text(&get_text!("error-api-openid-invalid"))
+            .text_signal(error.text_signal().map(|text| {
+                if text.is_empty() {
+                    get_text!("error-api-openid-invalid")
+                } else {
+                    text
+                }
+            }))
         })
     }
 
@@ -83,7 +113,7 @@ impl OpenIdFinalize {
         let state = self;
         html!("div", {
             .future(clone!(state => async move {
-                if openid_session_finalize(state.session_id.clone(), state.session_key.clone()).await.is_err() {
+                if openid_session_finalize(state.session_id.clone(), state.session_key.clone(), None).await.is_err() {
                     state.phase.set_neq(OpenIdFinalizePhase::Invalid);
                 }
             }))
@@ -124,6 +154,13 @@ impl OpenIdFinalize {
             .child(html!("div", {
                 .text(&get_text!("landing-agree-terms"))
             }))
+            .child(html!("a", {
+                .attr("href", CONFIG.terms_url)
+                .attr("target", "_blank")
+                .style("text-decoration", "underline")
+                .style("margin-bottom", "1.875rem")
+                .text(&get_text!("landing-terms-link"))
+            }))
             .child(Squareish1Button::new().render(
                 get_text!("button-submit"),
                 clone!(state => move || {
@@ -133,4 +170,64 @@ impl OpenIdFinalize {
         })
     }
 
-}
\ No newline at end of file
+    fn render_linking(self: &Arc<Self>) -> Dom {
+        let state = self;
+        html!("div", {
+            .future(clone!(state => async move {
+                if openid_session_link(state.session_id.clone(), state.session_key.clone()).await.is_err() {
+                    state.phase.set_neq(OpenIdFinalizePhase::Invalid);
+                }
+            }))
+            .class(&*TEXT_SIZE_LG)
+            .text(&get_text!("landing-openid-linking"))
+        })
+    }
+
+    fn render_ask_for_password(self: &Arc<Self>) -> Dom {
+        let state = self;
+        static ERROR_MESSAGE:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("color", ColorSemantic::Error.to_str())
+            }
+        });
+
+        static CONTAINER:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("display", "flex")
+                .style("flex-direction", "column")
+                .style("align-items", "center")
+                .style("justify-content", "center")
+                .style("gap", "1.875rem")
+            }
+        });
+
+        html!("div", {
+            .class(&*CONTAINER)
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .text_signal(state.email.signal_cloned().map(|email| get_text!("landing-openid-email-collision", {"email" => email})))
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .class(&*ERROR_MESSAGE)
+                .text_signal(state.error.text_signal())
+            }))
+            .child(state.password.render(Some(&get_text!("landing-signin-form-password"))))
+            .child(Squareish1Button::new().render(
+                get_text!("button-submit"),
+                clone!(state => move || {
+                    state.error.clear();
+                    state.loader.load(clone!(state => async move {
+                        let email = state.email.get_cloned();
+                        let password = state.password.value.get_cloned().unwrap_or_default();
+                        // unlike the other phases, a wrong password can be retried
+                        if let Err(e) = openid_session_finalize(state.session_id.clone(), state.session_key.clone(), Some((&email, &password))).await {
+                            state.error.set(e);
+                        }
+                    }));
+                })
+            ))
+        })
+    }
+}
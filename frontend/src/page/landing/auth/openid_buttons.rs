@@ -0,0 +1,98 @@
+use dominator_helpers::futures::AsyncLoader;
+use shared::api::auth::{AuthOpenIdProviderInfo, AuthOpenIdProviders};
+use super::openid_connect;
+use crate::{atoms::buttons::OutlineButton, prelude::*};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum OpenIdButtonsKind {
+    Signin,
+    Register,
+}
+
+// One button per configured openid provider
+// the list comes from the backend, so new providers show up without any frontend changes
+pub(super) struct OpenIdButtons {
+    pub kind: OpenIdButtonsKind,
+    // only when registering from an invite link
+    pub invite_code: Option<String>,
+    pub providers: MutableVec<AuthOpenIdProviderInfo>,
+    pub error: ApiErrorDisplay,
+    pub loader: AsyncLoader,
+}
+
+impl OpenIdButtons {
+    pub fn new(kind: OpenIdButtonsKind, invite_code: Option<String>, error: ApiErrorDisplay) -> Arc<Self> {
+        Arc::new(Self {
+            kind,
+            invite_code,
+            providers: MutableVec::new(),
+            error,
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+
+        html!("div", {
+            .style("display", "flex")
+            .style("flex-direction", "column")
+            .style("gap", "1.875rem")
+            .future(clone!(state => async move {
+                match AuthOpenIdProviders::fetch().await {
+                    Ok(res) => {
+                        state.providers.lock_mut().replace_cloned(res.providers);
+                    },
+                    Err(e) => {
+                        state.error.set(e);
+                    }
+                }
+            }))
+            .children_signal_vec(state.providers.signal_vec_cloned().map(clone!(state => move |info| {
+                state.render_button(info)
+            })))
+        })
+    }
+
+    fn render_button(self: &Arc<Self>, info: AuthOpenIdProviderInfo) -> Dom {
+        let state = self;
+
+        let text = match state.kind {
+            OpenIdButtonsKind::Signin => get_text!("landing-signin-openid-button", {"name" => info.name.clone()}),
+            OpenIdButtonsKind::Register => get_text!("landing-register-openid-button", {"name" => info.name.clone()}),
+        };
+
+        let image = info.icon.as_ref().map(|icon| {
+            let src = if icon.starts_with("http://") || icon.starts_with("https://") {
+                icon.clone()
+            } else {
+                CONFIG.app_image_url(icon)
+            };
+
+            html!("img", {
+                .style("height", "2rem")
+                .attr("src", &src)
+            })
+        });
+
+        let provider = info.provider;
+
+        OutlineButton::new(false).render(
+            image,
+            text,
+            clone!(state => move || {
+                state.loader.load(clone!(state, provider => async move {
+                    state.error.clear();
+                    match openid_connect(provider, state.invite_code.clone()).await {
+                        Ok(_) => {
+                            // openid_connect will redirect
+                        },
+                        Err(e) => {
+                            state.error.set(e);
+                        }
+                    }
+                }));
+            })
+        )
+    }
+}
@@ -0,0 +1,99 @@
+use dominator_helpers::futures::AsyncLoader;
+use super::signin_recovery_code;
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton, Squareish1Button}, input::{TextInput, TextInputKind}}, prelude::*};
+
+// For when the email is out of reach, so a reset link can't be received
+// a good code leads straight to setting a new password
+pub(super) struct RecoveryCodeSignin {
+    pub error: ApiErrorDisplay,
+    pub email: TextInput,
+    pub code: TextInput,
+    pub loader: AsyncLoader,
+}
+
+impl RecoveryCodeSignin {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            error: ApiErrorDisplay::new(),
+            email: TextInput::new(TextInputKind::Email),
+            code: TextInput::new(TextInputKind::Any),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+        static CONTAINER:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("display", "flex")
+                .style("flex-direction", "column")
+                .style("align-items", "center")
+                .style("justify-content", "center")
+            }
+        });
+
+        static INPUTS:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("display", "flex")
+                .style("flex-direction", "column")
+                .style("align-items", "center")
+                .style("gap", "1.875rem")
+            }
+        });
+
+        static ERROR_MESSAGE:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("color", ColorSemantic::Error.to_str())
+                .style("padding", "5.19rem 0 4.81rem 0")
+            }
+        });
+
+        html!("div", {
+            .class(&*CONTAINER)
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .class(&*ERROR_MESSAGE)
+                .text_signal(state.error.text_signal())
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .style("margin-bottom", "1.875rem")
+                .text(&get_text!("landing-recovery-code-instructions"))
+            }))
+            .child(html!("div", {
+                .class(&*INPUTS)
+                .child(state.email.render(Some(&get_text!("landing-signin-form-email"))))
+                .child(state.code.render(Some(&get_text!("landing-recovery-code-form-code"))))
+                .child(Squareish1Button::new().render(
+                    get_text!("landing-recovery-code-button"),
+                    clone!(state => move || {
+                        state.error.clear();
+                        let email = state.email.value.get_cloned().unwrap_or_default();
+                        let code = state.code.value.get_cloned().unwrap_or_default();
+                        state.loader.load(clone!(state => async move {
+                            match signin_recovery_code(email, code).await {
+                                Ok(res) => {
+                                    Route::Landing(Landing::Auth(AuthRoute::PasswordResetConfirm {
+                                        oob_token_id: res.oob_token_id,
+                                        oob_token_key: res.oob_token_key,
+                                    })).go_to_url();
+                                },
+                                Err(e) => {
+                                    state.error.set(e);
+                                }
+                            }
+                        }));
+                    })
+                ))
+                .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                    None,
+                    get_text!("landing-recovery-code-back-button"),
+                    || {
+                        Route::Landing(Landing::Auth(AuthRoute::Signin)).go_to_url();
+                    }
+                ))
+            }))
+        })
+    }
+}
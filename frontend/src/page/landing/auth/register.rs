@@ -1,21 +1,26 @@
 use dominator_helpers::futures::AsyncLoader;
-use shared::{auth::FRONTEND_ROUTE_AFTER_SIGNIN, backend::route::OpenIdProvider};
-use super::{register, openid_connect};
-use crate::{atoms::{buttons::{OutlineButton, Squareish1Button}, input::{TextInput, TextInputKind}}, prelude::*};
+use shared::auth::FRONTEND_ROUTE_AFTER_SIGNIN;
+use super::{check_invite, register, openid_buttons::{OpenIdButtons, OpenIdButtonsKind}};
+use crate::{atoms::{buttons::{OutlineButton, Squareish1Button}, challenge::ChallengeWidget, input::{TextInput, TextInputKind}}, prelude::*};
 
 pub(super) struct Register {
+    // from an invite link, see Route::RegisterInvite
+    pub invite_code: Option<String>,
     pub error: ApiErrorDisplay,
     pub email: TextInput,
     pub password: TextInput,
+    pub challenge: ChallengeWidget,
     pub loader: AsyncLoader,
 }
 
 impl Register {
-    pub fn new() -> Arc<Self> {
+    pub fn new(invite_code: Option<String>) -> Arc<Self> {
         Arc::new(Self {
+            invite_code,
             error: ApiErrorDisplay::new(),
             email: TextInput::new(TextInputKind::Email),
             password: TextInput::new(TextInputKind::Password),
+            challenge: ChallengeWidget::new(),
             loader: AsyncLoader::new(),
         })
     }
@@ -56,6 +61,20 @@ impl Register {
         });
         html!("div", {
             .class(&*CONTAINER)
+            .apply_if(state.invite_code.is_some(), |dom| {
+                dom.future(clone!(state => async move {
+                    // an invite for a specific email pre-fills it, and a bad one is shown right away
+                    match check_invite(state.invite_code.clone().unwrap_ext()).await {
+                        Ok(Some(email)) => {
+                            state.email.value.set(Some(email));
+                        },
+                        Ok(None) => {},
+                        Err(e) => {
+                            state.error.set(e);
+                        }
+                    }
+                }))
+            })
             .child(state.render_error())
             .child(html!("div", {
                 .child(html!("div", {
@@ -64,6 +83,7 @@ impl Register {
                         .class(&*INPUTS)
                         .child(state.email.render(Some(&get_text!("landing-signin-form-email"))))
                         .child(state.password.render(Some(&get_text!("landing-signin-form-password"))))
+                        .child(state.challenge.render())
                         .child(html!("div", {
                             .style("width", "100%")
                             .class(&*BUTTONS)
@@ -73,12 +93,13 @@ impl Register {
                                     state.loader.load(clone!(state => async move {
                                         state.error.clear();
 
-                                        match register(&state.email.value.get_cloned().unwrap_or_default(), &state.password.value.get_cloned().unwrap_or_default()).await {
+                                        match register(&state.email.value.get_cloned().unwrap_or_default(), &state.password.value.get_cloned().unwrap_or_default(), state.invite_code.clone(), state.challenge.token.get_cloned()).await {
                                             Ok(_) => {
                                                 FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
                                             },
                                             Err(e) => {
                                                 state.error.set(e);
+                                                state.challenge.reset();
                                             }
                                         }
                                     }));
@@ -100,46 +121,21 @@ impl Register {
                         .style("flex-direction", "column")
                         .style("justify-content", "center")
                         .class(&*BUTTONS)
-                        .child(OutlineButton::new(false).render(
-                            Some(html!("img", {
-                                .style("height", "2rem")
-                                .attr("src", &CONFIG.app_image_url("facebook-logo.svg"))
-                            })),
-                            get_text!("landing-register-facebook-button"),
-                            clone!(state => move || {
-                                state.loader.load(clone!(state => async move {
-                                    state.error.clear();
-                                    match openid_connect(OpenIdProvider::Facebook).await {
-                                        Ok(_) => {
-                                            // openid_connect will redirect
-                                        },
-                                        Err(e) => {
-                                            state.error.set(e);
-                                        }
-                                    }
-                                }));
-                            })
-                        ))
-                        .child(OutlineButton::new(false).render(
-                            Some(html!("img", {
-                                .style("height", "2rem")
-                                .attr("src", &CONFIG.app_image_url("google-logo.svg"))
-                            })),
-                            get_text!("landing-register-google-button"),
-                            clone!(state => move || {
-                                state.loader.load(clone!(state => async move {
-                                    state.error.clear();
-                                    match openid_connect(OpenIdProvider::Google).await {
-                                        Ok(_) => {
-                                            // openid_connect will redirect
-                                        },
-                                        Err(e) => {
-                                            state.error.set(e);
-                                        }
-                                    }
-                                }));
-                            })
-                        ))
+                        .child(OpenIdButtons::new(OpenIdButtonsKind::Register, state.invite_code.clone(), state.error.clone()).render())
+                    }))
+                }))
+                // signing up records acceptance of the current terms, see the backend's TERMS_VERSION
+                .child(html!("div", {
+                    .style("margin-top", "1.875rem")
+                    .style("text-align", "center")
+                    .child(html!("span", {
+                        .text(&format!("{} ", get_text!("landing-agree-terms")))
+                    }))
+                    .child(html!("a", {
+                        .attr("href", CONFIG.terms_url)
+                        .attr("target", "_blank")
+                        .style("text-decoration", "underline")
+                        .text(&get_text!("landing-terms-link"))
                     }))
                 }))
                 .child(html!("div", {
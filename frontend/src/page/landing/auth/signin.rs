@@ -1,13 +1,16 @@
 use dominator_helpers::futures::AsyncLoader;
-use shared::{auth::FRONTEND_ROUTE_AFTER_SIGNIN, backend::route::OpenIdProvider};
-use super::{signin, send_password_reset, openid_connect};
-use crate::{atoms::{buttons::{ButtonSize, OutlineButton, Squareish1Button}, input::{TextInput, TextInputKind}}, prelude::*};
+use shared::auth::FRONTEND_ROUTE_AFTER_SIGNIN;
+use super::{signin, send_password_reset, send_magic_link, openid_buttons::{OpenIdButtons, OpenIdButtonsKind}};
+use crate::{atoms::{buttons::{ButtonSize, OutlineButton, Squareish1Button}, challenge::ChallengeWidget, input::{TextInput, TextInputKind}}, prelude::*};
 
 pub(super) struct Signin {
     pub error: ApiErrorDisplay,
     pub notice: Mutable<Option<SigninNotice>>,
     pub email: TextInput,
     pub password: TextInput,
+    pub remember_me: Mutable<bool>,
+    // only for sending a password reset, signing in itself isn't challenged
+    pub challenge: ChallengeWidget,
     pub loader: AsyncLoader,
 }
 
@@ -15,6 +18,7 @@ pub(super) struct Signin {
 enum SigninNotice {
     EmailNotVerified,
     PasswordReset,
+    MagicLink,
 }
 
 impl Signin {
@@ -24,6 +28,8 @@ impl Signin {
             notice: Mutable::new(None),
             email: TextInput::new(TextInputKind::Email),
             password: TextInput::new(TextInputKind::Password),
+            remember_me: Mutable::new(false),
+            challenge: ChallengeWidget::new(),
             loader: AsyncLoader::new(),
         })
     }
@@ -79,6 +85,13 @@ impl Signin {
                             .text(&get_text!("landing-signin-password-reset-sent"))
                         })
                     },
+                    SigninNotice::MagicLink => {
+                        html!("div", {
+                            .class(&*TEXT_SIZE_LG)
+                            .style("margin-bottom", "1.875rem")
+                            .text(&get_text!("landing-signin-magic-link-sent"))
+                        })
+                    },
                 })
             }))
             .child(html!("div", {
@@ -92,6 +105,18 @@ impl Signin {
                             .child(state.email.render(Some(&get_text!("landing-signin-form-email"))))
                             .child(state.password.render(Some(&get_text!("landing-signin-form-password"))))
                         }))
+                        .child(html!("label", {
+                            .style("margin-top", "1rem")
+                            .child(html!("input" => web_sys::HtmlInputElement, {
+                                .attr("type", "checkbox")
+                                .with_node!(elem => {
+                                    .event(clone!(state => move |_:events::Change| {
+                                        state.remember_me.set_neq(elem.checked());
+                                    }))
+                                })
+                            }))
+                            .text(&get_text!("landing-signin-form-remember-me"))
+                        }))
 
                         .child(html!("div", {
                             .style("width", "100%")
@@ -102,7 +127,7 @@ impl Signin {
                                 clone!(state => move || {
                                     state.error.clear();
                                     state.loader.load(clone!(state => async move {
-                                        match signin(&state.email.value.get_cloned().unwrap_or_default(), &state.password.value.get_cloned().unwrap_or_default()).await {
+                                        match signin(&state.email.value.get_cloned().unwrap_or_default(), &state.password.value.get_cloned().unwrap_or_default(), state.remember_me.get()).await {
                                             Ok(_) => {
                                                 FRONTEND_ROUTE_AFTER_SIGNIN.go_to_url();
                                             },
@@ -114,16 +139,22 @@ impl Signin {
                                 })
                             ))
                         }))
+                        .child(html!("div", {
+                            .style("margin-top", "1.875rem")
+                            .child(state.challenge.render())
+                        }))
                         .child(html!("div", {
                             .style("margin-top", "1.875rem")
                             .style("align-self", "flex-start")
+                            .style("display", "flex")
+                            .style("gap", "1rem")
                             .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
                                 None,
                                 get_text!("landing-reset-password-button"),
                                 clone!(state => move || {
                                     state.error.clear();
                                     state.loader.load(clone!(state => async move {
-                                        match send_password_reset(Some(&state.email.value.get_cloned().unwrap_or_default())).await {
+                                        match send_password_reset(Some(&state.email.value.get_cloned().unwrap_or_default()), state.challenge.token.get_cloned()).await {
                                             Ok(_) => {
                                                 state.notice.set_neq(Some(SigninNotice::PasswordReset));
                                             },
@@ -131,9 +162,35 @@ impl Signin {
                                                 state.error.set(e);
                                             }
                                         }
+                                        // the token is used up either way
+                                        state.challenge.reset();
                                     }));
                                 })
                             ))
+                            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                                None,
+                                get_text!("landing-magic-link-button"),
+                                clone!(state => move || {
+                                    state.error.clear();
+                                    state.loader.load(clone!(state => async move {
+                                        match send_magic_link(&state.email.value.get_cloned().unwrap_or_default()).await {
+                                            Ok(_) => {
+                                                state.notice.set_neq(Some(SigninNotice::MagicLink));
+                                            },
+                                            Err(e) => {
+                                                state.error.set(e);
+                                            }
+                                        }
+                                    }));
+                                })
+                            ))
+                            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                                None,
+                                get_text!("landing-recovery-code-link-button"),
+                                || {
+                                    Route::Landing(Landing::Auth(AuthRoute::RecoveryCode)).go_to_url();
+                                }
+                            ))
                         }))
                     }))
                     .child(html!("div", {
@@ -150,46 +207,7 @@ impl Signin {
                         .style("flex-direction", "column")
                         .style("justify-content", "center")
                         .class(&*BUTTONS)
-                        .child(OutlineButton::new(false).render(
-                            Some(html!("img", {
-                                .style("height", "2rem")
-                                .attr("src", &CONFIG.app_image_url("google-logo.svg"))
-                            })),
-                            get_text!("landing-signin-google-button"),
-                            clone!(state => move || {
-                                state.loader.load(clone!(state => async move {
-                                    state.error.clear();
-                                    match openid_connect(OpenIdProvider::Google).await {
-                                        Ok(_) => {
-                                            // openid_connect will redirect
-                                        },
-                                        Err(e) => {
-                                            state.error.set(e);
-                                        }
-                                    }
-                                }));
-                            })
-                        ))
-                        .child(OutlineButton::new(false).render(
-                            Some(html!("img", {
-                                .style("height", "2rem")
-                                .attr("src", &CONFIG.app_image_url("facebook-logo.svg"))
-                            })),
-                            get_text!("landing-signin-facebook-button"),
-                            clone!(state => move || {
-                                state.loader.load(clone!(state => async move {
-                                    state.error.clear();
-                                    match openid_connect(OpenIdProvider::Facebook).await {
-                                        Ok(_) => {
-                                            // openid_connect will redirect
-                                        },
-                                        Err(e) => {
-                                            state.error.set(e);
-                                        }
-                                    }
-                                }));
-                            })
-                        ))
+                        .child(OpenIdButtons::new(OpenIdButtonsKind::Signin, None, state.error.clone()).render())
                     }))
                 }))
                 .child(html!("div", {
@@ -0,0 +1,113 @@
+use dominator_helpers::futures::AsyncLoader;
+use wasm_bindgen_futures::spawn_local;
+use super::{terms_accept, terms_status};
+use crate::{atoms::buttons::{ButtonSize, OutlineButton, Squareish1Button}, prelude::*};
+
+// Shown when the terms changed since the user last accepted them
+// every fully protected route fails with TermsNotAccepted until they do
+pub(super) struct TermsAccept {
+    pub error: ApiErrorDisplay,
+    // the version being shown, which is the one that gets accepted
+    pub version: Mutable<Option<u32>>,
+    pub loader: AsyncLoader,
+}
+
+impl TermsAccept {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            error: ApiErrorDisplay::new(),
+            version: Mutable::new(None),
+            loader: AsyncLoader::new(),
+        })
+    }
+
+    pub fn render(self: Arc<Self>) -> Dom {
+        let state = self;
+        static CONTAINER:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("display", "flex")
+                .style("flex-direction", "column")
+                .style("align-items", "center")
+                .style("justify-content", "center")
+                .style("gap", "1.875rem")
+            }
+        });
+
+        static ERROR_MESSAGE:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("color", ColorSemantic::Error.to_str())
+            }
+        });
+
+        static LINK_UNDERLINE:Lazy<String> = Lazy::new(|| {
+            class! {
+                .style("text-decoration", "underline")
+            }
+        });
+
+        html!("div", {
+            .class(&*CONTAINER)
+            .future(clone!(state => async move {
+                match terms_status().await {
+                    Ok(res) => {
+                        state.version.set_neq(Some(res.version));
+                    },
+                    Err(e) => {
+                        state.error.set(e);
+                    }
+                }
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .class(&*TEXT_WEIGHT_BOLD)
+                .class(&*ERROR_MESSAGE)
+                .text_signal(state.error.text_signal())
+            }))
+            .child(html!("div", {
+                .class(&*TEXT_SIZE_LG)
+                .text(&get_text!("landing-terms-updated"))
+            }))
+            .child(html!("a", {
+                .attr("href", CONFIG.terms_url)
+                .attr("target", "_blank")
+                .class(&*TEXT_SIZE_LG)
+                .class(&*LINK_UNDERLINE)
+                .text(&get_text!("landing-terms-link"))
+            }))
+            .child_signal(state.version.signal().map(clone!(state => move |version| {
+                version.map(|version| {
+                    Squareish1Button::new().render(
+                        get_text!("landing-terms-accept-button"),
+                        clone!(state => move || {
+                            state.error.clear();
+                            state.loader.load(clone!(state => async move {
+                                if let Err(e) = terms_accept(version).await {
+                                    state.error.set(e);
+                                }
+                            }));
+                        })
+                    )
+                })
+            })))
+            .child(OutlineButton::new(true).set_size(ButtonSize::Sm).render(
+                None,
+                get_text!("landing-terms-decline-button"),
+                || {
+                    spawn_local(async {
+                        match AUTH.signout().await {
+                            Ok(Some(logout_url)) => {
+                                web_sys::window().unwrap_ext().location().assign(&logout_url).unwrap_ext();
+                                return;
+                            },
+                            Ok(None) => {},
+                            Err(err) => {
+                                log::error!("{:?}", err);
+                            }
+                        }
+                        Route::Landing(Landing::Welcome).go_to_url();
+                    });
+                }
+            ))
+        })
+    }
+}
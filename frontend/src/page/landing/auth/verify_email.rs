@@ -1,11 +1,13 @@
 use dominator_helpers::futures::AsyncLoader;
 use shared::{auth::FRONTEND_ROUTE_AFTER_SIGNIN, backend::route::OpenIdProvider};
-use super::{send_email_validation, confirm_email_validation};
-use crate::{atoms::{buttons::Squareish1Button, input::TextInput}, prelude::*};
+use super::{send_email_validation, confirm_email_validation, confirm_email_validation_code};
+use crate::{atoms::{buttons::Squareish1Button, input::{TextInput, TextInputKind}}, prelude::*};
 
 pub(super) struct VerifyEmailWaiting {
     pub error: ApiErrorDisplay,
     pub resent: Mutable<bool>,
+    // the code from the email, for when it's read on another device
+    pub code: TextInput,
     pub loader: AsyncLoader,
 }
 
@@ -14,6 +16,7 @@ impl VerifyEmailWaiting {
         Arc::new(Self {
             error: ApiErrorDisplay::new(),
             resent: Mutable::new(false),
+            code: TextInput::new(TextInputKind::Any),
             loader: AsyncLoader::new(),
         })
     }
@@ -46,6 +49,23 @@ impl VerifyEmailWaiting {
                 .style("margin-bottom", "1rem")
                 .text(&get_text!("landing-go-verify-email"))
             }))
+            .child(html!("div", {
+                .class(&*INPUTS)
+                .style("margin-bottom", "1.875rem")
+                .child(state.code.render(Some(&get_text!("landing-verify-email-code"))))
+                .child(Squareish1Button::new().render(
+                    get_text!("landing-verify-email-code-button"),
+                    clone!(state => move || {
+                        state.error.clear();
+                        let code = state.code.value.get_cloned().unwrap_or_default();
+                        state.loader.load(clone!(state => async move {
+                            if let Err(e) = confirm_email_validation_code(code).await {
+                                state.error.set(e);
+                            }
+                        }));
+                    })
+                ))
+            }))
             .child(Squareish1Button::new().render(
                 get_text!("landing-resend-button"),
                 clone!(state => move || {
@@ -1,3 +1,3 @@
 pub mod landing;
 pub mod not_found;
-pub mod dashboard;
\ No newline at end of file
+pub mod dashboard;pub mod impersonation;
@@ -10,6 +10,7 @@ use crate::{auth::AuthPhase, page::{
             self,
             DashboardPage,
         }, 
+        impersonation::ImpersonationBanner,
         landing::LandingPage, 
         not_found::NotFoundPage 
     }, prelude::*
@@ -53,13 +54,14 @@ pub fn render() -> Dom {
 
     let top_level_route_sig = Route::signal().map(|route| {
         let requires_auth = route.requires_auth();
+        let required_permission = route.required_permission();
         let top_level = match route {
             Route::Landing(_) => TopLevelRoute::Landing,
             Route::Dashboard(_) => TopLevelRoute::Dashboard,
             Route::NotFound(_) => TopLevelRoute::NotFound,
         };
 
-        (top_level, requires_auth)
+        (top_level, requires_auth, required_permission)
     }).dedupe();
 
     let top_level_sig = map_ref! {
@@ -75,7 +77,11 @@ pub fn render() -> Dom {
     html!("div", {
         .style("width", "100%")
         .style("height", "100%")
-        .child_signal(top_level_sig.map(|((route, requires_auth), auth, _)| {
+        .apply(|dom| match AUTH.impersonation() {
+            Some(impersonation) => dom.child(ImpersonationBanner::new(impersonation).render()),
+            None => dom
+        })
+        .child_signal(top_level_sig.map(|((route, requires_auth, required_permission), auth, _)| {
             // Gate auth access
             match auth {
                 AuthPhase::Init => {
@@ -91,6 +97,16 @@ pub fn render() -> Dom {
                     Route::Landing(Landing::Auth(AuthRoute::VerifyEmailWaiting)).go_to_url();
                     return None;
                 },
+                // Same for when the terms have changed, they need to be accepted again first
+                AuthPhase::TermsNotAccepted if requires_auth => {
+                    Route::Landing(Landing::Auth(AuthRoute::TermsAccept)).go_to_url();
+                    return None;
+                },
+                // User is logged in, but their roles don't allow this page
+                AuthPhase::Authenticated if required_permission.is_some_and(|permission| !AUTH.has_permission(permission)) => {
+                    Route::NotFound(NotFoundReason::NoAuth).go_to_url();
+                    return None;
+                },
                 // everything else is allowed
                 _ => {}
             }
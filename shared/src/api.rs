@@ -7,6 +7,12 @@
 /// are all in sync, across frontend and backend and generated documentation
 /// and that any changes are caught at compile time
 pub mod auth;
+pub mod account;
+pub mod admin;
+pub mod api_token;
+pub mod invite;
+pub mod recovery_code;
+pub mod organization;
 
 use serde::{de::DeserializeOwned, Serialize};
 
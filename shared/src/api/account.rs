@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{api::{api_token::ApiTokenInfo, auth::AuthIdentity, organization::OrganizationInfo}, backend::route::{AccountRoute, Route}, user::{Role, UserId, UserProfile}};
+
+use super::{ApiBoth, ApiEmpty, ApiReq, ApiRes, Method};
+
+/// Request account deletion
+/// the account is only deleted after a grace period, and can be cancelled until then
+pub struct AccountDelete { }
+
+impl ApiBoth for AccountDelete {
+    const ROUTE: Route = Route::Account(AccountRoute::Delete);
+
+    type Req = AccountDeleteRequest;
+    type Res = AccountDeleteResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountDeleteRequest {
+    // the same client-side hash as signin
+    // may be omitted if the user signed in very recently (e.g. openid users who never set a password)
+    pub password: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountDeleteResponse {
+    // unix timestamp in milliseconds
+    pub delete_after: u64,
+}
+
+/// Cancel a pending account deletion
+pub struct AccountDeleteCancel { }
+
+impl ApiEmpty for AccountDeleteCancel {
+    const ROUTE: Route = Route::Account(AccountRoute::DeleteCancel);
+
+    const METHOD: Method = Method::Post;
+}
+
+/// Export all the personal data we store for the account
+pub struct AccountExport { }
+
+impl ApiRes for AccountExport {
+    const ROUTE: Route = Route::Account(AccountRoute::Export);
+
+    type Res = AccountExportResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountExportResponse {
+    pub account: AccountExportAccount,
+    pub identities: Vec<AuthIdentity>,
+    // without the secrets, which aren't stored
+    pub api_tokens: Vec<ApiTokenInfo>,
+    pub roles: Vec<Role>,
+    pub auth_events: Vec<AuthEventInfo>,
+    pub profile: Option<UserProfile>,
+    pub organizations: Vec<OrganizationInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountExportAccount {
+    pub uid: UserId,
+    pub email: String,
+    pub email_verified: bool,
+    pub created_at: String,
+    // unix timestamp in milliseconds, if a deletion is pending
+    pub delete_after: Option<u64>,
+}
+
+/// The user's profile, None if it was never set (e.g. a password registration)
+pub struct AccountProfile { }
+
+impl ApiRes for AccountProfile {
+    const ROUTE: Route = Route::Account(AccountRoute::Profile);
+
+    type Res = AccountProfileResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountProfileResponse {
+    pub profile: Option<UserProfile>,
+}
+
+/// Replace the user's profile
+/// after this, signing in with an openid provider no longer overwrites it
+pub struct AccountProfileUpdate { }
+
+impl ApiReq for AccountProfileUpdate {
+    const ROUTE: Route = Route::Account(AccountRoute::ProfileUpdate);
+
+    type Req = AccountProfileUpdateRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountProfileUpdateRequest {
+    pub profile: UserProfile,
+}
+
+/// The user's own recent security activity (signins, password resets, etc.)
+pub struct AccountSecurityEvents { }
+
+impl ApiRes for AccountSecurityEvents {
+    const ROUTE: Route = Route::Account(AccountRoute::SecurityEvents);
+
+    type Res = AccountSecurityEventsResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccountSecurityEventsResponse {
+    // newest first
+    pub events: Vec<AuthEventInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuthEventInfo {
+    pub kind: AuthEventKind,
+    // e.g. the failure reason, or the signin method
+    pub detail: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthEventKind {
+    Register,
+    SigninSuccess,
+    SigninFailure,
+    Signout,
+    PasswordResetRequested,
+    PasswordResetCompleted,
+    EmailVerified,
+    OpenIdLinked,
+    OpenIdUnlinked,
+    SessionRevoked,
+    RecoveryCodesGenerated,
+    RecoveryCodeUsed,
+}
+
+impl AuthEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Register => "register",
+            Self::SigninSuccess => "signin_success",
+            Self::SigninFailure => "signin_failure",
+            Self::Signout => "signout",
+            Self::PasswordResetRequested => "password_reset_requested",
+            Self::PasswordResetCompleted => "password_reset_completed",
+            Self::EmailVerified => "email_verified",
+            Self::OpenIdLinked => "open_id_linked",
+            Self::OpenIdUnlinked => "open_id_unlinked",
+            Self::SessionRevoked => "session_revoked",
+            Self::RecoveryCodesGenerated => "recovery_codes_generated",
+            Self::RecoveryCodeUsed => "recovery_code_used",
+        }
+    }
+}
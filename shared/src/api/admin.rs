@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{backend::route::{AdminRoute, Route}, user::{Role, UserId}};
+
+use super::{account::AccountExportResponse, ApiBoth, ApiReq, ApiRes, Method};
+
+/// Delete any account immediately, no grace period
+pub struct AdminAccountDelete { }
+
+impl ApiReq for AdminAccountDelete {
+    const ROUTE: Route = Route::Admin(AdminRoute::AccountDelete);
+
+    type Req = AdminAccountRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+/// Export the personal data of any account
+pub struct AdminAccountExport { }
+
+impl ApiBoth for AdminAccountExport {
+    const ROUTE: Route = Route::Admin(AdminRoute::AccountExport);
+
+    type Req = AdminAccountRequest;
+    type Res = AccountExportResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AdminAccountRequest {
+    pub uid: UserId,
+}
+
+/// List every role that's been granted, to anyone
+pub struct AdminRoleList { }
+
+impl ApiRes for AdminRoleList {
+    const ROUTE: Route = Route::Admin(AdminRoute::RoleList);
+
+    type Res = AdminRoleListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AdminRoleListResponse {
+    pub roles: Vec<AdminRoleInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AdminRoleInfo {
+    pub uid: UserId,
+    pub role: Role,
+    pub created_at: String,
+}
+
+/// Grant a role to any account
+pub struct AdminRoleGrant { }
+
+impl ApiReq for AdminRoleGrant {
+    const ROUTE: Route = Route::Admin(AdminRoute::RoleGrant);
+
+    type Req = AdminRoleRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+/// Revoke a role from any account
+pub struct AdminRoleRevoke { }
+
+impl ApiReq for AdminRoleRevoke {
+    const ROUTE: Route = Route::Admin(AdminRoute::RoleRevoke);
+
+    type Req = AdminRoleRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AdminRoleRequest {
+    pub uid: UserId,
+    pub role: Role,
+}
+
+/// Start impersonating any account
+/// the token is sent with AUTH_IMPERSONATION_TOKEN_ID_NAME instead of being set as a cookie
+pub struct AdminImpersonationStart { }
+
+impl ApiBoth for AdminImpersonationStart {
+    const ROUTE: Route = Route::Admin(AdminRoute::ImpersonationStart);
+
+    type Req = AdminAccountRequest;
+    type Res = AdminImpersonationStartResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AdminImpersonationStartResponse {
+    pub token_id: String,
+    pub token_key: String,
+    // unix timestamp in milliseconds, it isn't extended by use
+    pub expires_at: u64,
+}
+
+/// The most recent impersonation audit entries, newest first
+pub struct AdminImpersonationLog { }
+
+impl ApiRes for AdminImpersonationLog {
+    const ROUTE: Route = Route::Admin(AdminRoute::ImpersonationLog);
+
+    type Res = AdminImpersonationLogResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AdminImpersonationLogResponse {
+    pub entries: Vec<AdminImpersonationLogEntry>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AdminImpersonationLogEntry {
+    pub actor_uid: UserId,
+    pub target_uid: UserId,
+    pub event: ImpersonationEvent,
+    // the api route that was called, for requests
+    pub route: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpersonationEvent {
+    Start,
+    Request,
+    Stop,
+}
+
+impl ImpersonationEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Request => "request",
+            Self::Stop => "stop",
+        }
+    }
+}
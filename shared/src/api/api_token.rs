@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backend::route::{ApiTokenRoute, ApiTokenScope, Route};
+
+use super::{ApiBoth, ApiReq, ApiRes, Method};
+
+/// Create a personal api token, for non-browser clients
+/// sent as `Authorization: Bearer <token>`
+pub struct ApiTokenCreate { }
+
+impl ApiBoth for ApiTokenCreate {
+    const ROUTE: Route = Route::ApiToken(ApiTokenRoute::Create);
+
+    type Req = ApiTokenCreateRequest;
+    type Res = ApiTokenCreateResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiTokenCreateRequest {
+    pub name: String,
+    pub scopes: Vec<ApiTokenScope>,
+    // never expires if not set
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiTokenCreateResponse {
+    // the full token, this is the only time it's ever returned
+    pub token: String,
+    pub info: ApiTokenInfo,
+}
+
+/// List the account's api tokens (without the secrets)
+pub struct ApiTokenList { }
+
+impl ApiRes for ApiTokenList {
+    const ROUTE: Route = Route::ApiToken(ApiTokenRoute::List);
+
+    type Res = ApiTokenListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiTokenListResponse {
+    pub tokens: Vec<ApiTokenInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub created_at: String,
+    // unix timestamps in milliseconds
+    pub last_used_at: Option<u64>,
+    pub expires_at: Option<u64>,
+}
+
+/// Revoke an api token, it stops working immediately
+pub struct ApiTokenRevoke { }
+
+impl ApiReq for ApiTokenRevoke {
+    const ROUTE: Route = Route::ApiToken(ApiTokenRoute::Revoke);
+
+    type Req = ApiTokenRevokeRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiTokenRevokeRequest {
+    pub id: String,
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{backend::route::{AuthRoute, OpenIdProvider, Route}, user::UserId};
+use crate::{auth::PasswordParams, backend::route::{AuthRoute, OpenIdProvider, Route}, user::{Role, UserId}};
 
 use super::{ApiBoth, ApiEmpty, ApiEmptyDynRoute, ApiReq, ApiRes, Method};
 
@@ -20,6 +20,15 @@ impl ApiBoth for AuthSignin {
 pub struct AuthSigninRequest {
     pub email: String,
     pub password: String,
+    // stay signed in after the browser is closed
+    #[serde(default)]
+    pub remember_me: bool,
+    // the same password hashed with the upgrade params from AuthPasswordParams, if there were any
+    #[serde(default)]
+    pub upgrade_password: Option<String>,
+    // the salt from those upgrade params, echoed back
+    #[serde(default)]
+    pub upgrade_salt: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -32,9 +41,27 @@ pub struct AuthSigninResponse {
 //// Signin
 pub struct AuthSignout { }
 
-impl ApiEmpty for AuthSignout {
+impl ApiRes for AuthSignout {
     const ROUTE:Route = Route::Auth(AuthRoute::Signout);
 
+    type Res = AuthSignoutResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthSignoutResponse {
+    // if signed in through an openid provider that's configured for it, the client should go here
+    // to sign out of the provider too, which then redirects back to the frontend
+    pub logout_url: Option<String>,
+}
+
+/// Stop impersonating, called with the impersonation token
+pub struct AuthImpersonationStop { }
+
+impl ApiEmpty for AuthImpersonationStop {
+    const ROUTE:Route = Route::Auth(AuthRoute::ImpersonationStop);
+
     const METHOD: Method = Method::Post;
 }
 
@@ -53,6 +80,14 @@ impl ApiBoth for AuthRegister {
 pub struct AuthRegisterRequest {
     pub email: String,
     pub password: String,
+    // random, generated by the client (see PasswordParams)
+    pub salt: String,
+    // required if registration is invite-only, see AuthInviteCheck
+    #[serde(default)]
+    pub invite_code: Option<String>,
+    // from the bot challenge widget, required if the backend's CHALLENGE config lists this route
+    #[serde(default)]
+    pub challenge_token: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -75,6 +110,7 @@ impl ApiRes for AuthCheck {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct AuthCheckResponse{
     pub uid: UserId,
+    pub roles: Vec<Role>,
 }
 
 /// (re) Send email validation
@@ -102,6 +138,56 @@ pub struct AuthConfirmVerifyEmailRequest {
     pub oob_token_key: String,
 }
 
+/// Confirm email validation with the code from the email, instead of the link
+/// only for the signed-in user the code was sent to
+pub struct AuthConfirmVerifyEmailCode { }
+impl ApiReq for AuthConfirmVerifyEmailCode {
+    const ROUTE: Route = Route::Auth(AuthRoute::ConfirmEmailValidationCode);
+
+    type Req = AuthConfirmVerifyEmailCodeRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthConfirmVerifyEmailCodeRequest {
+    pub code: String,
+}
+
+/// The current terms version, and the latest one the signed-in user accepted
+/// fully protected routes fail with TermsNotAccepted until they match
+pub struct AuthTermsStatus { }
+impl ApiRes for AuthTermsStatus {
+    const ROUTE: Route = Route::Auth(AuthRoute::TermsStatus);
+
+    type Res = AuthTermsStatusResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthTermsStatusResponse {
+    pub version: u32,
+    // None if the user never accepted any version
+    pub accepted_version: Option<u32>,
+}
+
+/// Accept the terms, the version is the one that was shown to the user
+/// it must be the current one, an outdated page can't accept on the user's behalf
+pub struct AuthTermsAccept { }
+impl ApiReq for AuthTermsAccept {
+    const ROUTE: Route = Route::Auth(AuthRoute::TermsAccept);
+
+    type Req = AuthTermsAcceptRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthTermsAcceptRequest {
+    pub version: u32,
+}
+
 /// Send password reset
 pub struct AuthSendResetPasswordAny { }
 impl ApiReq for AuthSendResetPasswordAny {
@@ -114,7 +200,10 @@ impl ApiReq for AuthSendResetPasswordAny {
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct AuthSendResetPasswordRequestAny { 
-    pub email: String
+    pub email: String,
+    // same as AuthRegisterRequest::challenge_token
+    #[serde(default)]
+    pub challenge_token: Option<String>,
 }
 
 pub struct AuthSendResetPasswordMe { }
@@ -140,6 +229,8 @@ pub struct AuthConfirmResetPasswordRequest {
     pub oob_token_id: String,
     pub oob_token_key: String,
     pub password: String,
+    // a new password gets a new random salt too
+    pub salt: String,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -170,6 +261,144 @@ pub struct AuthCheckResetPasswordResponse {
     pub email: String,
 }
 
+/// Invite check
+/// whether an invite code can still be used, for the register page to show before the user fills it in
+pub struct AuthInviteCheck { }
+impl ApiBoth for AuthInviteCheck {
+    const ROUTE: Route = Route::Auth(AuthRoute::InviteCheck);
+
+    type Req = AuthInviteCheckRequest;
+    type Res = AuthInviteCheckResponse;
+
+    const METHOD: Method = Method::Post;
+}
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthInviteCheckRequest {
+    pub code: String,
+}
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthInviteCheckResponse {
+    // set if the invite is only for this email
+    pub email: Option<String>,
+}
+
+/// Password params
+/// which argon2 params the client must hash the password with, before signin (or register, etc.)
+/// an unknown email gets the current params, and a fake salt that's always the same for that email
+pub struct AuthPasswordParams { }
+impl ApiBoth for AuthPasswordParams {
+    const ROUTE: Route = Route::Auth(AuthRoute::PasswordParams);
+
+    type Req = AuthPasswordParamsRequest;
+    type Res = AuthPasswordParamsResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthPasswordParamsRequest {
+    pub email: String
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthPasswordParamsResponse {
+    // what the account's password was hashed with
+    pub params: PasswordParams,
+    // if the account's params are outdated, hash with these too on signin (as upgrade_password)
+    pub upgrade: Option<PasswordParams>,
+}
+
+/// Use a recovery code, for when the email (and so any reset link) is out of reach
+/// it doesn't sign in, it signs out everywhere and returns a password reset token
+/// which the frontend uses as if it came from a reset link
+pub struct AuthSigninRecoveryCode { }
+impl ApiBoth for AuthSigninRecoveryCode {
+    const ROUTE: Route = Route::Auth(AuthRoute::SigninRecoveryCode);
+
+    type Req = AuthSigninRecoveryCodeRequest;
+    type Res = AuthSigninRecoveryCodeResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthSigninRecoveryCodeRequest {
+    pub email: String,
+    pub code: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthSigninRecoveryCodeResponse {
+    pub oob_token_id: String,
+    pub oob_token_key: String,
+}
+
+/// Send magic link
+/// emails a one-time signin link, for passwordless signin
+pub struct AuthSendMagicLink { }
+impl ApiReq for AuthSendMagicLink {
+    const ROUTE: Route = Route::Auth(AuthRoute::SendMagicLink);
+
+    type Req = AuthSendMagicLinkRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthSendMagicLinkRequest {
+    pub email: String
+}
+
+/// Confirm magic link
+/// exchanges the emailed link for a signin, same as AuthSignin
+pub struct AuthConfirmMagicLink { }
+impl ApiBoth for AuthConfirmMagicLink {
+    const ROUTE: Route = Route::Auth(AuthRoute::ConfirmMagicLink);
+
+    type Req = AuthConfirmMagicLinkRequest;
+    type Res = AuthConfirmMagicLinkResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthConfirmMagicLinkRequest {
+    pub oob_token_id: String,
+    pub oob_token_key: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthConfirmMagicLinkResponse {
+    pub uid: UserId,
+    pub email_verified: bool,
+    pub auth_key: String,
+}
+
+/// OpenId Providers
+/// lists the configured providers, so the frontend knows which buttons to show
+pub struct AuthOpenIdProviders {}
+impl ApiRes for AuthOpenIdProviders {
+    const ROUTE: Route = Route::Auth(AuthRoute::OpenIdProviders);
+
+    type Res = AuthOpenIdProvidersResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthOpenIdProvidersResponse {
+    pub providers: Vec<AuthOpenIdProviderInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuthOpenIdProviderInfo {
+    pub provider: OpenIdProvider,
+    // human-readable, e.g. "Google"
+    pub name: String,
+    // absolute url, or relative to the frontend media root
+    pub icon: Option<String>,
+}
+
 /// OpenId Connect
 pub struct AuthOpenIdConnect {}
 impl ApiBoth for AuthOpenIdConnect {
@@ -184,6 +413,10 @@ impl ApiBoth for AuthOpenIdConnect {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct AuthOpenIdConnectRequest {
     pub provider: OpenIdProvider,
+    // kept in the openid session, in case finalizing it registers a new account
+    // ignored when linking
+    #[serde(default)]
+    pub invite_code: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -197,7 +430,7 @@ pub struct AuthOpenIdAccessTokenHook {
 }
 impl ApiEmptyDynRoute for AuthOpenIdAccessTokenHook {
     fn route(&self) -> Route {
-        Route::Auth(AuthRoute::OpenIdAccessTokenHook(self.provider))
+        Route::Auth(AuthRoute::OpenIdAccessTokenHook(self.provider.clone()))
     }
 
     const METHOD: Method = Method::Post;
@@ -219,6 +452,10 @@ impl ApiBoth for AuthOpenIdFinalizeExec {
 pub struct AuthOpenIdFinalizeRequest {
     pub session_id: String,
     pub session_key: String,
+    // proof of the existing account, only needed (and checked) when exec reports an email collision
+    // it's the client-side hash, same as signin
+    #[serde(default)]
+    pub password: Option<String>,
 } 
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -242,5 +479,82 @@ impl ApiBoth for AuthOpenIdFinalizeQuery {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct AuthOpenIdFinalizeQueryResponse {
     pub email: String,
-    pub user_exists: bool,
+    pub provider: OpenIdProvider,
+    pub status: AuthOpenIdFinalizeStatus,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum AuthOpenIdFinalizeStatus {
+    // the identity is already linked to an account
+    Signin,
+    // no account has this identity or its email, a new one will be created
+    Register,
+    // another account has this email, so proof of that account is needed before linking
+    EmailCollision,
+    // the session was started from AuthOpenIdLinkConnect, finalize with AuthOpenIdLinkFinalize
+    Link,
+}
+
+/// OpenId Link Connect
+/// like AuthOpenIdConnect, but links the identity to the signed-in account
+pub struct AuthOpenIdLinkConnect {}
+impl ApiBoth for AuthOpenIdLinkConnect {
+    const ROUTE: Route = Route::Auth(AuthRoute::OpenIdLinkConnect);
+
+    type Req = AuthOpenIdConnectRequest;
+    type Res = AuthOpenIdConnectResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+/// OpenId Link Finalize
+pub struct AuthOpenIdLinkFinalize {}
+impl ApiReq for AuthOpenIdLinkFinalize {
+    const ROUTE: Route = Route::Auth(AuthRoute::OpenIdLinkFinalize);
+
+    type Req = AuthOpenIdFinalizeRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+/// Identities
+/// the external identities linked to the signed-in account
+pub struct AuthIdentities {}
+impl ApiRes for AuthIdentities {
+    const ROUTE: Route = Route::Auth(AuthRoute::Identities);
+
+    type Res = AuthIdentitiesResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthIdentitiesResponse {
+    pub identities: Vec<AuthIdentity>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuthIdentity {
+    pub provider: OpenIdProvider,
+    // the provider's stable id for the user
+    pub subject: String,
+    // the email the provider reported when it was linked
+    pub email: String,
+    pub created_at: String,
+}
+
+/// Identity Unlink
+pub struct AuthIdentityUnlink {}
+impl ApiReq for AuthIdentityUnlink {
+    const ROUTE: Route = Route::Auth(AuthRoute::IdentityUnlink);
+
+    type Req = AuthIdentityUnlinkRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthIdentityUnlinkRequest {
+    pub provider: OpenIdProvider,
+    pub subject: String,
 } 
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{backend::route::{InviteRoute, Route}, user::UserId};
+
+use super::{ApiBoth, ApiReq, ApiRes, Method};
+
+/// Create an invite code, for registering while registration is gated (see the backend's REGISTRATION_POLICY)
+pub struct InviteCreate { }
+
+impl ApiBoth for InviteCreate {
+    const ROUTE: Route = Route::Invite(InviteRoute::Create);
+
+    type Req = InviteCreateRequest;
+    type Res = InviteCreateResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct InviteCreateRequest {
+    // if set, only this email can register with the invite (and the register page is pre-filled with it)
+    pub email: Option<String>,
+    // how many accounts can register with it, at least 1
+    pub max_uses: u32,
+    // never expires if not set
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct InviteCreateResponse {
+    pub info: InviteInfo,
+}
+
+/// List all invites, newest first
+pub struct InviteList { }
+
+impl ApiRes for InviteList {
+    const ROUTE: Route = Route::Invite(InviteRoute::List);
+
+    type Res = InviteListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct InviteListResponse {
+    pub invites: Vec<InviteInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct InviteInfo {
+    pub code: String,
+    // the frontend register page for this invite, to send to whoever is invited
+    pub url: String,
+    pub inviter_uid: UserId,
+    pub email: Option<String>,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub created_at: String,
+    // unix timestamp in milliseconds
+    pub expires_at: Option<u64>,
+}
+
+/// Revoke an invite, it can't be used from then on
+pub struct InviteRevoke { }
+
+impl ApiReq for InviteRevoke {
+    const ROUTE: Route = Route::Invite(InviteRoute::Revoke);
+
+    type Req = InviteRevokeRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct InviteRevokeRequest {
+    pub code: String,
+}
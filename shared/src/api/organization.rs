@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{backend::route::{OpenIdProvider, OrganizationRoute, Route}, user::{OrganizationRole, UserId}};
+
+use super::{ApiBoth, ApiReq, ApiRes, Method};
+
+/// Create an organization, the caller becomes its owner and it becomes their active one
+pub struct OrganizationCreate { }
+
+impl ApiBoth for OrganizationCreate {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::Create);
+
+    type Req = OrganizationCreateRequest;
+    type Res = OrganizationCreateResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationCreateRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationCreateResponse {
+    pub info: OrganizationInfo,
+}
+
+/// List the organizations the user is a member of
+pub struct OrganizationList { }
+
+impl ApiRes for OrganizationList {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::List);
+
+    type Res = OrganizationListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationListResponse {
+    pub organizations: Vec<OrganizationInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OrganizationInfo {
+    pub id: String,
+    pub name: String,
+    // the user's own role in it
+    pub role: OrganizationRole,
+    // whether it's the user's active organization
+    pub active: bool,
+    pub created_at: String,
+}
+
+/// Set the active organization, which the organization routes below act on
+pub struct OrganizationSwitch { }
+
+impl ApiReq for OrganizationSwitch {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::Switch);
+
+    type Req = OrganizationSwitchRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationSwitchRequest {
+    // None to have no active organization
+    pub id: Option<String>,
+}
+
+/// List the members of the active organization
+pub struct OrganizationMemberList { }
+
+impl ApiRes for OrganizationMemberList {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::MemberList);
+
+    type Res = OrganizationMemberListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationMemberListResponse {
+    pub members: Vec<OrganizationMemberInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OrganizationMemberInfo {
+    pub uid: UserId,
+    pub email: String,
+    pub role: OrganizationRole,
+    // when they joined
+    pub created_at: String,
+}
+
+/// Remove a member from the active organization
+/// anyone can remove themselves (i.e. leave), except the last owner
+pub struct OrganizationMemberRemove { }
+
+impl ApiReq for OrganizationMemberRemove {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::MemberRemove);
+
+    type Req = OrganizationMemberRemoveRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationMemberRemoveRequest {
+    pub uid: UserId,
+}
+
+/// Invite someone to the active organization by email
+/// they get a link, and accept it while signed in with an account on that email
+pub struct OrganizationInviteCreate { }
+
+impl ApiBoth for OrganizationInviteCreate {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::InviteCreate);
+
+    type Req = OrganizationInviteCreateRequest;
+    type Res = OrganizationInviteCreateResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInviteCreateRequest {
+    pub email: String,
+    pub role: OrganizationRole,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInviteCreateResponse {
+    pub info: OrganizationInviteInfo,
+}
+
+/// List the pending invites of the active organization
+pub struct OrganizationInviteList { }
+
+impl ApiRes for OrganizationInviteList {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::InviteList);
+
+    type Res = OrganizationInviteListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInviteListResponse {
+    pub invites: Vec<OrganizationInviteInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OrganizationInviteInfo {
+    pub id: String,
+    pub email: String,
+    pub role: OrganizationRole,
+    pub invited_by: UserId,
+    pub created_at: String,
+    // unix timestamp in milliseconds
+    pub expires_at: u64,
+}
+
+/// Revoke a pending invite, its link stops working
+pub struct OrganizationInviteRevoke { }
+
+impl ApiReq for OrganizationInviteRevoke {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::InviteRevoke);
+
+    type Req = OrganizationInviteRevokeRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInviteRevokeRequest {
+    pub id: String,
+}
+
+/// What an invite link is for, so it can be shown before accepting or declining
+pub struct OrganizationInviteCheck { }
+
+impl ApiBoth for OrganizationInviteCheck {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::InviteCheck);
+
+    type Req = OrganizationInviteTokenRequest;
+    type Res = OrganizationInviteCheckResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+// the OOB token from the invite email link
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInviteTokenRequest {
+    pub oob_token_id: String,
+    pub oob_token_key: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInviteCheckResponse {
+    pub organization_name: String,
+    pub email: String,
+    pub role: OrganizationRole,
+}
+
+/// Join the organization, which then becomes the active one
+pub struct OrganizationInviteAccept { }
+
+impl ApiReq for OrganizationInviteAccept {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::InviteAccept);
+
+    type Req = OrganizationInviteTokenRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+/// Turn the invite down, it can't be used from then on
+pub struct OrganizationInviteDecline { }
+
+impl ApiReq for OrganizationInviteDecline {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::InviteDecline);
+
+    type Req = OrganizationInviteTokenRequest;
+
+    const METHOD: Method = Method::Post;
+}
+
+/// Claim an email domain for the active organization (owners only)
+/// once verified, emails on it can only sign in through the given OpenID provider
+pub struct OrganizationSsoDomainAdd { }
+
+impl ApiBoth for OrganizationSsoDomainAdd {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::SsoDomainAdd);
+
+    type Req = OrganizationSsoDomainAddRequest;
+    type Res = OrganizationSsoDomainResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationSsoDomainAddRequest {
+    pub domain: String,
+    pub provider: OpenIdProvider,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationSsoDomainResponse {
+    pub info: SsoDomainInfo,
+}
+
+/// List the domains the active organization has claimed, verified or not
+pub struct OrganizationSsoDomainList { }
+
+impl ApiRes for OrganizationSsoDomainList {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::SsoDomainList);
+
+    type Res = OrganizationSsoDomainListResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationSsoDomainListResponse {
+    pub domains: Vec<SsoDomainInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SsoDomainInfo {
+    pub domain: String,
+    pub provider: OpenIdProvider,
+    // the DNS TXT record that proves the organization controls the domain
+    pub txt_record_name: String,
+    pub txt_record_value: String,
+    // unix timestamp in milliseconds, None until the TXT record was found
+    pub verified_at: Option<u64>,
+    pub created_at: String,
+}
+
+/// Look up the domain's TXT record, and start enforcing SSO for it if it's there
+/// a domain can only be verified by one organization at a time
+pub struct OrganizationSsoDomainVerify { }
+
+impl ApiBoth for OrganizationSsoDomainVerify {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::SsoDomainVerify);
+
+    type Req = OrganizationSsoDomainRequest;
+    type Res = OrganizationSsoDomainResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationSsoDomainRequest {
+    pub domain: String,
+}
+
+/// Give up the claim, password signin works again for the domain
+pub struct OrganizationSsoDomainRemove { }
+
+impl ApiReq for OrganizationSsoDomainRemove {
+    const ROUTE: Route = Route::Organization(OrganizationRoute::SsoDomainRemove);
+
+    type Req = OrganizationSsoDomainRequest;
+
+    const METHOD: Method = Method::Post;
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backend::route::{RecoveryCodeRoute, Route};
+
+use super::{ApiRes, Method};
+
+/// How many unused recovery codes the account has left
+pub struct RecoveryCodeStatus { }
+
+impl ApiRes for RecoveryCodeStatus {
+    const ROUTE: Route = Route::RecoveryCode(RecoveryCodeRoute::Status);
+
+    type Res = RecoveryCodeStatusResponse;
+
+    const METHOD: Method = Method::Get;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RecoveryCodeStatusResponse {
+    pub remaining: u32,
+    // when the current set was generated, None if there never was one
+    pub created_at: Option<String>,
+}
+
+/// Generate a new set of recovery codes, replacing any old ones
+pub struct RecoveryCodeRegenerate { }
+
+impl ApiRes for RecoveryCodeRegenerate {
+    const ROUTE: Route = Route::RecoveryCode(RecoveryCodeRoute::Regenerate);
+
+    type Res = RecoveryCodeRegenerateResponse;
+
+    const METHOD: Method = Method::Post;
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RecoveryCodeRegenerateResponse {
+    // the codes themselves, this is the only time they're ever returned
+    pub codes: Vec<String>,
+}
@@ -1,6 +1,51 @@
+use serde::{Deserialize, Serialize};
+
 use crate::frontend::route::{Route as FrontendRoute, Dashboard};
 
 pub const AUTH_TOKEN_ID_NAME: &str = "X-EXAMPLE-TOKEN-ID";
 pub const AUTH_TOKEN_KEY_NAME: &str = "X-EXAMPLE-TOKEN-KEY";
+// sent instead of the signin token id while an admin is impersonating a user (the key header is the same)
+// it's never a cookie, so the admin's own session is untouched
+pub const AUTH_IMPERSONATION_TOKEN_ID_NAME: &str = "X-EXAMPLE-IMPERSONATION-TOKEN-ID";
+
+pub const FRONTEND_ROUTE_AFTER_SIGNIN:FrontendRoute = FrontendRoute::Dashboard(Dashboard::Browse);
+
+/// Everything the client needs to derive the password hash that's sent to the server
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PasswordParams {
+    pub kdf: PasswordKdf,
+    // base64 (url safe, no padding) of PASSWORD_SALT_LENGTH random bytes, per account
+    // None for accounts from before per-account salts, which derive it from the email instead
+    pub salt: Option<String>,
+}
+
+pub const PASSWORD_SALT_LENGTH: usize = 16;
+
+/// The argon2id params the client derives the password hash with
+/// each account's password is stored with its version, so the costs can be raised without locking anyone out
+/// never change or remove a version, add a new one at the end instead
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct PasswordKdf {
+    pub version: u32,
+    // memory, in KiB
+    pub m_cost: u32,
+    // iterations
+    pub t_cost: u32,
+    // parallelism
+    pub p_cost: u32,
+}
+
+impl PasswordKdf {
+    pub const ALL: [Self; 1] = [
+        // the argon2 crate's defaults, which is what was used before params were versioned
+        Self { version: 1, m_cost: 19 * 1024, t_cost: 2, p_cost: 1 },
+    ];
+
+    pub fn current() -> Self {
+        Self::ALL[Self::ALL.len() - 1]
+    }
 
-pub const FRONTEND_ROUTE_AFTER_SIGNIN:FrontendRoute = FrontendRoute::Dashboard(Dashboard::Browse);
\ No newline at end of file
+    pub fn from_version(version: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|kdf| kdf.version == version)
+    }
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use wasm_bindgen::JsValue;
 
+use super::route::OpenIdProvider;
+
 #[derive(Serialize, Deserialize, Error, Debug, Clone)]
 pub enum ApiError {
     #[error("{0}")]
@@ -40,8 +42,53 @@ pub enum AuthError {
     EmailAlreadyExists,
     #[error("not authorized")]
     NotAuthorized,
+    #[error("signed in, but missing the permission for this")]
+    Forbidden,
     #[error("invalid signin")]
     InvalidSignin,
     #[error("no such user for password reset")]
     NoUserPasswordReset,
+    #[error("password or recent signin required to confirm")]
+    ConfirmationRequired,
+    #[error("a recent signin is required for this")]
+    RecentSigninRequired,
+    #[error("request origin is not allowed")]
+    InvalidOrigin,
+    #[error("an account with this email already exists, proof of it is required to link")]
+    OpenIdEmailCollision,
+    #[error("this identity is already linked to another account")]
+    OpenIdIdentityAlreadyLinked,
+    #[error("registration is closed")]
+    RegistrationClosed,
+    #[error("registration requires an invite")]
+    InviteRequired,
+    #[error("invalid, expired or used up invite")]
+    InvalidInvite,
+    #[error("registration isn't open to this email's domain")]
+    EmailDomainNotAllowed,
+    #[error("this email address isn't allowed")]
+    EmailNotAllowed,
+    #[error("disposable email addresses aren't allowed")]
+    DisposableEmail,
+    #[error("wrong verification code, {attempts_left} attempts left")]
+    InvalidVerificationCode {
+        attempts_left: u32
+    },
+    #[error("the bot challenge is missing or wasn't passed")]
+    ChallengeFailed,
+    #[error("the current terms need to be accepted")]
+    TermsNotAccepted,
+    #[error("no active organization, create or switch to one first")]
+    NoActiveOrganization,
+    #[error("this organization invite was sent to a different email address")]
+    OrganizationInviteWrongAccount,
+    // the email's domain is claimed by an organization, which only allows signing in through its provider
+    #[error("this email's domain must sign in through {}", provider.as_str())]
+    SsoRequired {
+        provider: OpenIdProvider
+    },
+    #[error("too many attempts, retry after {retry_after_ms}ms")]
+    TooManyAttempts {
+        retry_after_ms: u64
+    },
 }
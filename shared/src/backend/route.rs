@@ -1,26 +1,108 @@
 use serde::{Deserialize, Serialize};
 
+use crate::user::Permission;
+
 #[derive(Debug, Clone)]
 pub enum Route {
     Auth(AuthRoute),
+    Account(AccountRoute),
+    Admin(AdminRoute),
+    ApiToken(ApiTokenRoute),
+    Invite(InviteRoute),
+    RecoveryCode(RecoveryCodeRoute),
+    Organization(OrganizationRoute),
 }
 
 #[derive(Debug, Clone)]
 pub enum AuthRoute {
     Register,
     Signin,
+    SigninRecoveryCode,
+    PasswordParams,
     Check,
     Signout,
     SendEmailValidation,
     ConfirmEmailValidation,
+    ConfirmEmailValidationCode,
     SendPasswordResetAny,
     SendPasswordResetMe,
     ConfirmPasswordReset,
     CheckPasswordReset,
+    SendMagicLink,
+    ConfirmMagicLink,
+    OpenIdProviders,
     OpenIdConnect,
     OpenIdAccessTokenHook(OpenIdProvider),
     OpenIdFinalizeExec,
     OpenIdFinalizeQuery,
+    OpenIdLinkConnect,
+    OpenIdLinkFinalize,
+    Identities,
+    IdentityUnlink,
+    ImpersonationStop,
+    InviteCheck,
+    TermsStatus,
+    TermsAccept,
+}
+
+#[derive(Debug, Clone)]
+pub enum AccountRoute {
+    Delete,
+    DeleteCancel,
+    Export,
+    SecurityEvents,
+    Profile,
+    ProfileUpdate,
+}
+
+#[derive(Debug, Clone)]
+pub enum AdminRoute {
+    AccountDelete,
+    AccountExport,
+    RoleList,
+    RoleGrant,
+    RoleRevoke,
+    ImpersonationStart,
+    ImpersonationLog,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiTokenRoute {
+    Create,
+    List,
+    Revoke,
+}
+
+#[derive(Debug, Clone)]
+pub enum InviteRoute {
+    Create,
+    List,
+    Revoke,
+}
+
+#[derive(Debug, Clone)]
+pub enum RecoveryCodeRoute {
+    Status,
+    Regenerate,
+}
+
+#[derive(Debug, Clone)]
+pub enum OrganizationRoute {
+    Create,
+    List,
+    Switch,
+    MemberList,
+    MemberRemove,
+    InviteCreate,
+    InviteList,
+    InviteRevoke,
+    InviteCheck,
+    InviteAccept,
+    InviteDecline,
+    SsoDomainAdd,
+    SsoDomainList,
+    SsoDomainVerify,
+    SsoDomainRemove,
 }
 
 impl Route {
@@ -37,6 +119,12 @@ impl Route {
 
         match paths {
             ["auth", auth_path @ ..] => AuthRoute::try_from_paths(auth_path).map(Self::Auth),
+            ["account", account_path @ ..] => AccountRoute::try_from_paths(account_path).map(Self::Account),
+            ["admin", admin_path @ ..] => AdminRoute::try_from_paths(admin_path).map(Self::Admin),
+            ["api-token", api_token_path @ ..] => ApiTokenRoute::try_from_paths(api_token_path).map(Self::ApiToken),
+            ["invite", invite_path @ ..] => InviteRoute::try_from_paths(invite_path).map(Self::Invite),
+            ["recovery-code", recovery_code_path @ ..] => RecoveryCodeRoute::try_from_paths(recovery_code_path).map(Self::RecoveryCode),
+            ["organization", organization_path @ ..] => OrganizationRoute::try_from_paths(organization_path).map(Self::Organization),
             _ => None,
         }
     }
@@ -58,6 +146,8 @@ impl Route {
             Route::Auth(auth_route) => match auth_route { 
                 AuthRoute::Register => RouteAuthKind::CookiesOnly,
                 AuthRoute::Signin => RouteAuthKind::CookiesOnly,
+                // doesn't sign in by itself, the password reset it leads to does
+                AuthRoute::SigninRecoveryCode => RouteAuthKind::None,
                 // signout only needs the auth token, as that is the only thing it destroys
                 AuthRoute::Signout => RouteAuthKind::PartialAuthTokenOnly,
                 // sending an email validation requires that the user is signed in
@@ -67,15 +157,139 @@ impl Route {
                 AuthRoute::SendPasswordResetMe => RouteAuthKind::Full,
                 // these use OOB tokens, so no auth token is needed, it's just a click from email
                 AuthRoute::ConfirmEmailValidation => RouteAuthKind::None,
+                // the code is short, so unlike the link it's only good for the signed-in user it was sent to
+                AuthRoute::ConfirmEmailValidationCode => RouteAuthKind::PartialAuthAndUserTokenOnly,
                 // well, actually, this one signs the user in too :P
                 AuthRoute::ConfirmPasswordReset => RouteAuthKind::CookiesOnly,
                 AuthRoute::CheckPasswordReset => RouteAuthKind::None,
+                AuthRoute::SendMagicLink => RouteAuthKind::None,
+                AuthRoute::PasswordParams => RouteAuthKind::None,
+                // like password reset, this signs the user in
+                AuthRoute::ConfirmMagicLink => RouteAuthKind::CookiesOnly,
                 AuthRoute::Check => RouteAuthKind::Full,
+                AuthRoute::OpenIdProviders => RouteAuthKind::None,
                 AuthRoute::OpenIdConnect => RouteAuthKind::None,
                 AuthRoute::OpenIdAccessTokenHook(_) => RouteAuthKind::None,
                 AuthRoute::OpenIdFinalizeExec => RouteAuthKind::CookiesOnly,
                 AuthRoute::OpenIdFinalizeQuery => RouteAuthKind::None,
+                AuthRoute::OpenIdLinkConnect => RouteAuthKind::Full,
+                AuthRoute::OpenIdLinkFinalize => RouteAuthKind::Full,
+                AuthRoute::Identities => RouteAuthKind::Full,
+                AuthRoute::IdentityUnlink => RouteAuthKind::Full,
+                // like signout, only the (impersonation) token itself is destroyed
+                AuthRoute::ImpersonationStop => RouteAuthKind::PartialAuthTokenOnly,
+                // before registering, so there's no account yet
+                AuthRoute::InviteCheck => RouteAuthKind::None,
+                // like send-email-validation, these are how a partially signed-in user gets past the gate
+                AuthRoute::TermsStatus => RouteAuthKind::PartialAuthAndUserTokenOnly,
+                AuthRoute::TermsAccept => RouteAuthKind::PartialAuthAndUserTokenOnly,
             },
+            // deleting or exporting an account is only for fully signed-in users
+            Route::Account(_) => RouteAuthKind::Full,
+            // see required_permission() for the role check, but the user must be fully signed in first
+            Route::Admin(_) => RouteAuthKind::Full,
+            Route::ApiToken(_) => RouteAuthKind::Full,
+            Route::RecoveryCode(_) => RouteAuthKind::Full,
+            Route::Invite(_) => RouteAuthKind::Full,
+            // invites are accepted by a signed-in account on the invited email, so these are Full too
+            Route::Organization(_) => RouteAuthKind::Full,
+        }
+    }
+
+    // authorization on top of auth_kind(), checked against the user's roles
+    // only meaningful for routes that require a fully signed-in user
+    pub fn required_permission(&self) -> Option<Permission> {
+        match self {
+            Route::Admin(admin_route) => Some(match admin_route {
+                AdminRoute::AccountExport => Permission::AccountsRead,
+                AdminRoute::AccountDelete => Permission::AccountsWrite,
+                AdminRoute::RoleList | AdminRoute::RoleGrant | AdminRoute::RoleRevoke => Permission::RolesManage,
+                AdminRoute::ImpersonationStart | AdminRoute::ImpersonationLog => Permission::Impersonate,
+            }),
+            Route::Invite(_) => Some(Permission::InvitesManage),
+            _ => None,
+        }
+    }
+
+    // sensitive routes need a signin that's recent enough (see AUTH_STEP_UP_MAX_AGE in the backend config)
+    // on top of auth_kind(), so a stolen or forgotten session can't be used for them
+    // api tokens and impersonation never count as recent
+    pub fn requires_recent_signin(&self) -> bool {
+        match self {
+            Route::Auth(AuthRoute::OpenIdLinkConnect | AuthRoute::IdentityUnlink) => true,
+            Route::ApiToken(ApiTokenRoute::Create) => true,
+            Route::RecoveryCode(RecoveryCodeRoute::Regenerate) => true,
+            // these decide how everyone with the domain's emails may sign in
+            Route::Organization(OrganizationRoute::SsoDomainVerify | OrganizationRoute::SsoDomainRemove) => true,
+            Route::Admin(AdminRoute::AccountDelete | AdminRoute::RoleGrant | AdminRoute::RoleRevoke | AdminRoute::ImpersonationStart) => true,
+            _ => false,
+        }
+    }
+
+    // whether the user must have accepted the current terms (TERMS_VERSION in the backend config) to call this route
+    // only fully protected routes, and not the ways out: deleting the account (or changing one's mind) and exporting it
+    // shouldn't need agreeing to terms the user may be leaving over
+    pub fn requires_terms(&self) -> bool {
+        match self {
+            Route::Account(AccountRoute::Delete | AccountRoute::DeleteCancel | AccountRoute::Export) => false,
+            _ => self.auth_kind() == RouteAuthKind::Full,
+        }
+    }
+
+    // whether an admin impersonating a user may call this route as them
+    // impersonation is for seeing what the user sees, not for acting on their credentials or account
+    pub fn allows_impersonation(&self) -> bool {
+        match self {
+            // the signout response would clear the admin's own cookie, impersonation-stop is used instead
+            Route::Auth(AuthRoute::Signout) => false,
+            Route::Auth(AuthRoute::ConfirmEmailValidationCode | AuthRoute::TermsAccept | AuthRoute::SendPasswordResetMe | AuthRoute::OpenIdLinkConnect | AuthRoute::OpenIdLinkFinalize | AuthRoute::IdentityUnlink) => false,
+            Route::Account(AccountRoute::Delete | AccountRoute::DeleteCancel | AccountRoute::ProfileUpdate) => false,
+            Route::ApiToken(ApiTokenRoute::Create | ApiTokenRoute::Revoke) => false,
+            Route::RecoveryCode(RecoveryCodeRoute::Regenerate) => false,
+            // no chaining impersonations, or using the user's roles
+            Route::Admin(_) | Route::Invite(_) => false,
+            // looking around is fine, changing memberships isn't
+            Route::Organization(OrganizationRoute::Create | OrganizationRoute::Switch | OrganizationRoute::MemberRemove | OrganizationRoute::InviteCreate | OrganizationRoute::InviteRevoke | OrganizationRoute::InviteAccept | OrganizationRoute::InviteDecline) => false,
+            Route::Organization(OrganizationRoute::SsoDomainAdd | OrganizationRoute::SsoDomainVerify | OrganizationRoute::SsoDomainRemove) => false,
+            _ => true,
+        }
+    }
+
+    // whether responses may be stored by the browser or any cache along the way
+    // anything that sets credentials or returns account data must not be (see the security headers in the backend)
+    pub fn allows_caching(&self) -> bool {
+        match self {
+            Route::Auth(AuthRoute::OpenIdProviders) => true,
+            _ => false,
+        }
+    }
+
+    // headers set on top of the backend's SECURITY_HEADERS for this route, a header with the same name replaces the default
+    // e.g. to loosen the Permissions-Policy or add a Content-Disposition for a route that needs it
+    pub fn security_headers(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            // the whole account's data, if it's ever opened directly it's saved rather than shown
+            Route::Account(AccountRoute::Export) | Route::Admin(AdminRoute::AccountExport) => &[("Content-Disposition", "attachment; filename=\"account-export.json\"")],
+            _ => &[],
+        }
+    }
+
+    // which scope an api token needs to call this route
+    // None means api tokens can't call it at all, only signed-in browser sessions can
+    // (e.g. anything that manages credentials or the account itself)
+    pub fn api_token_scope(&self) -> Option<ApiTokenScope> {
+        match self {
+            Route::Auth(AuthRoute::Check) => Some(ApiTokenScope::AccountRead),
+            Route::Auth(AuthRoute::Identities) => Some(ApiTokenScope::AccountRead),
+            Route::Account(AccountRoute::Export) => Some(ApiTokenScope::AccountRead),
+            Route::Account(AccountRoute::SecurityEvents) => Some(ApiTokenScope::AccountRead),
+            Route::Account(AccountRoute::Profile) => Some(ApiTokenScope::AccountRead),
+            Route::RecoveryCode(RecoveryCodeRoute::Status) => Some(ApiTokenScope::AccountRead),
+            Route::Organization(OrganizationRoute::List | OrganizationRoute::MemberList | OrganizationRoute::InviteList | OrganizationRoute::SsoDomainList) => Some(ApiTokenScope::AccountRead),
+            // the account must still have the permission, this is just whether the token may try
+            Route::Admin(_) => Some(ApiTokenScope::Admin),
+            Route::Invite(_) => Some(ApiTokenScope::Admin),
+            _ => None,
         }
     }
 }
@@ -86,23 +300,119 @@ impl AuthRoute {
         match *paths {
             ["register"] => Some(Self::Register),
             ["signin"] => Some(Self::Signin),
+            ["signin-recovery-code"] => Some(Self::SigninRecoveryCode),
             ["signout"] => Some(Self::Signout),
             ["check"] => Some(Self::Check),
             ["send-email-validation"] => Some(Self::SendEmailValidation),
             ["confirm-email-validation"] => Some(Self::ConfirmEmailValidation),
+            ["confirm-email-validation-code"] => Some(Self::ConfirmEmailValidationCode),
             ["send-password-reset-any"] => Some(Self::SendPasswordResetAny),
             ["send-password-reset-me"] => Some(Self::SendPasswordResetMe),
             ["confirm-password-reset"] => Some(Self::ConfirmPasswordReset),
             ["check-password-reset"] => Some(Self::CheckPasswordReset),
+            ["send-magic-link"] => Some(Self::SendMagicLink),
+            ["password-params"] => Some(Self::PasswordParams),
+            ["confirm-magic-link"] => Some(Self::ConfirmMagicLink),
+            ["openid-providers"] => Some(Self::OpenIdProviders),
             ["openid-connect"] => Some(Self::OpenIdConnect),
             ["openid-access-token-hook", provider] => OpenIdProvider::try_from_str(provider).map(Self::OpenIdAccessTokenHook),
             ["openid-finalize-exec"] => Some(Self::OpenIdFinalizeExec),
             ["openid-finalize-query"] => Some(Self::OpenIdFinalizeQuery),
+            ["openid-link-connect"] => Some(Self::OpenIdLinkConnect),
+            ["openid-link-finalize"] => Some(Self::OpenIdLinkFinalize),
+            ["identities"] => Some(Self::Identities),
+            ["identity-unlink"] => Some(Self::IdentityUnlink),
+            ["impersonation-stop"] => Some(Self::ImpersonationStop),
+            ["invite-check"] => Some(Self::InviteCheck),
+            ["terms-status"] => Some(Self::TermsStatus),
+            ["terms-accept"] => Some(Self::TermsAccept),
             _ => None
         }
     }
+}
+
+impl AccountRoute {
+    pub fn try_from_paths(paths: &[&str]) -> Option<Self> {
+        match *paths {
+            ["delete"] => Some(Self::Delete),
+            ["delete-cancel"] => Some(Self::DeleteCancel),
+            ["export"] => Some(Self::Export),
+            ["security-events"] => Some(Self::SecurityEvents),
+            ["profile"] => Some(Self::Profile),
+            ["profile-update"] => Some(Self::ProfileUpdate),
+            _ => None
+        }
+    }
+}
+
+impl AdminRoute {
+    pub fn try_from_paths(paths: &[&str]) -> Option<Self> {
+        match *paths {
+            ["account-delete"] => Some(Self::AccountDelete),
+            ["account-export"] => Some(Self::AccountExport),
+            ["role-list"] => Some(Self::RoleList),
+            ["role-grant"] => Some(Self::RoleGrant),
+            ["role-revoke"] => Some(Self::RoleRevoke),
+            ["impersonation-start"] => Some(Self::ImpersonationStart),
+            ["impersonation-log"] => Some(Self::ImpersonationLog),
+            _ => None
+        }
+    }
+}
+
+impl ApiTokenRoute {
+    pub fn try_from_paths(paths: &[&str]) -> Option<Self> {
+        match *paths {
+            ["create"] => Some(Self::Create),
+            ["list"] => Some(Self::List),
+            ["revoke"] => Some(Self::Revoke),
+            _ => None
+        }
+    }
+}
+
+impl InviteRoute {
+    pub fn try_from_paths(paths: &[&str]) -> Option<Self> {
+        match *paths {
+            ["create"] => Some(Self::Create),
+            ["list"] => Some(Self::List),
+            ["revoke"] => Some(Self::Revoke),
+            _ => None
+        }
+    }
+}
 
+impl RecoveryCodeRoute {
+    pub fn try_from_paths(paths: &[&str]) -> Option<Self> {
+        match *paths {
+            ["status"] => Some(Self::Status),
+            ["regenerate"] => Some(Self::Regenerate),
+            _ => None
+        }
+    }
+}
 
+impl OrganizationRoute {
+    pub fn try_from_paths(paths: &[&str]) -> Option<Self> {
+        match *paths {
+            ["create"] => Some(Self::Create),
+            ["list"] => Some(Self::List),
+            ["switch"] => Some(Self::Switch),
+            ["member-list"] => Some(Self::MemberList),
+            ["member-remove"] => Some(Self::MemberRemove),
+            ["invite-create"] => Some(Self::InviteCreate),
+            ["invite-list"] => Some(Self::InviteList),
+            ["invite-revoke"] => Some(Self::InviteRevoke),
+            ["invite-check"] => Some(Self::InviteCheck),
+            ["invite-accept"] => Some(Self::InviteAccept),
+            ["invite-decline"] => Some(Self::InviteDecline),
+            ["sso-domain-add"] => Some(Self::SsoDomainAdd),
+            ["sso-domain-list"] => Some(Self::SsoDomainList),
+            ["sso-domain-verify"] => Some(Self::SsoDomainVerify),
+            ["sso-domain-remove"] => Some(Self::SsoDomainRemove),
+            _ => None
+        }
+    }
 }
 
 impl std::fmt::Display for Route {
@@ -111,6 +421,24 @@ impl std::fmt::Display for Route {
             Self::Auth(auth_route) => {
                 format!("auth/{}", auth_route)
             }
+            Self::Account(account_route) => {
+                format!("account/{}", account_route)
+            }
+            Self::Admin(admin_route) => {
+                format!("admin/{}", admin_route)
+            }
+            Self::ApiToken(api_token_route) => {
+                format!("api-token/{}", api_token_route)
+            }
+            Self::Invite(invite_route) => {
+                format!("invite/{}", invite_route)
+            }
+            Self::RecoveryCode(recovery_code_route) => {
+                format!("recovery-code/{}", recovery_code_route)
+            }
+            Self::Organization(organization_route) => {
+                format!("organization/{}", organization_route)
+            }
         };
 
         write!(f, "{}", s)
@@ -121,18 +449,122 @@ impl std::fmt::Display for AuthRoute {
         let s: String = match self {
             Self::Register => "register".to_string(),
             Self::Signin => "signin".to_string(),
+            Self::SigninRecoveryCode => "signin-recovery-code".to_string(),
             Self::Signout => "signout".to_string(),
             Self::Check => "check".to_string(),
             Self::SendEmailValidation => "send-email-validation".to_string(),
             Self::ConfirmEmailValidation => "confirm-email-validation".to_string(),
+            Self::ConfirmEmailValidationCode => "confirm-email-validation-code".to_string(),
             Self::SendPasswordResetAny => "send-password-reset-any".to_string(),
             Self::SendPasswordResetMe => "send-password-reset-me".to_string(),
             Self::ConfirmPasswordReset => "confirm-password-reset".to_string(),
             Self::CheckPasswordReset => "check-password-reset".to_string(),
+            Self::SendMagicLink => "send-magic-link".to_string(),
+            Self::PasswordParams => "password-params".to_string(),
+            Self::ConfirmMagicLink => "confirm-magic-link".to_string(),
+            Self::OpenIdProviders => "openid-providers".to_string(),
             Self::OpenIdConnect => "openid-connect".to_string(),
             Self::OpenIdAccessTokenHook(provider) => format!("openid-access-token-hook/{}", provider.as_str()),
             Self::OpenIdFinalizeExec => "openid-finalize-exec".to_string(),
             Self::OpenIdFinalizeQuery => "openid-finalize-query".to_string(),
+            Self::OpenIdLinkConnect => "openid-link-connect".to_string(),
+            Self::OpenIdLinkFinalize => "openid-link-finalize".to_string(),
+            Self::Identities => "identities".to_string(),
+            Self::IdentityUnlink => "identity-unlink".to_string(),
+            Self::ImpersonationStop => "impersonation-stop".to_string(),
+            Self::InviteCheck => "invite-check".to_string(),
+            Self::TermsStatus => "terms-status".to_string(),
+            Self::TermsAccept => "terms-accept".to_string(),
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for AccountRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: String = match self {
+            Self::Delete => "delete".to_string(),
+            Self::DeleteCancel => "delete-cancel".to_string(),
+            Self::Export => "export".to_string(),
+            Self::SecurityEvents => "security-events".to_string(),
+            Self::Profile => "profile".to_string(),
+            Self::ProfileUpdate => "profile-update".to_string(),
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for AdminRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: String = match self {
+            Self::AccountDelete => "account-delete".to_string(),
+            Self::AccountExport => "account-export".to_string(),
+            Self::RoleList => "role-list".to_string(),
+            Self::RoleGrant => "role-grant".to_string(),
+            Self::RoleRevoke => "role-revoke".to_string(),
+            Self::ImpersonationStart => "impersonation-start".to_string(),
+            Self::ImpersonationLog => "impersonation-log".to_string(),
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for ApiTokenRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: String = match self {
+            Self::Create => "create".to_string(),
+            Self::List => "list".to_string(),
+            Self::Revoke => "revoke".to_string(),
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for InviteRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: String = match self {
+            Self::Create => "create".to_string(),
+            Self::List => "list".to_string(),
+            Self::Revoke => "revoke".to_string(),
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for RecoveryCodeRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: String = match self {
+            Self::Status => "status".to_string(),
+            Self::Regenerate => "regenerate".to_string(),
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for OrganizationRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: String = match self {
+            Self::Create => "create".to_string(),
+            Self::List => "list".to_string(),
+            Self::Switch => "switch".to_string(),
+            Self::MemberList => "member-list".to_string(),
+            Self::MemberRemove => "member-remove".to_string(),
+            Self::InviteCreate => "invite-create".to_string(),
+            Self::InviteList => "invite-list".to_string(),
+            Self::InviteRevoke => "invite-revoke".to_string(),
+            Self::InviteCheck => "invite-check".to_string(),
+            Self::InviteAccept => "invite-accept".to_string(),
+            Self::InviteDecline => "invite-decline".to_string(),
+            Self::SsoDomainAdd => "sso-domain-add".to_string(),
+            Self::SsoDomainList => "sso-domain-list".to_string(),
+            Self::SsoDomainVerify => "sso-domain-verify".to_string(),
+            Self::SsoDomainRemove => "sso-domain-remove".to_string(),
         };
 
         write!(f, "{}", s)
@@ -159,25 +591,52 @@ pub enum RouteAuthKind {
     None
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
-pub enum OpenIdProvider {
-    Google,
-    Facebook
+impl RouteAuthKind {
+    // whether state-changing requests must come from an allowed origin (see the origin guard in the backend)
+    // CookiesOnly routes have no key header to protect them, so without this another site could
+    // e.g. sign the browser into the attacker's account (login csrf)
+    // public routes don't carry credentials, and some are posted to from other sites on purpose (openid callbacks)
+    pub fn requires_allowed_origin(&self) -> bool {
+        match self {
+            Self::Full | Self::CookiesOnly | Self::PartialAuthTokenOnly | Self::PartialAuthAndUserTokenOnly => true,
+            Self::None => false,
+        }
+    }
 }
 
+/// What a personal api token is allowed to do, see Route::api_token_scope()
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    /// read the account's own data, e.g. auth check, linked identities, and export
+    AccountRead,
+    /// the admin endpoints
+    Admin,
+}
+
+impl ApiTokenScope {
+    pub const fn all() -> [Self; 2] {
+        [Self::AccountRead, Self::Admin]
+    }
+}
+
+/// The slug of a configured OpenID Connect provider, e.g. "google"
+/// the providers themselves are described by backend configuration, so this is open-ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct OpenIdProvider(String);
+
 impl OpenIdProvider {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::Google => "google",
-            Self::Facebook => "facebook",
-        }
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
 
+    // slugs end up in urls, so they're kept to a safe subset
     pub fn try_from_str(s: &str) -> Option<Self> {
-        match s {
-            "google" => Some(Self::Google),
-            "facebook" => Some(Self::Facebook),
-            _ => None
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            Some(Self(s.to_string()))
+        } else {
+            None
         }
     }
 }
@@ -1,5 +1,5 @@
 use worker::worker_sys::web_sys::Response;
-use crate::backend::result::ApiError;
+use crate::backend::result::{ApiError, AuthError};
 
 use super::ext::ResponseExt;
 
@@ -14,6 +14,16 @@ impl From<&ApiError> for Response {
         let status_code = match err {
             // just a nice helper to debug things
             // it's up to the frontend to decide what to do with this
+            ApiError::Auth(AuthError::TooManyAttempts { .. }) => 429,
+            ApiError::Auth(AuthError::Forbidden) => 403,
+            // still signed in, so not a 401
+            ApiError::Auth(AuthError::RecentSigninRequired) => 403,
+            ApiError::Auth(AuthError::TermsNotAccepted) => 403,
+            ApiError::Auth(AuthError::NoActiveOrganization | AuthError::OrganizationInviteWrongAccount) => 403,
+            ApiError::Auth(AuthError::SsoRequired { .. }) => 403,
+            ApiError::Auth(AuthError::InvalidOrigin) => 403,
+            ApiError::Auth(AuthError::RegistrationClosed | AuthError::InviteRequired | AuthError::InvalidInvite | AuthError::EmailDomainNotAllowed | AuthError::EmailNotAllowed | AuthError::DisposableEmail | AuthError::ChallengeFailed) => 403,
+            ApiError::Auth(AuthError::InvalidVerificationCode { .. }) => 400,
             ApiError::Auth(_) => 401,
             ApiError::Unknown(_) => 500
         };
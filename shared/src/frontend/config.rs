@@ -0,0 +1,23 @@
+// The parts of the frontend config that are needed outside of the wasm build too
+// e.g. frontend/headers writes them into the Content-Security-Policy
+// the frontend's CONFIG picks one of these, the rest of its config stays in frontend/src/config.rs
+#[derive(Debug, Clone, Copy)]
+pub struct FrontendDeployConfig {
+    pub media_root: &'static str,
+    pub api_domain: &'static str,
+    // the Cloudflare Turnstile site key, see the backend's CHALLENGE config
+    // None means no challenge widget is shown
+    pub turnstile_site_key: Option<&'static str>,
+}
+
+pub const FRONTEND_DEPLOY_CONFIG_DEV: FrontendDeployConfig = FrontendDeployConfig {
+    media_root: "http://localhost:9000",
+    api_domain: "http://localhost:8787",
+    turnstile_site_key: None,
+};
+
+pub const FRONTEND_DEPLOY_CONFIG_PROD: FrontendDeployConfig = FrontendDeployConfig {
+    media_root: "/media",
+    api_domain: "https://api-prod.example.workers.dev",
+    turnstile_site_key: None,
+};
@@ -1 +1,2 @@
-pub mod route;
\ No newline at end of file
+pub mod route;
+pub mod config;
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::user::Permission;
 
 #[derive(Debug, Clone)]
 pub enum Route {
@@ -21,7 +22,9 @@ impl Route {
         match paths {
             [""] => Self::Landing(Landing::Welcome),
             ["register"] => Self::Landing(Landing::Auth(AuthRoute::Register)),
+            ["register", invite_code] => Self::Landing(Landing::Auth(AuthRoute::RegisterInvite { invite_code: invite_code.to_string() })),
             ["signin"] => Self::Landing(Landing::Auth(AuthRoute::Signin)),
+            ["recovery-code"] => Self::Landing(Landing::Auth(AuthRoute::RecoveryCode)),
             ["reset-password-confirm", oob_token_id, oob_token_key] => {
                 Self::Landing(Landing::Auth(AuthRoute::PasswordResetConfirm { oob_token_id: oob_token_id.to_string(), oob_token_key: oob_token_key.to_string()}))
             },
@@ -36,10 +39,29 @@ impl Route {
             ["dashboard", "browse"] => {
                 Self::Dashboard(Dashboard::Browse)
             },
+            ["dashboard", "admin"] => {
+                Self::Dashboard(Dashboard::Admin)
+            },
+            ["dashboard", "security"] => {
+                Self::Dashboard(Dashboard::Security)
+            },
+            ["dashboard", "recovery-codes"] => {
+                Self::Dashboard(Dashboard::RecoveryCodes)
+            },
+            ["dashboard", "organizations"] => {
+                Self::Dashboard(Dashboard::Organizations)
+            },
+            ["dashboard", "organization-invite", oob_token_id, oob_token_key] => {
+                Self::Dashboard(Dashboard::OrganizationInvite { oob_token_id: oob_token_id.to_string(), oob_token_key: oob_token_key.to_string()})
+            },
+            ["terms-accept"] => Self::Landing(Landing::Auth(AuthRoute::TermsAccept)),
             ["verify-email-waiting"] => Self::Landing(Landing::Auth(AuthRoute::VerifyEmailWaiting)),
             ["verify-email-confirm", oob_token_id, oob_token_key] => {
                 Self::Landing(Landing::Auth(AuthRoute::VerifyEmailConfirm { oob_token_id: oob_token_id.to_string(), oob_token_key: oob_token_key.to_string()}))
             },
+            ["magic-link-confirm", oob_token_id, oob_token_key] => {
+                Self::Landing(Landing::Auth(AuthRoute::MagicLinkConfirm { oob_token_id: oob_token_id.to_string(), oob_token_key: oob_token_key.to_string()}))
+            },
             ["openid-finalize", session_id, session_key] => {
                 Self::Landing(Landing::Auth(AuthRoute::OpenIdFinalize { session_id: session_id.to_string(), session_key: session_key.to_string()}))
             },
@@ -64,6 +86,15 @@ impl Route {
             _ => false,
         }
     }
+
+    // on top of requires_auth(), pages that are only for some roles
+    // the backend enforces this too, this is just so the page isn't shown
+    pub fn required_permission(&self) -> Option<Permission> {
+        match self {
+            Self::Dashboard(Dashboard::Admin) => Some(Permission::AccountsRead),
+            _ => None,
+        }
+    }
 }
 
 
@@ -74,16 +105,25 @@ impl std::fmt::Display for Route {
                 Landing::Welcome => "".to_string(),
                 Landing::Auth(auth_page) => match auth_page {
                     AuthRoute::Signin => "signin".to_string(),
+                    AuthRoute::RecoveryCode => "recovery-code".to_string(),
                     AuthRoute::Register => "register".to_string(),
+                    AuthRoute::RegisterInvite { invite_code } => format!("register/{invite_code}"),
                     AuthRoute::VerifyEmailWaiting => "verify-email-waiting".to_string(),
+                    AuthRoute::TermsAccept => "terms-accept".to_string(),
                     AuthRoute::VerifyEmailConfirm { oob_token_id, oob_token_key} => format!("verify-email-confirm/{oob_token_id}/{oob_token_key}"),
                     AuthRoute::PasswordResetConfirm{ oob_token_id, oob_token_key} => format!("reset-password-confirm/{oob_token_id}/{oob_token_key}"),
+                    AuthRoute::MagicLinkConfirm{ oob_token_id, oob_token_key} => format!("magic-link-confirm/{oob_token_id}/{oob_token_key}"),
                     AuthRoute::OpenIdFinalize{ session_id, session_key} => format!("openid-finalize/{session_id}/{session_key}"),
                 },
             }
             Route::Dashboard(dashboard) => {
                 match dashboard {
                     Dashboard::Browse => format!("dashboard/browse"),
+                    Dashboard::Admin => format!("dashboard/admin"),
+                    Dashboard::Security => format!("dashboard/security"),
+                    Dashboard::RecoveryCodes => format!("dashboard/recovery-codes"),
+                    Dashboard::Organizations => format!("dashboard/organizations"),
+                    Dashboard::OrganizationInvite { oob_token_id, oob_token_key } => format!("dashboard/organization-invite/{oob_token_id}/{oob_token_key}"),
                 }
             },
             Route::NotFound(reason) => match reason {
@@ -99,6 +139,15 @@ impl std::fmt::Display for Route {
 #[derive(Clone, Debug, PartialEq)]
 pub enum Dashboard {
     Browse,
+    Admin,
+    Security,
+    RecoveryCodes,
+    Organizations,
+    // the link sent with an organization invite, accepted while signed in as the invited email
+    OrganizationInvite {
+        oob_token_id: String,
+        oob_token_key: String
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -110,8 +159,16 @@ pub enum Landing {
 #[derive(Clone, Debug)]
 pub enum AuthRoute {
     Signin,
+    // signin with a recovery code, which leads to a password reset
+    RecoveryCode,
     Register,
+    // the link sent with an invite, see the backend's REGISTRATION_POLICY
+    RegisterInvite {
+        invite_code: String
+    },
     VerifyEmailWaiting,
+    // shown when the terms changed since the user last accepted them
+    TermsAccept,
     VerifyEmailConfirm {
         oob_token_id: String,
         oob_token_key: String
@@ -120,6 +177,10 @@ pub enum AuthRoute {
         oob_token_id: String,
         oob_token_key: String
     },
+    MagicLinkConfirm {
+        oob_token_id: String,
+        oob_token_key: String
+    },
     OpenIdFinalize{
         session_id: String,
         session_key: String,
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use wasm_bindgen::prelude::*;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct UserId(Uuid);
 
 impl UserId {
@@ -51,4 +51,128 @@ impl Display for UserId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0.to_string())
     }
-}
\ No newline at end of file
+}
+
+/// A role granted to a user, stored in the `user_role` table
+/// what each role may actually do is decided by its permissions, see Route::required_permission()
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// full access to the admin area
+    Admin,
+    /// read-only support staff
+    Support,
+}
+
+impl Role {
+    pub const fn all() -> [Self; 2] {
+        [Self::Admin, Self::Support]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Admin => "admin",
+            Self::Support => "support",
+        }
+    }
+
+    pub fn try_from_str(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|role| role.as_str() == s)
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            Self::Admin => &[Permission::AccountsRead, Permission::AccountsWrite, Permission::RolesManage, Permission::Impersonate, Permission::InvitesManage],
+            Self::Support => &[Permission::AccountsRead],
+        }
+    }
+
+    pub fn has_permission(roles: &[Role], permission: Permission) -> bool {
+        roles.iter().any(|role| role.permissions().contains(&permission))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// look up and export any account
+    AccountsRead,
+    /// change or delete any account
+    AccountsWrite,
+    /// grant and revoke roles
+    RolesManage,
+    /// sign in as another user, see their view of things
+    Impersonate,
+    /// create, list and revoke registration invites
+    InvitesManage,
+}
+
+/// The user's display details, stored in the `user_profile` table
+/// filled in from the openid provider's claims (for providers with `profile` set), until the user edits it themselves
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct UserProfile {
+    pub name: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    // e.g. "en-US", as the provider sends it
+    pub locale: Option<String>,
+    pub picture_url: Option<String>,
+}
+
+impl UserProfile {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.given_name.is_none()
+            && self.family_name.is_none()
+            && self.locale.is_none()
+            && self.picture_url.is_none()
+    }
+}
+
+/// A user's role within an organization, stored in the `membership` table
+/// unlike Role, this only means something inside that one organization
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OrganizationRole {
+    /// everything, including managing admins and other owners
+    Owner,
+    /// invite and remove members
+    Admin,
+    Member,
+}
+
+impl OrganizationRole {
+    pub const fn all() -> [Self; 3] {
+        [Self::Owner, Self::Admin, Self::Member]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Admin => "admin",
+            Self::Member => "member",
+        }
+    }
+
+    pub fn try_from_str(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|role| role.as_str() == s)
+    }
+
+    // whether this role may invite, list invites, and remove members at all
+    pub fn can_manage_members(&self) -> bool {
+        match self {
+            Self::Owner | Self::Admin => true,
+            Self::Member => false,
+        }
+    }
+
+    // whether this role may invite someone as, or remove someone with, the other role
+    // admins can't touch owners or other admins
+    pub fn can_manage_role(&self, other: OrganizationRole) -> bool {
+        match self {
+            Self::Owner => true,
+            Self::Admin => other == Self::Member,
+            Self::Member => false,
+        }
+    }
+}
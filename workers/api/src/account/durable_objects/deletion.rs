@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{db::user::UserAccount, prelude::durable_object::*};
+
+// Holds the grace period timer for a pending account deletion
+// there's one instance per user (named by uid), so that it can be found again to cancel
+#[durable_object]
+pub struct AccountDeletionDO {
+    state: State,
+    env: Env
+}
+
+
+#[durable_object]
+impl DurableObject for AccountDeletionDO {
+    fn new(state: State, env: Env) -> Self {
+        Self {
+            state: state,
+            env: env,
+        }
+    }
+
+    async fn fetch(&mut self, req: Request) -> worker::Result<Response> {
+        let action = req.headers().get("action")?.and_then(|x| AccountDeletionAction::from_string(x).ok()).ok_or("missing action header")?;
+
+        match action {
+            AccountDeletionAction::Schedule { uid, delete_after } => {
+                self.state.storage().put("uid", uid.to_string()).await?;
+                self.state.storage().set_alarm(Duration::from_millis(delete_after.saturating_sub(now_ms()))).await?;
+                Response::empty()
+            }
+            AccountDeletionAction::Cancel => {
+                self.state.storage().delete_alarm().await?;
+                self.state.storage().delete_all().await?;
+                Response::empty()
+            }
+        }
+    }
+
+    async fn alarm(&mut self) -> worker::Result<Response> {
+        let uid:UserId = self.storage_get::<String>("uid").await?.try_into()?;
+
+        // the db is the source of truth, in case a cancellation raced with the alarm
+        match UserAccount::load_by_id(&self.env, &uid).await {
+            Ok(account) if account.delete_after.map(|x| x <= now_ms()).unwrap_or(false) => {
+                UserAccount::delete(&self.env, &uid).await.map_err(|err| err.to_string())?;
+                worker::console_log!("deleted account {uid}");
+            },
+            _ => {
+                worker::console_warn!("account deletion alarm fired for {uid}, but it's no longer pending");
+            }
+        }
+
+        self.state.storage().delete_all().await?;
+        Response::empty()
+    }
+}
+
+impl DurableObjectExt for AccountDeletionDO {
+    fn state(&self) -> &State {
+        &self.state
+    }
+}
+
+impl AccountDeletionDO {
+    #[cfg(debug_assertions)]
+    const NAMESPACE: &'static str = "ACCOUNT_DELETION_DEV";
+    #[cfg(not(debug_assertions))]
+    const NAMESPACE: &'static str = "ACCOUNT_DELETION_PROD";
+
+    // the stub is from uid, unlike the auth tokens
+    fn stub(env: &Env, uid: &UserId) -> ApiResult<Stub> {
+        env.durable_object(Self::NAMESPACE)?.id_from_name(&uid.to_string())?.get_stub().map_err(|err| err.into())
+    }
+
+    pub async fn schedule(env: &Env, uid: UserId, delete_after: u64) -> ApiResult<()> {
+        let mut do_headers = Headers::new();
+        let stub = Self::stub(env, &uid)?;
+        do_headers.append("action", &AccountDeletionAction::Schedule { uid, delete_after }.to_string()?)?;
+
+        let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
+        stub.fetch_with_request(req).await?;
+
+        Ok(())
+    }
+
+    pub async fn cancel(env: &Env, uid: &UserId) -> ApiResult<()> {
+        let mut do_headers = Headers::new();
+        do_headers.append("action", &AccountDeletionAction::Cancel.to_string()?)?;
+
+        let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
+        Self::stub(env, uid)?.fetch_with_request(req).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+enum AccountDeletionAction {
+    Schedule {
+        uid: UserId,
+        // unix timestamp in milliseconds
+        delete_after: u64,
+    },
+    Cancel,
+}
+
+impl AccountDeletionAction {
+    fn to_string(&self) -> ApiResult<String> {
+        serde_json::to_string(self).map_err(|err| err.to_string().into())
+    }
+    fn from_string(action: String) -> ApiResult<Self> {
+        serde_json::from_str(action.as_str()).map_err(|err| err.to_string().into())
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use shared::{api::{account::{AccountDelete, AccountDeleteCancel, AccountDeleteRequest, AccountDeleteResponse, AccountExport, AccountExportAccount, AccountExportResponse, AccountProfile, AccountProfileResponse, AccountProfileUpdate, AccountProfileUpdateRequest, AccountSecurityEvents, AccountSecurityEventsResponse, AuthEventKind}, ApiBoth, ApiReq, ApiRes}, backend::{result::{ApiResult, AuthError}, worker::ResponseExt}, user::{UserId, UserProfile}};
+use web_sys::Response;
+use worker::Env;
+use crate::{
+    api_ext::{ApiBothExt, ApiEmptyExt, ApiReqExt, ApiResExt}, auth::{delete_signin_cookie, record_auth_event, verify_password, durable_objects::token::AuthTokenDO}, config::{ACCOUNT_DELETION_GRACE_PERIOD, AUTH_EVENT_LIST_LIMIT, PROFILE_FIELD_MAX_LENGTH}, db::{api_token::ApiToken, auth_event::AuthEvent, identity::UserIdentity, organization::UserOrganization, profile::UserProfileDb, role::UserRole, user::UserAccount}, helpers::now_ms, ApiContext
+};
+use super::durable_objects::deletion::AccountDeletionDO;
+
+#[async_trait(?Send)]
+impl ApiBothExt for AccountDelete {
+    type Req = <AccountDelete as ApiBoth>::Req;
+    type Res = <AccountDelete as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: AccountDeleteRequest) -> ApiResult<AccountDeleteResponse> {
+        let user = ctx.user.as_ref().unwrap();
+
+        // openid users never chose a password, so a fresh signin is accepted instead
+        match data.password {
+            Some(password) => {
+                verify_password(&user.account, &password).map_err(|_| AuthError::ConfirmationRequired)?;
+            },
+            None if user.signed_in_recently() => {},
+            None => {
+                return Err(AuthError::ConfirmationRequired.into());
+            }
+        }
+
+        let uid = user.account.id.clone();
+        let delete_after = now_ms() + ACCOUNT_DELETION_GRACE_PERIOD;
+
+        UserAccount::schedule_deletion(&ctx.env, &uid, delete_after).await?;
+        AccountDeletionDO::schedule(&ctx.env, uid.clone(), delete_after).await?;
+
+        // sign out everywhere, the user can still sign back in to cancel
+        // api tokens don't follow the UserToken, so they're revoked separately
+        UserAccount::rotate_user_token(&ctx.env, &uid).await?;
+        ApiToken::delete_all_by_uid(&ctx.env, &uid).await?;
+        if let Some(token_id) = user.signin_token_id() {
+            AuthTokenDO::destroy(&ctx.env, token_id).await?;
+        }
+        record_auth_event(ctx, AuthEventKind::SessionRevoked, Some(&uid), None, Some("account_deletion")).await;
+
+        Ok(AccountDeleteResponse {
+            delete_after
+        })
+    }
+
+    fn response(_ctx: &ApiContext, data: AccountDeleteResponse) -> Response {
+        let res = Response::new_json(&data);
+        delete_signin_cookie(&res);
+        res
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiEmptyExt for AccountDeleteCancel {
+    async fn handle(ctx: &ApiContext) -> ApiResult<()> {
+        let uid = ctx.uid_unchecked();
+
+        AccountDeletionDO::cancel(&ctx.env, &uid).await?;
+        UserAccount::cancel_deletion(&ctx.env, &uid).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AccountExport {
+    type Res = <AccountExport as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
+        load_account_export(&ctx.env, &ctx.uid_unchecked()).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AccountSecurityEvents {
+    type Res = <AccountSecurityEvents as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
+        let events = AuthEvent::list_by_uid(&ctx.env, &ctx.uid_unchecked(), AUTH_EVENT_LIST_LIMIT).await?;
+
+        Ok(AccountSecurityEventsResponse {
+            events: events.into_iter().map(|x| x.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AccountProfile {
+    type Res = <AccountProfile as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
+        Ok(AccountProfileResponse {
+            profile: UserProfileDb::load(&ctx.env, &ctx.uid_unchecked()).await?
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AccountProfileUpdate {
+    type Req = <AccountProfileUpdate as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AccountProfileUpdateRequest) -> ApiResult<()> {
+        let profile = validate_profile(data.profile)?;
+        UserProfileDb::update_by_user(&ctx.env, &ctx.uid_unchecked(), &profile).await
+    }
+}
+
+// trims everything, blanks become None, and the picture must be an https url
+fn validate_profile(profile: UserProfile) -> ApiResult<UserProfile> {
+    fn field(value: Option<String>) -> ApiResult<Option<String>> {
+        match value.map(|x| x.trim().to_string()).filter(|x| !x.is_empty()) {
+            Some(x) if x.chars().count() > PROFILE_FIELD_MAX_LENGTH => Err(format!("profile fields must be at most {PROFILE_FIELD_MAX_LENGTH} characters").into()),
+            x => Ok(x)
+        }
+    }
+
+    let picture_url = field(profile.picture_url)?;
+    if picture_url.as_ref().is_some_and(|x| !x.starts_with("https://")) {
+        return Err("profile picture must be an https url".into());
+    }
+
+    Ok(UserProfile {
+        name: field(profile.name)?,
+        given_name: field(profile.given_name)?,
+        family_name: field(profile.family_name)?,
+        locale: field(profile.locale)?,
+        picture_url,
+    })
+}
+
+// shared with the admin export
+pub async fn load_account_export(env: &Env, uid: &UserId) -> ApiResult<AccountExportResponse> {
+    let account = UserAccount::load_by_id(env, uid).await?;
+    let identities = UserIdentity::list_by_uid(env, uid).await?;
+    let api_tokens = ApiToken::list_by_uid(env, uid).await?;
+    let roles = UserRole::list_by_uid(env, uid).await?;
+    let auth_events = AuthEvent::list_all_by_uid(env, uid).await?;
+    let profile = UserProfileDb::load(env, uid).await?;
+    let organizations = UserOrganization::list_by_uid(env, uid).await?;
+
+    Ok(AccountExportResponse {
+        account: AccountExportAccount {
+            uid: account.id,
+            email: account.email,
+            email_verified: account.email_verified,
+            created_at: account.created_at,
+            delete_after: account.delete_after,
+        },
+        identities: identities.into_iter().map(|x| x.into()).collect(),
+        api_tokens: api_tokens.into_iter().map(|x| x.into()).collect(),
+        roles,
+        auth_events: auth_events.into_iter().map(|x| x.into()).collect(),
+        profile,
+        organizations: organizations.into_iter().map(|x| x.into_info(account.active_org_id.as_deref())).collect(),
+    })
+}
@@ -0,0 +1,4 @@
+mod handler;
+pub(super) mod durable_objects;
+
+pub(crate) use handler::load_account_export;
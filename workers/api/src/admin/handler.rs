@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use shared::{api::{admin::{AdminAccountDelete, AdminAccountExport, AdminAccountRequest, AdminImpersonationLog, AdminImpersonationLogResponse, AdminImpersonationStart, AdminImpersonationStartResponse, AdminRoleGrant, AdminRoleList, AdminRoleListResponse, AdminRoleRequest, AdminRoleRevoke, ImpersonationEvent}, account::AccountExportResponse, ApiBoth, ApiReq, ApiRes}, backend::result::ApiResult, user::Role};
+use crate::{
+    account::{durable_objects::deletion::AccountDeletionDO, load_account_export}, api_ext::{ApiBothExt, ApiReqExt, ApiResExt}, auth::durable_objects::token::AuthTokenDO, config::{AUTH_IMPERSONATION_TOKEN_EXPIRES, IMPERSONATION_LOG_LIST_LIMIT}, db::{impersonation::ImpersonationLog, role::UserRole, user::UserAccount}, helpers::now_ms, ApiContext
+};
+
+// the permission for each of these is checked before the handler runs, see Route::required_permission()
+
+#[async_trait(?Send)]
+impl ApiReqExt for AdminAccountDelete {
+    type Req = <AdminAccountDelete as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AdminAccountRequest) -> ApiResult<()> {
+        worker::console_log!("admin {} is deleting account {}", ctx.uid_unchecked(), data.uid);
+
+        // no grace period here, but clear out any pending timer
+        AccountDeletionDO::cancel(&ctx.env, &data.uid).await?;
+        UserAccount::delete(&ctx.env, &data.uid).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for AdminAccountExport {
+    type Req = <AdminAccountExport as ApiBoth>::Req;
+    type Res = <AdminAccountExport as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: AdminAccountRequest) -> ApiResult<AccountExportResponse> {
+        worker::console_log!("admin {} is exporting account {}", ctx.uid_unchecked(), data.uid);
+
+        load_account_export(&ctx.env, &data.uid).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AdminRoleList {
+    type Res = <AdminRoleList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<AdminRoleListResponse> {
+        let roles = UserRole::list_all(&ctx.env).await?;
+
+        Ok(AdminRoleListResponse {
+            roles: roles.into_iter().map(|x| x.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AdminRoleGrant {
+    type Req = <AdminRoleGrant as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AdminRoleRequest) -> ApiResult<()> {
+        worker::console_log!("admin {} is granting {} to {}", ctx.uid_unchecked(), data.role.as_str(), data.uid);
+
+        // make sure the account exists, so roles don't end up dangling
+        UserAccount::load_by_id(&ctx.env, &data.uid).await?;
+        UserRole::insert(&ctx.env, &data.uid, data.role).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AdminRoleRevoke {
+    type Req = <AdminRoleRevoke as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AdminRoleRequest) -> ApiResult<()> {
+        // an admin can't revoke their own admin role, so there's always at least one admin left
+        if data.uid == ctx.uid_unchecked() && data.role == Role::Admin {
+            return Err("admins can't revoke their own admin role".into());
+        }
+
+        worker::console_log!("admin {} is revoking {} from {}", ctx.uid_unchecked(), data.role.as_str(), data.uid);
+
+        UserRole::delete(&ctx.env, &data.uid, data.role).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for AdminImpersonationStart {
+    type Req = <AdminImpersonationStart as ApiBoth>::Req;
+    type Res = <AdminImpersonationStart as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: AdminAccountRequest) -> ApiResult<AdminImpersonationStartResponse> {
+        let actor = ctx.uid_unchecked();
+        if data.uid == actor {
+            return Err("admins can't impersonate themselves".into());
+        }
+
+        let target = UserAccount::load_by_id(&ctx.env, &data.uid).await?;
+
+        worker::console_log!("admin {} is impersonating {}", actor, target.id);
+        ImpersonationLog::insert(&ctx.env, &actor, &target.id, ImpersonationEvent::Start, None).await?;
+
+        let expires_at = now_ms() + AUTH_IMPERSONATION_TOKEN_EXPIRES;
+        let token = AuthTokenDO::create_impersonation(&ctx.env, target.id, target.user_token, actor, AUTH_IMPERSONATION_TOKEN_EXPIRES).await?;
+
+        Ok(AdminImpersonationStartResponse {
+            token_id: token.id,
+            token_key: token.key,
+            expires_at,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AdminImpersonationLog {
+    type Res = <AdminImpersonationLog as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<AdminImpersonationLogResponse> {
+        let entries = ImpersonationLog::list_recent(&ctx.env, IMPERSONATION_LOG_LIST_LIMIT).await?;
+
+        Ok(AdminImpersonationLogResponse {
+            entries: entries.into_iter().map(|x| x.into()).collect()
+        })
+    }
+}
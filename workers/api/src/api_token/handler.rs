@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use shared::{api::{account::AuthEventKind, api_token::{ApiTokenCreate, ApiTokenCreateRequest, ApiTokenCreateResponse, ApiTokenList, ApiTokenListResponse, ApiTokenRevoke, ApiTokenRevokeRequest}, ApiBoth, ApiReq, ApiRes}, backend::result::ApiResult};
+use crate::{
+    api_ext::{ApiBothExt, ApiReqExt, ApiResExt}, auth::record_auth_event, config::{API_TOKEN_MAX_PER_USER, MS_PER_DAY}, db::api_token::ApiToken, helpers::now_ms, ApiContext
+};
+use super::NewApiToken;
+
+#[async_trait(?Send)]
+impl ApiBothExt for ApiTokenCreate {
+    type Req = <ApiTokenCreate as ApiBoth>::Req;
+    type Res = <ApiTokenCreate as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: ApiTokenCreateRequest) -> ApiResult<ApiTokenCreateResponse> {
+        let ApiTokenCreateRequest { name, mut scopes, expires_in_days } = data;
+        let uid = ctx.uid_unchecked();
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("api token name is required".into());
+        }
+        scopes.sort_by_key(|scope| *scope as u8);
+        scopes.dedup();
+        if scopes.is_empty() {
+            return Err("api token needs at least one scope".into());
+        }
+        if ApiToken::count_by_uid(&ctx.env, &uid).await? >= API_TOKEN_MAX_PER_USER {
+            return Err(format!("at most {API_TOKEN_MAX_PER_USER} api tokens are allowed").into());
+        }
+
+        let expires_at = expires_in_days.map(|days| now_ms() + (days as u64 * MS_PER_DAY));
+        let NewApiToken { id, token, token_hash } = NewApiToken::generate();
+
+        ApiToken::insert(&ctx.env, &id, &uid, name, &token_hash, &scopes, expires_at).await?;
+
+        let info = ApiToken::load(&ctx.env, &id).await?.ok_or("api token was not inserted")?.into();
+
+        Ok(ApiTokenCreateResponse {
+            token,
+            info
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for ApiTokenList {
+    type Res = <ApiTokenList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<ApiTokenListResponse> {
+        let tokens = ApiToken::list_by_uid(&ctx.env, &ctx.uid_unchecked()).await?;
+
+        Ok(ApiTokenListResponse {
+            tokens: tokens.into_iter().map(|token| token.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for ApiTokenRevoke {
+    type Req = <ApiTokenRevoke as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: ApiTokenRevokeRequest) -> ApiResult<()> {
+        let uid = ctx.uid_unchecked();
+        ApiToken::delete(&ctx.env, &uid, &data.id).await?;
+        record_auth_event(ctx, AuthEventKind::SessionRevoked, Some(&uid), None, Some("api_token")).await;
+        Ok(())
+    }
+}
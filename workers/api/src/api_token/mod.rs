@@ -0,0 +1,42 @@
+mod handler;
+
+use base64::Engine;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use crate::{config::{API_TOKEN_PREFIX, API_TOKEN_SECRET_LENGTH}, prelude::*};
+
+// a freshly generated token, the full string is only ever given to the user once
+pub struct NewApiToken {
+    pub id: String,
+    pub token: String,
+    pub token_hash: String,
+}
+
+impl NewApiToken {
+    pub fn generate() -> Self {
+        let id = uuid::Uuid::now_v7().as_simple().to_string();
+        let secret = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&rand::thread_rng().gen::<[u8; API_TOKEN_SECRET_LENGTH]>());
+        let token_hash = hash_secret(&secret);
+
+        Self {
+            token: format!("{API_TOKEN_PREFIX}{id}_{secret}"),
+            id,
+            token_hash,
+        }
+    }
+}
+
+// the secret is high-entropy random data, not a password, so a fast hash is enough
+// it's only so that a leaked db doesn't leak usable tokens
+pub fn hash_secret(secret: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+// splits "pat_<id>_<secret>" into (id, secret)
+// the id is a simple uuid, so it never contains the delimiter (though the secret might)
+pub fn parse_token(token: &str) -> ApiResult<(&str, &str)> {
+    token
+        .strip_prefix(API_TOKEN_PREFIX)
+        .and_then(|token| token.split_once('_'))
+        .ok_or_else(|| "malformed api token".into())
+}
@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use web_sys::{js_sys, Headers, RequestInit, WorkerGlobalScope};
+use crate::prelude::*;
+
+// Bot protection for the routes anyone can call, read from the CHALLENGE var (a JSON object, see wrangler.toml)
+// the client solves a challenge (e.g. the Turnstile widget) and sends the token it got along with the request
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ChallengeConfig {
+    pub verifier: ChallengeVerifierKind,
+    // only these routes require a token, not configured means none of them
+    pub routes: Vec<ChallengeRoute>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeVerifierKind {
+    // needs the TURNSTILE_SECRET_KEY secret
+    // the default, so a config that lists routes but forgets the verifier doesn't let anything through
+    #[default]
+    Turnstile,
+    // stand-ins for local development and tests, any token (but not a missing one) passes or fails
+    // rejected outside of debug builds
+    AlwaysPass,
+    AlwaysFail,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeRoute {
+    Register,
+    SendPasswordResetAny,
+}
+
+impl ChallengeConfig {
+    const ENV_VAR: &'static str = "CHALLENGE";
+
+    pub fn load(env: &Env) -> ApiResult<Self> {
+        match env.var(Self::ENV_VAR) {
+            Ok(var) => serde_json::from_str(&var.to_string()).map_err(|err| format!("invalid {}: {}", Self::ENV_VAR, err).into()),
+            // not configured means no challenges
+            Err(_) => Ok(Self::default())
+        }
+    }
+
+    // only called for a route that needs a token, so with no routes listed a stand-in is harmless
+    pub fn verifier(&self, env: &Env) -> ApiResult<Box<dyn ChallengeVerifier>> {
+        if !cfg!(debug_assertions) && self.verifier != ChallengeVerifierKind::Turnstile {
+            return Err(format!("invalid {}: {:?} is only for development", Self::ENV_VAR, self.verifier).into());
+        }
+
+        Ok(match self.verifier {
+            ChallengeVerifierKind::Turnstile => Box::new(TurnstileVerifier::new(env)?),
+            ChallengeVerifierKind::AlwaysPass => Box::new(StaticVerifier(true)),
+            ChallengeVerifierKind::AlwaysFail => Box::new(StaticVerifier(false)),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+pub trait ChallengeVerifier {
+    // whether the token is from a solved challenge
+    // an error means it couldn't be checked at all, e.g. the verifier is down
+    async fn verify(&self, token: &str, ip: Option<&str>) -> ApiResult<bool>;
+}
+
+// fails the request with ChallengeFailed if the route needs a token and this one doesn't pass
+// should be called before anything else (rate limits included), so bots don't use those up
+pub async fn check_challenge(ctx: &ApiContext, route: ChallengeRoute, token: Option<&str>) -> ApiResult<()> {
+    let config = ChallengeConfig::load(&ctx.env)?;
+
+    if !config.routes.contains(&route) {
+        return Ok(());
+    }
+
+    let token = token.filter(|token| !token.is_empty()).ok_or(AuthError::ChallengeFailed)?;
+
+    if !config.verifier(&ctx.env)?.verify(token, ctx.client_ip().as_deref()).await? {
+        worker::console_log!("challenge failed for {:?}", route);
+        return Err(AuthError::ChallengeFailed.into());
+    }
+
+    Ok(())
+}
+
+struct StaticVerifier(bool);
+
+#[async_trait(?Send)]
+impl ChallengeVerifier for StaticVerifier {
+    async fn verify(&self, _token: &str, _ip: Option<&str>) -> ApiResult<bool> {
+        Ok(self.0)
+    }
+}
+
+// https://developers.cloudflare.com/turnstile/get-started/server-side-validation/
+struct TurnstileVerifier {
+    secret_key: String,
+}
+
+#[derive(Serialize)]
+struct TurnstileRequest<'a> {
+    secret: &'a str,
+    response: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remoteip: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct TurnstileResponse {
+    success: bool,
+    #[serde(rename = "error-codes", default)]
+    error_codes: Vec<String>,
+}
+
+impl TurnstileVerifier {
+    const SECRET_NAME: &'static str = "TURNSTILE_SECRET_KEY";
+    const VERIFY_URL: &'static str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+    fn new(env: &Env) -> ApiResult<Self> {
+        Ok(Self {
+            secret_key: get_secret(env, Self::SECRET_NAME)?,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ChallengeVerifier for TurnstileVerifier {
+    async fn verify(&self, token: &str, ip: Option<&str>) -> ApiResult<bool> {
+        let mut init = RequestInit::new();
+        init.method("POST");
+        let headers = Headers::new()?;
+        headers.set("content-type", "application/json")?;
+        init.headers(&headers);
+
+        let body = serde_json::to_string(&TurnstileRequest { secret: &self.secret_key, response: token, remoteip: ip }).map_err(|err| err.to_string())?;
+        init.body(Some(&JsValue::from_str(&body)));
+
+        let req = Request::new_with_str_and_init(Self::VERIFY_URL, &init)?;
+        let promise = js_sys::global().unchecked_into::<WorkerGlobalScope>().fetch_with_request(&req);
+        let res = JsFuture::from(promise).await?.unchecked_into::<Response>();
+
+        if !res.ok() {
+            return Err(format!("turnstile verification failed, status code: {}", res.status()).into());
+        }
+
+        let text = JsFuture::from(res.text()?).await?.as_string().unwrap_or_default();
+        let res: TurnstileResponse = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+
+        if !res.success {
+            worker::console_log!("turnstile rejected a token: {:?}", res.error_codes);
+        }
+
+        Ok(res.success)
+    }
+}
@@ -1,2 +1,3 @@
 pub mod openid;
-pub mod token;
\ No newline at end of file
+pub mod token;
+pub mod rate_limit;
\ No newline at end of file
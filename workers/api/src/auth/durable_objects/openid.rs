@@ -1,6 +1,6 @@
 use openidconnect::{CsrfToken, Nonce};
 use serde::{Deserialize, Serialize};
-use shared::backend::route::OpenIdProvider;
+use shared::{backend::route::OpenIdProvider, user::{UserId, UserProfile}};
 
 use crate::{config::{AUTH_OPEN_ID_SESSION_EXPIRES, AUTH_TOKEN_KEY_LENGTH}, prelude::durable_object::*};
 
@@ -24,7 +24,7 @@ impl DurableObject for OpenIdSessionDO {
         let action = req.headers().get("action")?.and_then(|x| OpenIdSessionAction::from_string(x).ok()).ok_or("missing action header")?;
 
         match action {
-            OpenIdSessionAction::Create{provider} => {
+            OpenIdSessionAction::Create{provider, link_uid, invite_code} => {
                 let mut headers = Headers::new();
                 let key = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&rand::thread_rng().gen::<[u8; AUTH_TOKEN_KEY_LENGTH]>());
                 let provider = provider.as_str();
@@ -34,13 +34,20 @@ impl DurableObject for OpenIdSessionDO {
 
                 self.state.storage().put("key", key).await?;
                 self.state.storage().put("provider", provider).await?;
+                if let Some(link_uid) = link_uid {
+                    self.state.storage().put("link_uid", link_uid.to_string()).await?;
+                }
+                if let Some(invite_code) = invite_code {
+                    self.state.storage().put("invite_code", invite_code).await?;
+                }
                 self.state.storage().set_alarm(Duration::from_millis(AUTH_OPEN_ID_SESSION_EXPIRES)).await?;
 
                 Ok(Response::empty()?.with_headers(headers))
             }
 
-            OpenIdSessionAction::SetNonce { nonce } => {
+            OpenIdSessionAction::SetNonce { nonce, pkce_verifier } => {
                 self.state.storage().put("nonce", nonce).await?;
+                self.state.storage().put("pkce_verifier", pkce_verifier).await?;
                 Response::empty()
             }
 
@@ -50,21 +57,31 @@ impl DurableObject for OpenIdSessionDO {
                 }
                 let provider = OpenIdProvider::try_from_str(&self.storage_get::<String>("provider").await?).ok_or("invalid provider str")?;
                 let nonce = Nonce::new(self.storage_get::<String>("nonce").await?);
+                let pkce_verifier = self.storage_get::<String>("pkce_verifier").await?;
 
                 let mut headers = Headers::new();
 
                 headers.set("result", &serde_json::to_string(&OpenIdSessionNonce {
                     provider,
-                    nonce
+                    nonce,
+                    pkce_verifier,
                 }).unwrap()).unwrap();
 
                 Ok(Response::empty()?.with_headers(headers))
             }
 
-            OpenIdSessionAction::SetAccessToken { access_token, email, email_verified} => {
+            OpenIdSessionAction::SetAccessToken { claims } => {
+                let OpenIdSessionClaims { access_token, subject, email, email_verified, id_token, profile } = claims;
                 self.state.storage().put("access_token", access_token).await?;
+                self.state.storage().put("subject", subject).await?;
                 self.state.storage().put("email", email).await?;
                 self.state.storage().put("email_verified", email_verified).await?;
+                if let Some(id_token) = id_token {
+                    self.state.storage().put("id_token", id_token).await?;
+                }
+                if let Some(profile) = profile {
+                    self.state.storage().put("profile", profile).await?;
+                }
 
                 Response::empty()
             }
@@ -113,9 +130,11 @@ impl OpenIdSessionDO {
         env.durable_object(Self::NAMESPACE)?.id_from_string(id)?.get_stub().map_err(|err| err.into())
     }
 
-    pub async fn create(env: &Env, provider: OpenIdProvider) -> ApiResult<OpenIdSession> {
+    // link_uid is set when a signed-in user is linking a new identity to their account
+    // invite_code is only used if finalizing registers a new account
+    pub async fn create(env: &Env, provider: OpenIdProvider, link_uid: Option<UserId>, invite_code: Option<String>) -> ApiResult<OpenIdSession> {
         let mut do_headers = Headers::new();
-        do_headers.append("action", &OpenIdSessionAction::Create{provider}.to_string()?)?;
+        do_headers.append("action", &OpenIdSessionAction::Create{provider, link_uid, invite_code}.to_string()?)?;
 
         let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
         let id = env.durable_object(Self::NAMESPACE)?.unique_id()?.to_string();
@@ -125,9 +144,10 @@ impl OpenIdSessionDO {
         Ok(OpenIdSession{ id, key })
     }
 
-    pub async fn set_nonce(env: &Env, id: &str, nonce: String) -> ApiResult<()> {
+    // the pkce verifier is kept for the code exchange, only its challenge goes to the provider
+    pub async fn set_nonce(env: &Env, id: &str, nonce: String, pkce_verifier: String) -> ApiResult<()> {
         let mut do_headers = Headers::new();
-        do_headers.append("action", &OpenIdSessionAction::SetNonce { nonce}.to_string()?)?;
+        do_headers.append("action", &OpenIdSessionAction::SetNonce { nonce, pkce_verifier }.to_string()?)?;
 
         let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
         let _ = Self::stub(env, &id)?.fetch_with_request(req).await?;
@@ -148,9 +168,9 @@ impl OpenIdSessionDO {
             .and_then(|x| serde_json::from_str(&x).map_err(|err| err.to_string().into()))
     }
 
-    pub async fn set_access_token(env: &Env, id: &str, access_token: String, email: String, email_verified: bool) -> ApiResult<()> {
+    pub async fn set_access_token(env: &Env, id: &str, claims: OpenIdSessionClaims) -> ApiResult<()> {
         let mut do_headers = Headers::new();
-        do_headers.append("action", &OpenIdSessionAction::SetAccessToken {access_token, email, email_verified}.to_string()?)?;
+        do_headers.append("action", &OpenIdSessionAction::SetAccessToken {claims}.to_string()?)?;
 
         let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
         let _ = Self::stub(env, &id)?.fetch_with_request(req).await?;
@@ -190,14 +210,30 @@ impl OpenIdSessionDO {
         }
         let provider = OpenIdProvider::try_from_str(&self.storage_get::<String>("provider").await?).ok_or("invalid provider str")?;
         let access_token = self.storage_get::<String>("access_token").await?;
+        let subject = self.storage_get::<String>("subject").await?;
         let email = self.storage_get::<String>("email").await?;
         let email_verified = self.storage_get::<bool>("email_verified").await?;
+        // only set for link sessions
+        let link_uid = match self.state.storage().get::<String>("link_uid").await.ok() {
+            Some(link_uid) => Some(UserId::try_from(link_uid)?),
+            None => None
+        };
+        // only for openid connect providers
+        let id_token = self.state.storage().get::<String>("id_token").await.ok();
+        // only for providers configured with `profile`
+        let profile = self.state.storage().get::<UserProfile>("profile").await.ok();
+        let invite_code = self.state.storage().get::<String>("invite_code").await.ok();
 
         Ok(OpenIdSessionFinalizeInfo {
             provider,
             access_token,
+            subject,
             email,
-            email_verified
+            email_verified,
+            link_uid,
+            id_token,
+            profile,
+            invite_code,
         })
     }
 }
@@ -206,18 +242,19 @@ impl OpenIdSessionDO {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum OpenIdSessionAction {
     Create {
-        provider: OpenIdProvider
+        provider: OpenIdProvider,
+        link_uid: Option<UserId>,
+        invite_code: Option<String>,
     },
     SetNonce {
-        nonce: String
+        nonce: String,
+        pkce_verifier: String,
     },
     GetTokenExchange {
         key: String
     },
     SetAccessToken {
-        access_token: String,
-        email: String,
-        email_verified: bool,
+        claims: OpenIdSessionClaims,
     },
     FinalizeExec {
         key: String
@@ -266,12 +303,34 @@ impl OpenIdSession {
 pub struct OpenIdSessionNonce {
     pub provider: OpenIdProvider,
     pub nonce: Nonce,
+    pub pkce_verifier: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OpenIdSessionFinalizeInfo {
     pub provider: OpenIdProvider,
     pub access_token: String,
+    // the provider's stable id for the user
+    pub subject: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub link_uid: Option<UserId>,
+    // kept with the signin, as the hint for signing out of the provider too
+    pub id_token: Option<String>,
+    pub profile: Option<UserProfile>,
+    // from the register page, see RegistrationPolicy
+    pub invite_code: Option<String>,
+}
+
+// What the provider told us about the user, once the code is exchanged
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OpenIdSessionClaims {
+    pub access_token: String,
+    pub subject: String,
     pub email: String,
     pub email_verified: bool,
+    // openid connect only
+    pub id_token: Option<String>,
+    // None unless the provider is configured with `profile`, or if it had none of the claims
+    pub profile: Option<UserProfile>,
 }
\ No newline at end of file
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::durable_object::*;
+
+// Counts attempts against some sensitive action, within a sliding window
+// there's one instance per key (named by e.g. "signin:email:foo@example.com"), so that all attempts are serialized
+// past a few free attempts, each subsequent attempt must wait exponentially longer
+// and past the max attempts, the key is locked out entirely for a while
+// attempts are reserved before they're made (and released if they succeed), so parallel attempts can't all get in
+// before any of them is counted
+#[durable_object]
+pub struct RateLimitDO {
+    state: State,
+    _env: Env
+}
+
+
+#[durable_object]
+impl DurableObject for RateLimitDO {
+    fn new(state: State, env: Env) -> Self {
+        Self {
+            state: state,
+            _env: env,
+        }
+    }
+
+    async fn fetch(&mut self, req: Request) -> worker::Result<Response> {
+        let action = req.headers().get("action")?.and_then(|x| RateLimitAction::from_string(x).ok()).ok_or("missing action header")?;
+
+        let now = now_ms();
+        let mut headers = Headers::new();
+
+        match action {
+            RateLimitAction::Reserve { policy } => {
+                let mut stored = self.load(&policy, now).await;
+                let retry_after_ms = stored.retry_after_ms(&policy, now);
+                if retry_after_ms == 0 {
+                    // random rather than the time, two attempts can land in the same millisecond
+                    // (and u32, since storage goes through js numbers)
+                    let id = rand::thread_rng().gen::<u32>();
+                    let locked = stored.record(&policy, id, now);
+                    self.save(&stored, &policy).await?;
+                    headers.set("attempt-id", &id.to_string()).unwrap();
+                    headers.set("locked", &locked.to_string()).unwrap();
+                }
+                headers.set("retry-after-ms", &retry_after_ms.to_string()).unwrap();
+            },
+            RateLimitAction::Release { policy, attempt_id } => {
+                let mut stored = self.load(&policy, now).await;
+                // it may be gone already, e.g. if it triggered a lockout
+                if let Some(index) = stored.attempts.iter().position(|x| x.id == attempt_id) {
+                    stored.attempts.remove(index);
+                    self.save(&stored, &policy).await?;
+                }
+            },
+            RateLimitAction::Clear => {
+                self.state.storage().delete_alarm().await?;
+                self.state.storage().delete_all().await?;
+            }
+        }
+
+        Ok(Response::empty()?.with_headers(headers))
+    }
+
+    async fn alarm(&mut self) -> worker::Result<Response> {
+        self.state.storage().delete_all().await?;
+        Response::empty()
+    }
+}
+
+impl DurableObjectExt for RateLimitDO {
+    fn state(&self) -> &State {
+        &self.state
+    }
+}
+
+impl RateLimitDO {
+    #[cfg(debug_assertions)]
+    const NAMESPACE: &'static str = "AUTH_RATE_LIMIT_DEV";
+    #[cfg(not(debug_assertions))]
+    const NAMESPACE: &'static str = "AUTH_RATE_LIMIT_PROD";
+
+    // the stub is from the name, like account deletion
+    fn stub(env: &Env, name: &str) -> ApiResult<Stub> {
+        env.durable_object(Self::NAMESPACE)?.id_from_name(name)?.get_stub().map_err(|err| err.into())
+    }
+
+    async fn load(&self, policy: &RateLimitPolicy, now: u64) -> RateLimitStorage {
+        // a missing key just means nothing has been recorded yet
+        // (and so does state from before attempts had ids, it's short-lived anyway)
+        let mut stored = self.state.storage().get::<RateLimitStorage>("state").await.unwrap_or_default();
+        stored.attempts.retain(|x| x.at + policy.window > now);
+        stored
+    }
+
+    async fn save(&mut self, stored: &RateLimitStorage, policy: &RateLimitPolicy) -> worker::Result<()> {
+        self.state.storage().put("state", stored).await?;
+        // once the window and any lockout have passed, there's nothing left to remember
+        let last_attempt = stored.attempts.last().map(|x| x.at).unwrap_or(0);
+        let expires_at = (last_attempt + policy.window).max(stored.locked_until);
+        self.state.storage().set_alarm(Duration::from_millis(expires_at.saturating_sub(now_ms()))).await?;
+        Ok(())
+    }
+
+    async fn send(env: &Env, name: &str, action: RateLimitAction) -> ApiResult<Response> {
+        let mut do_headers = Headers::new();
+        do_headers.append("action", &action.to_string()?)?;
+
+        let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
+        Self::stub(env, name)?.fetch_with_request(req).await.map_err(|err| err.into())
+    }
+
+    // check and record in one go, the attempt is only recorded if it's allowed
+    pub async fn reserve(env: &Env, name: &str, policy: RateLimitPolicy) -> ApiResult<RateLimitReserveResponse> {
+        let res = Self::send(env, name, RateLimitAction::Reserve { policy }).await?;
+        let retry_after_ms = retry_after_header(&res)?;
+
+        if retry_after_ms > 0 {
+            return Ok(RateLimitReserveResponse::Wait { retry_after_ms });
+        }
+
+        Ok(RateLimitReserveResponse::Reserved {
+            attempt_id: res.headers().get("attempt-id")?.ok_or("missing attempt-id header")?.parse().map_err(|_| "invalid attempt-id header")?,
+            locked: res.headers().get("locked")?.map(|x| x == "true").unwrap_or(false),
+        })
+    }
+
+    // takes back a reserved attempt, e.g. because it succeeded
+    pub async fn release(env: &Env, name: &str, policy: RateLimitPolicy, attempt_id: u32) -> ApiResult<()> {
+        Self::send(env, name, RateLimitAction::Release { policy, attempt_id }).await?;
+        Ok(())
+    }
+
+    pub async fn clear(env: &Env, name: &str) -> ApiResult<()> {
+        Self::send(env, name, RateLimitAction::Clear).await?;
+        Ok(())
+    }
+}
+
+fn retry_after_header(res: &Response) -> ApiResult<u64> {
+    res.headers().get("retry-after-ms")?.ok_or("missing retry-after-ms header")?.parse().map_err(|_| "invalid retry-after-ms header".into())
+}
+
+pub enum RateLimitReserveResponse {
+    // not allowed yet, nothing was recorded
+    Wait {
+        retry_after_ms: u64
+    },
+    Reserved {
+        // identifies the attempt, for releasing it
+        attempt_id: u32,
+        // whether this attempt is the one that triggered a lockout
+        locked: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct RateLimitPolicy {
+    // the sliding window, in milliseconds, that attempts are counted within
+    pub window: u64,
+    // this many attempts are allowed without any delay
+    pub free_attempts: u32,
+    // the first delay after the free attempts, doubled for each further attempt
+    pub delay_base: u64,
+    // reaching this many attempts locks the key out
+    pub max_attempts: u32,
+    // how long a lockout lasts, in milliseconds
+    pub lockout: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+enum RateLimitAction {
+    Reserve {
+        policy: RateLimitPolicy
+    },
+    Release {
+        policy: RateLimitPolicy,
+        attempt_id: u32,
+    },
+    Clear,
+}
+
+impl RateLimitAction {
+    fn to_string(&self) -> ApiResult<String> {
+        serde_json::to_string(self).map_err(|err| err.to_string().into())
+    }
+    fn from_string(action: String) -> ApiResult<Self> {
+        serde_json::from_str(action.as_str()).map_err(|err| err.to_string().into())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct RateLimitStorage {
+    // oldest first
+    attempts: Vec<RateLimitAttempt>,
+    // unix timestamp in milliseconds
+    locked_until: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RateLimitAttempt {
+    // random, see RateLimitAction::Reserve
+    id: u32,
+    // unix timestamp in milliseconds
+    at: u64,
+}
+
+impl RateLimitStorage {
+    fn retry_after_ms(&self, policy: &RateLimitPolicy, now: u64) -> u64 {
+        if self.locked_until > now {
+            return self.locked_until - now;
+        }
+
+        let count = self.attempts.len() as u32;
+        match self.attempts.last().map(|x| x.at) {
+            Some(last_attempt) if count >= policy.free_attempts => {
+                // capped so it can't overflow, and never longer than a lockout
+                let delay = (policy.delay_base << (count - policy.free_attempts).min(16)).min(policy.lockout);
+                (last_attempt + delay).saturating_sub(now)
+            },
+            _ => 0
+        }
+    }
+
+    fn record(&mut self, policy: &RateLimitPolicy, id: u32, now: u64) -> bool {
+        self.attempts.push(RateLimitAttempt { id, at: now });
+
+        if self.attempts.len() as u32 >= policy.max_attempts {
+            self.attempts.clear();
+            self.locked_until = now + policy.lockout;
+            true
+        } else {
+            false
+        }
+    }
+}
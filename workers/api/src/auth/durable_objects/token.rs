@@ -1,7 +1,9 @@
 
 use serde::{Deserialize, Serialize};
+use shared::backend::route::OpenIdProvider;
+use subtle::ConstantTimeEq;
 
-use crate::{config::AUTH_TOKEN_KEY_LENGTH, prelude::durable_object::*};
+use crate::{config::{AUTH_SIGNIN_LIFETIME_REMEMBER, AUTH_SIGNIN_LIFETIME_SESSION, AUTH_TOKEN_KEY_LENGTH, AUTH_VERIFY_EMAIL_CODE_MAX_ATTEMPTS}, prelude::durable_object::*};
 
 #[durable_object]
 pub struct AuthTokenDO {
@@ -23,18 +25,31 @@ impl DurableObject for AuthTokenDO {
         let action = req.headers().get("action")?.and_then(|x| AuthTokenAction::from_string(x).ok()).ok_or("missing action header")?;
 
         match action {
-            AuthTokenAction::Create {user_token, uid, expires_ms, kind} => {
+            AuthTokenAction::Create {user_token, uid, expires_ms, kind, actor, lifetime, openid, code_hash} => {
+                // a named token (see create_verify_email) may be replacing an older one
+                self.state.storage().delete_all().await?;
+
                 let key = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&rand::thread_rng().gen::<[u8; AUTH_TOKEN_KEY_LENGTH]>());
 
                 let mut headers = Headers::new();
                 headers.set("key", &key).unwrap();
 
+                let created_at = now_ms();
                 AuthTokenStorage{
                     user_token,
                     uid,
                     key,
-                    kind
+                    kind,
+                    created_at,
+                    actor,
+                    idle_timeout: lifetime.map(|lifetime| lifetime.idle),
+                    expires_at: lifetime.map(|lifetime| created_at + lifetime.max),
+                    openid: openid.map(|openid| serde_json::to_string(&openid).unwrap()),
+                    code_hash,
+                    terms_version: None,
                 }.save(&mut self.state.storage()).await?;
+                // with a lifetime, the idle timeout is what's extended by use
+                let expires_ms = lifetime.map(|lifetime| lifetime.idle).unwrap_or(expires_ms);
                 self.state.storage().set_alarm(Duration::from_millis(expires_ms)).await?;
 
                 Ok(Response::empty()?.with_headers(headers))
@@ -46,30 +61,53 @@ impl DurableObject for AuthTokenDO {
                     return Err("invalid kind".into());
                 }
 
-                if key != stored.key {
+                if !bool::from(key.as_bytes().ct_eq(stored.key.as_bytes())) {
                     return Err("invalid key".into());
                 }
 
-
-                match after {
-                    AuthTokenAfterValidation::Delete => {
+                self.after_validation(stored, after).await
+            }
+            AuthTokenAction::ValidateCode { code_hash, after, kind } => {
+                // a missing (expired, used) token is just a wrong code, with nothing left to try
+                let stored = match AuthTokenStorage::load(&self.state.storage()).await {
+                    Ok(stored) if stored.kind == kind && stored.code_hash.is_some() => stored,
+                    _ => return Self::code_invalid_response(0)
+                };
+
+                if !bool::from(stored.code_hash.as_deref().unwrap_or_default().as_bytes().ct_eq(code_hash.as_bytes())) {
+                    let attempts = self.state.storage().get::<u32>("code_attempts").await.unwrap_or(0) + 1;
+                    let attempts_left = AUTH_VERIFY_EMAIL_CODE_MAX_ATTEMPTS.saturating_sub(attempts);
+                    if attempts_left == 0 {
+                        // the link goes with it, a new one must be sent
                         self.state.storage().delete_alarm().await?;
                         self.state.storage().delete_all().await?;
-                    },
-                    AuthTokenAfterValidation::ExtendExpiresMs(expires_ms) => {
-                        self.state.storage().set_alarm(Duration::from_millis(expires_ms)).await?;
+                    } else {
+                        self.state.storage().put("code_attempts", attempts).await?;
                     }
+                    return Self::code_invalid_response(attempts_left);
                 }
 
-                let mut headers = Headers::new();
-                headers.set("uid", &stored.uid.to_string()).unwrap();
-                headers.set("user-token", &stored.user_token).unwrap();
-                Ok(Response::empty()?.with_headers(headers))
+                self.after_validation(stored, after).await
+            }
+            AuthTokenAction::SetTermsVersion { version } => {
+                // only for a token that's still there, this mustn't bring an expired one back
+                if AuthTokenStorage::load(&self.state.storage()).await.is_ok() {
+                    self.state.storage().put("terms_version", version).await?;
+                }
+                Response::empty()
             }
             AuthTokenAction::Destroy => {
+                // it may be gone already, that's fine
+                let stored = AuthTokenStorage::load(&self.state.storage()).await.ok();
+
                 self.state.storage().delete_alarm().await?;
                 self.state.storage().delete_all().await?;
-                Response::empty()
+
+                let mut headers = Headers::new();
+                if let Some(openid) = stored.and_then(|stored| stored.openid) {
+                    headers.set("openid", &openid).unwrap();
+                }
+                Ok(Response::empty()?.with_headers(headers))
             }
         }
     }
@@ -96,22 +134,137 @@ impl AuthTokenDO {
         env.durable_object(Self::NAMESPACE)?.id_from_string(id)?.get_stub().map_err(|err| err.into())
     }
 
+    async fn after_validation(&mut self, stored: AuthTokenStorage, after: AuthTokenAfterValidation) -> worker::Result<Response> {
+        match after {
+            AuthTokenAfterValidation::Delete => {
+                self.state.storage().delete_alarm().await?;
+                self.state.storage().delete_all().await?;
+            },
+            AuthTokenAfterValidation::ExtendExpiresMs(expires_ms) => {
+                self.state.storage().set_alarm(Duration::from_millis(expires_ms)).await?;
+            },
+            AuthTokenAfterValidation::ExtendIdle => {
+                let now = now_ms();
+                let (idle_timeout, expires_at) = match (stored.idle_timeout, stored.expires_at) {
+                    (Some(idle_timeout), Some(expires_at)) => (idle_timeout, expires_at),
+                    // tokens created before lifetimes were stored have none, rather than signing everyone out
+                    // they get the remember-me lifetime, counted from when they were created (or from now, if that's not known either)
+                    // and it's stored, so it stays put from here on
+                    _ => {
+                        let lifetime = AUTH_SIGNIN_LIFETIME_REMEMBER;
+                        let expires_at = if stored.created_at == 0 { now } else { stored.created_at } + lifetime.max;
+                        self.state.storage().put("idle_timeout", lifetime.idle).await?;
+                        self.state.storage().put("expires_at", expires_at).await?;
+                        (lifetime.idle, expires_at)
+                    }
+                };
+
+                if now >= expires_at {
+                    self.state.storage().delete_alarm().await?;
+                    self.state.storage().delete_all().await?;
+                    return Err("token expired".into());
+                }
+
+                self.state.storage().set_alarm(Duration::from_millis(idle_timeout.min(expires_at - now))).await?;
+            },
+            AuthTokenAfterValidation::Keep => {}
+        }
+
+        let mut headers = Headers::new();
+        headers.set("uid", &stored.uid.to_string()).unwrap();
+        headers.set("user-token", &stored.user_token).unwrap();
+        headers.set("created-at", &stored.created_at.to_string()).unwrap();
+        if let Some(actor) = stored.actor {
+            headers.set("actor", &actor.to_string()).unwrap();
+        }
+        if let Some(terms_version) = stored.terms_version {
+            headers.set("terms-version", &terms_version.to_string()).unwrap();
+        }
+        Ok(Response::empty()?.with_headers(headers))
+    }
+
+    fn code_invalid_response(attempts_left: u32) -> worker::Result<Response> {
+        let mut headers = Headers::new();
+        headers.set("code-attempts-left", &attempts_left.to_string()).unwrap();
+        Ok(Response::empty()?.with_headers(headers))
+    }
+
     pub async fn create(env: &Env, kind: AuthTokenKind, uid: UserId, user_token: String, expires_ms: u64) -> ApiResult<AuthTokenCreateResponse> {
-        let mut do_headers = Headers::new();
-        do_headers.append("action", &AuthTokenAction::Create{
+        Self::create_inner(env, kind, uid, user_token, expires_ms, None, None, None).await
+    }
+
+    // signin tokens are extended by use (see AuthTokenAfterValidation::ExtendIdle), but only up to their max lifetime
+    pub async fn create_signin(env: &Env, uid: UserId, user_token: String, remember_me: bool) -> ApiResult<AuthTokenCreateResponse> {
+        Self::create_signin_inner(env, uid, user_token, remember_me, None).await
+    }
+
+    // a signin through an openid provider, which is remembered for signing out of it too
+    pub async fn create_signin_openid(env: &Env, uid: UserId, user_token: String, remember_me: bool, openid: AuthTokenOpenId) -> ApiResult<AuthTokenCreateResponse> {
+        Self::create_signin_inner(env, uid, user_token, remember_me, Some(openid)).await
+    }
+
+    async fn create_signin_inner(env: &Env, uid: UserId, user_token: String, remember_me: bool, openid: Option<AuthTokenOpenId>) -> ApiResult<AuthTokenCreateResponse> {
+        let lifetime = if remember_me { AUTH_SIGNIN_LIFETIME_REMEMBER } else { AUTH_SIGNIN_LIFETIME_SESSION };
+        let mut res = Self::create_inner(env, AuthTokenKind::Signin, uid, user_token, lifetime.idle, None, Some(lifetime), openid).await?;
+        // without remember-me, it's a session cookie that's gone when the browser closes
+        if remember_me {
+            res.cookie_expires_at = Some(now_ms() + lifetime.max);
+        }
+        Ok(res)
+    }
+
+    // the token is for the impersonated uid, and remembers the admin that is acting as them
+    pub async fn create_impersonation(env: &Env, uid: UserId, user_token: String, actor: UserId, expires_ms: u64) -> ApiResult<AuthTokenCreateResponse> {
+        Self::create_inner(env, AuthTokenKind::Impersonation, uid, user_token, expires_ms, Some(actor), None, None).await
+    }
+
+    // there's only ever one email verification per user, so it can also be found by uid (see verify_email_id)
+    // sending a new one replaces the old link and code
+    pub async fn create_verify_email(env: &Env, uid: UserId, user_token: String, expires_ms: u64, code_hash: String) -> ApiResult<AuthTokenCreateResponse> {
+        let id = Self::verify_email_id(env, &uid)?;
+
+        Self::create_at(env, id, AuthTokenAction::Create{
+            kind: AuthTokenKind::VerifyEmail,
+            uid,
+            user_token,
+            expires_ms,
+            actor: None,
+            lifetime: None,
+            openid: None,
+            code_hash: Some(code_hash),
+        }).await
+    }
+
+    pub fn verify_email_id(env: &Env, uid: &UserId) -> ApiResult<String> {
+        Ok(env.durable_object(Self::NAMESPACE)?.id_from_name(&format!("verify-email-{uid}"))?.to_string())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn create_inner(env: &Env, kind: AuthTokenKind, uid: UserId, user_token: String, expires_ms: u64, actor: Option<UserId>, lifetime: Option<AuthTokenLifetime>, openid: Option<AuthTokenOpenId>) -> ApiResult<AuthTokenCreateResponse> {
+        let id = env.durable_object(Self::NAMESPACE)?.unique_id()?.to_string();
+
+        Self::create_at(env, id, AuthTokenAction::Create{
             kind,
             uid,
             user_token,
-            expires_ms
-        }.to_string()?)?;
+            expires_ms,
+            actor,
+            lifetime,
+            openid,
+            code_hash: None,
+        }).await
+    }
+
+    async fn create_at(env: &Env, id: String, action: AuthTokenAction) -> ApiResult<AuthTokenCreateResponse> {
+        let mut do_headers = Headers::new();
+        do_headers.append("action", &action.to_string()?)?;
 
         let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
-        let id = env.durable_object(Self::NAMESPACE)?.unique_id()?.to_string();
         // the stub is from id, not uid
         let res = Self::stub(env, &id)?.fetch_with_request(req).await?;
         let key = res.headers().get("key")?.ok_or("missing key header")?;
 
-        Ok(AuthTokenCreateResponse { id, key })
+        Ok(AuthTokenCreateResponse { id, key, cookie_expires_at: None })
     }
 
     pub async fn validate(env: &Env, kind: AuthTokenKind, id: &str, key: String, after: AuthTokenAfterValidation) -> ApiResult<AuthTokenValidateResponse> {
@@ -120,10 +273,50 @@ impl AuthTokenDO {
 
         let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
         let res = Self::stub(env, id)?.fetch_with_request(req).await?;
+        Self::validate_response(&res)
+    }
+
+    // the code is a short alternative to the key, so it only gets a few tries before the whole token is gone
+    pub async fn validate_code(env: &Env, kind: AuthTokenKind, id: &str, code_hash: String, after: AuthTokenAfterValidation) -> ApiResult<AuthTokenValidateResponse> {
+        let mut do_headers = Headers::new();
+        do_headers.append("action", &AuthTokenAction::ValidateCode { code_hash, after, kind }.to_string()?)?;
+
+        let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
+        let res = Self::stub(env, id)?.fetch_with_request(req).await?;
+        if let Some(attempts_left) = res.headers().get("code-attempts-left")? {
+            let attempts_left = attempts_left.parse().map_err(|_| "invalid code-attempts-left header")?;
+            return Err(AuthError::InvalidVerificationCode { attempts_left }.into());
+        }
+        Self::validate_response(&res)
+    }
+
+    fn validate_response(res: &Response) -> ApiResult<AuthTokenValidateResponse> {
         let uid:UserId = res.headers().get("uid")?.ok_or("missing uid header")?.try_into()?;
         let user_token = res.headers().get("user-token")?.ok_or("missing user-token header")?;
+        let created_at = res.headers().get("created-at")?.ok_or("missing created-at header")?.parse().map_err(|_| "invalid created-at header")?;
+        let actor = match res.headers().get("actor")? {
+            Some(actor) => Some(actor.try_into()?),
+            None => None
+        };
+
+        let terms_version = match res.headers().get("terms-version")? {
+            Some(terms_version) => Some(terms_version.parse().map_err(|_| "invalid terms-version header")?),
+            None => None
+        };
+
+        Ok(AuthTokenValidateResponse { uid, user_token, created_at, actor, terms_version })
+    }
 
-        Ok(AuthTokenValidateResponse { uid, user_token })
+    // remembers in the signin token which terms version its user has accepted
+    // so the terms check doesn't have to ask the db on every request
+    pub async fn set_terms_version(env: &Env, id: &str, version: u32) -> ApiResult<()> {
+        let mut do_headers = Headers::new();
+        do_headers.append("action", &AuthTokenAction::SetTermsVersion { version }.to_string()?)?;
+
+        let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
+        Self::stub(env, id)?.fetch_with_request(req).await?;
+
+        Ok(())
     }
 
     pub async fn destroy(env: &Env, id: &str) -> ApiResult<()> {
@@ -135,6 +328,20 @@ impl AuthTokenDO {
 
         Ok(())
     }
+
+    // same as destroy, but returns the provider it was signed in through, if any
+    pub async fn destroy_signin(env: &Env, id: &str) -> ApiResult<Option<AuthTokenOpenId>> {
+        let mut do_headers = Headers::new();
+        do_headers.append("action", &AuthTokenAction::Destroy.to_string()?)?;
+
+        let req = Request::new_with_init("http://internal", RequestInit::new().with_headers(do_headers))?;
+        let res = Self::stub(env, id)?.fetch_with_request(req).await?;
+
+        match res.headers().get("openid")? {
+            Some(openid) => Ok(Some(serde_json::from_str(&openid).map_err(|err| err.to_string())?)),
+            None => Ok(None)
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -144,12 +351,25 @@ enum AuthTokenAction {
         uid: UserId,
         expires_ms: u64,
         kind: AuthTokenKind,
+        actor: Option<UserId>,
+        lifetime: Option<AuthTokenLifetime>,
+        openid: Option<AuthTokenOpenId>,
+        code_hash: Option<String>,
     },
     Validate {
         key: String,
         kind: AuthTokenKind,
         after: AuthTokenAfterValidation 
     },
+    ValidateCode {
+        // never the code itself
+        code_hash: String,
+        kind: AuthTokenKind,
+        after: AuthTokenAfterValidation,
+    },
+    SetTermsVersion {
+        version: u32,
+    },
     Destroy,
 }
 impl AuthTokenAction {
@@ -166,11 +386,33 @@ struct AuthTokenStorage {
     user_token: String,
     uid: UserId,
     key: String,
-    kind: AuthTokenKind
+    kind: AuthTokenKind,
+    // unix timestamp in milliseconds
+    created_at: u64,
+    // only for impersonation tokens, the admin acting as uid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actor: Option<UserId>,
+    // only for signin tokens, see AuthTokenLifetime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    idle_timeout: Option<u64>,
+    // unix timestamp in milliseconds, never extended past this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    // only for signins through an openid provider, the json of AuthTokenOpenId
+    #[serde(skip_serializing_if = "Option::is_none")]
+    openid: Option<String>,
+    // only for email verification, the hash of the code that can be entered instead of following the link
+    // wrong guesses are counted in "code_attempts"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_hash: Option<String>,
+    // only for signin tokens, the latest terms version the user is known to have accepted (see AuthUser::try_new)
+    // it's a cache, the terms_acceptance table is what counts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    terms_version: Option<u32>,
 }
 
 impl AuthTokenStorage {
-    const KEYS: [&'static str; 4] = ["user_token", "uid", "key", "kind"];
+    const KEYS: [&'static str; 11] = ["user_token", "uid", "key", "kind", "created_at", "actor", "idle_timeout", "expires_at", "openid", "code_hash", "terms_version"];
 
     async fn save(&self, storage: &mut Storage) -> worker::Result<()> {
         storage.put_multiple(self).await.map_err(|err| err.into())
@@ -183,12 +425,30 @@ impl AuthTokenStorage {
         let key = map.get(&JsValue::from_str("key")).as_string().ok_or("missing key")?;
         let uid:UserId = map.get(&JsValue::from_str("uid")).as_string().ok_or("missing uid")?.try_into()?;
         let kind:AuthTokenKind = map.get(&JsValue::from_str("kind")).as_string().ok_or("missing kind")?.try_into()?;
+        // tokens created before this was tracked are treated as ancient
+        let created_at = map.get(&JsValue::from_str("created_at")).as_f64().map(|x| x as u64).unwrap_or(0);
+        let actor = match map.get(&JsValue::from_str("actor")).as_string() {
+            Some(actor) => Some(actor.try_into()?),
+            None => None
+        };
+        let idle_timeout = map.get(&JsValue::from_str("idle_timeout")).as_f64().map(|x| x as u64);
+        let expires_at = map.get(&JsValue::from_str("expires_at")).as_f64().map(|x| x as u64);
+        let openid = map.get(&JsValue::from_str("openid")).as_string();
+        let code_hash = map.get(&JsValue::from_str("code_hash")).as_string();
+        let terms_version = map.get(&JsValue::from_str("terms_version")).as_f64().map(|x| x as u32);
 
         Ok(Self {
             user_token,
             uid,
             key,
             kind,
+            created_at,
+            actor,
+            idle_timeout,
+            expires_at,
+            openid,
+            code_hash,
+            terms_version,
         })
     }
 }
@@ -197,18 +457,45 @@ impl AuthTokenStorage {
 pub struct AuthTokenCreateResponse {
     pub id: String,
     pub key: String,
+    // unix timestamp in milliseconds, for a persistent signin cookie
+    // None means a session cookie (or a token that isn't sent as a cookie at all)
+    pub cookie_expires_at: Option<u64>,
 }
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AuthTokenValidateResponse {
     pub uid: UserId,
     pub user_token: String,
+    // unix timestamp in milliseconds
+    pub created_at: u64,
+    pub actor: Option<UserId>,
+    // see AuthTokenStorage
+    pub terms_version: Option<u32>,
 }
 
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum AuthTokenAfterValidation {
     Delete,
-    ExtendExpiresMs(u64)
+    ExtendExpiresMs(u64),
+    // extend by the token's own idle timeout, capped at its max lifetime (signin tokens)
+    ExtendIdle,
+    // the expiry is absolute, e.g. for impersonation
+    Keep,
+}
+
+// Which openid provider a signin came through, for RP-initiated logout
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthTokenOpenId {
+    pub provider: OpenIdProvider,
+    // sent back to the provider as a hint of who's signing out
+    pub id_token: Option<String>,
+}
+
+// How long a signin lasts: it expires after `idle` without use, and after `max` no matter what
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct AuthTokenLifetime {
+    pub idle: u64,
+    pub max: u64,
 }
 
 
@@ -216,7 +503,11 @@ pub enum AuthTokenAfterValidation {
 pub enum AuthTokenKind {
     Signin,
     PasswordReset,
-    VerifyEmail
+    VerifyEmail,
+    MagicLink,
+    Impersonation,
+    // created with the inviter's uid, the invitee is whoever signs in with the invited email
+    OrganizationInvite,
 }
 
 impl TryFrom<String> for AuthTokenKind {
@@ -227,6 +518,9 @@ impl TryFrom<String> for AuthTokenKind {
             "signin" => Ok(Self::Signin),
             "passwordreset" => Ok(Self::PasswordReset),
             "verifyemail" => Ok(Self::VerifyEmail),
+            "magiclink" => Ok(Self::MagicLink),
+            "impersonation" => Ok(Self::Impersonation),
+            "organizationinvite" => Ok(Self::OrganizationInvite),
             _ => Err("invalid kind")
         }
     }
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+use crate::prelude::*;
+
+const DISPOSABLE_DOMAINS: &str = include_str!("disposable_domains.txt");
+
+// Which emails may be used for an account at all, read from the EMAIL_DOMAIN_RULES var (a JSON object, see wrangler.toml)
+// unlike the registration policy, these also apply to openid signins, and there's no getting around them with an invite
+// domains match themselves and their subdomains, case-insensitively
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct EmailDomainRules {
+    // if not empty, only these domains
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    // the embedded list in disposable_domains.txt
+    pub block_disposable: bool,
+}
+
+impl EmailDomainRules {
+    const ENV_VAR: &'static str = "EMAIL_DOMAIN_RULES";
+
+    pub fn load(env: &Env) -> ApiResult<Self> {
+        match env.var(Self::ENV_VAR) {
+            Ok(var) => serde_json::from_str(&var.to_string()).map_err(|err| format!("invalid {}: {}", Self::ENV_VAR, err).into()),
+            // not configured means any email
+            Err(_) => Ok(Self::default())
+        }
+    }
+
+    pub fn check(&self, email: &str) -> ApiResult<()> {
+        let domain = email.rsplit_once('@').map(|(_, domain)| domain.trim().to_lowercase()).unwrap_or_default();
+
+        if !self.allow.is_empty() && !self.allow.iter().any(|allowed| domain_matches(&domain, allowed)) {
+            return Err(AuthError::EmailNotAllowed.into());
+        }
+
+        if self.deny.iter().any(|denied| domain_matches(&domain, denied)) {
+            return Err(AuthError::EmailNotAllowed.into());
+        }
+
+        // an explicitly allowed domain is never considered disposable
+        if self.block_disposable && self.allow.is_empty() && is_disposable(&domain) {
+            return Err(AuthError::DisposableEmail.into());
+        }
+
+        Ok(())
+    }
+}
+
+pub fn check_email_allowed(env: &Env, email: &str) -> ApiResult<()> {
+    EmailDomainRules::load(env)?.check(email)
+}
+
+fn is_disposable(domain: &str) -> bool {
+    DISPOSABLE_DOMAINS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .any(|disposable| domain_matches(domain, disposable))
+}
+
+// domain is already lowercased
+fn domain_matches(domain: &str, rule: &str) -> bool {
+    let rule = rule.trim().trim_start_matches('.').to_lowercase();
+
+    if rule.is_empty() {
+        return false;
+    }
+
+    domain == rule || domain.strip_suffix(&rule).is_some_and(|rest| rest.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::domain_matches;
+
+    #[test]
+    fn matches_domain_and_subdomains() {
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(domain_matches("mail.example.com", "example.com"));
+        assert!(domain_matches("a.b.example.com", "example.com"));
+    }
+
+    #[test]
+    fn rule_is_normalized() {
+        assert!(domain_matches("example.com", "Example.COM"));
+        assert!(domain_matches("example.com", " example.com "));
+        assert!(domain_matches("example.com", ".example.com"));
+        assert!(domain_matches("mail.example.com", ".example.com"));
+    }
+
+    #[test]
+    fn suffix_is_not_a_subdomain() {
+        assert!(!domain_matches("evilexample.com", "example.com"));
+        assert!(!domain_matches("example.com.evil.net", "example.com"));
+        assert!(!domain_matches("example.com", "mail.example.com"));
+        assert!(!domain_matches("example.org", "example.com"));
+    }
+
+    #[test]
+    fn empty_rule_matches_nothing() {
+        assert!(!domain_matches("example.com", ""));
+        assert!(!domain_matches("example.com", "   "));
+        assert!(!domain_matches("example.com", "."));
+    }
+}
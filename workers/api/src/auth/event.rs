@@ -0,0 +1,20 @@
+use shared::{api::account::AuthEventKind, user::UserId};
+use crate::{db::auth_event::{AuthEvent, AuthEventInsert}, ApiContext};
+
+// Records an entry in the security audit log, with the client info from the request
+// a failure here is only logged, so it can't break (or, for signin, change the error of) the request itself
+pub async fn record_auth_event(ctx: &ApiContext, kind: AuthEventKind, uid: Option<&UserId>, email: Option<&str>, detail: Option<&str>) {
+    let event = AuthEventInsert {
+        uid,
+        email,
+        kind,
+        detail,
+        ip: ctx.client_ip(),
+        user_agent: ctx.user_agent(),
+        request_id: ctx.request_id(),
+    };
+
+    if let Err(err) = AuthEvent::insert(&ctx.env, event).await {
+        worker::console_warn!("failed to record auth event {}: {:?}", kind.as_str(), err);
+    }
+}
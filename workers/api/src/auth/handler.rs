@@ -1,15 +1,18 @@
-mod openid;
+mod oauth2;
+pub(super) mod openid;
+mod rate_limit;
 mod util;
 
 use async_trait::async_trait;
 use base64::Engine;
 use rand::Rng;
-use shared::{api::{auth::{AuthCheck, AuthCheckResetPassword, AuthCheckResetPasswordRequest, AuthCheckResetPasswordResponse, AuthCheckResponse, AuthConfirmResetPassword, AuthConfirmResetPasswordRequest, AuthConfirmResetPasswordResponse, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailRequest, AuthOpenIdAccessTokenHook, AuthOpenIdConnect, AuthOpenIdConnectRequest, AuthOpenIdConnectResponse, AuthOpenIdFinalizeExec, AuthOpenIdFinalizeExecResponse, AuthOpenIdFinalizeQuery, AuthOpenIdFinalizeQueryResponse, AuthOpenIdFinalizeRequest, AuthRegister, AuthRegisterRequest, AuthRegisterResponse, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendResetPasswordRequestAny, AuthSendVerifyEmail, AuthSignin, AuthSigninRequest, AuthSigninResponse, AuthSignout}, ApiBoth, ApiReq, ApiRes}, backend::{result::{ApiError, ApiResult, AuthError}, worker::ResponseExt}, frontend::route::NotFoundReason as FrontendNotFoundReason, user::UserId};
+use shared::{api::{account::AuthEventKind, admin::ImpersonationEvent, auth::{AuthCheck, AuthCheckResetPassword, AuthCheckResetPasswordRequest, AuthCheckResetPasswordResponse, AuthCheckResponse, AuthConfirmResetPassword, AuthConfirmResetPasswordRequest, AuthConfirmResetPasswordResponse, AuthConfirmMagicLink, AuthConfirmMagicLinkRequest, AuthConfirmMagicLinkResponse, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailCode, AuthConfirmVerifyEmailCodeRequest, AuthConfirmVerifyEmailRequest, AuthIdentities, AuthIdentitiesResponse, AuthIdentityUnlink, AuthIdentityUnlinkRequest, AuthImpersonationStop, AuthInviteCheck, AuthInviteCheckRequest, AuthInviteCheckResponse, AuthOpenIdAccessTokenHook, AuthOpenIdConnect, AuthOpenIdConnectRequest, AuthOpenIdConnectResponse, AuthOpenIdFinalizeExec, AuthOpenIdFinalizeExecResponse, AuthOpenIdFinalizeQuery, AuthOpenIdFinalizeQueryResponse, AuthOpenIdFinalizeRequest, AuthOpenIdFinalizeStatus, AuthOpenIdLinkConnect, AuthOpenIdLinkFinalize, AuthOpenIdProviderInfo, AuthOpenIdProviders, AuthOpenIdProvidersResponse, AuthPasswordParams, AuthPasswordParamsRequest, AuthPasswordParamsResponse, AuthRegister, AuthRegisterRequest, AuthRegisterResponse, AuthSendMagicLink, AuthSendMagicLinkRequest, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendResetPasswordRequestAny, AuthSendVerifyEmail, AuthSignin, AuthSigninRecoveryCode, AuthSigninRecoveryCodeRequest, AuthSigninRecoveryCodeResponse, AuthSigninRequest, AuthSigninResponse, AuthSignout, AuthSignoutResponse, AuthTermsAccept, AuthTermsAcceptRequest, AuthTermsStatus, AuthTermsStatusResponse}, ApiBoth, ApiReq, ApiRes}, backend::{result::{ApiError, ApiResult, AuthError}, worker::ResponseExt}, auth::{PasswordKdf, PasswordParams}, frontend::route::NotFoundReason as FrontendNotFoundReason, user::UserId};
 use web_sys::Response;
 use crate::{
-    api_ext::{ApiBothExt, ApiBothWithExtraExt, ApiEmptyDynRouteWithExtraExt, ApiEmptyExt, ApiReqExt, ApiResExt}, auth::{durable_objects::token::{AuthTokenDO, AuthTokenKind}, handler::util::hash_password}, config::{AUTH_RESET_PASSWORD_TOKEN_EXPIRES, AUTH_SIGNIN_TOKEN_EXPIRES, AUTH_VERIFY_EMAIL_TOKEN_EXPIRES, FRONTEND_DOMAIN, FRONTEND_ROOT_PATH, OAUTH_REGISTER_PASSWORD_LENGTH}, db::user::UserAccount, mailer::{self, MailerKind}, ApiContext
+    api_ext::{ApiBothExt, ApiBothWithExtraExt, ApiEmptyDynRouteWithExtraExt, ApiEmptyExt, ApiReqExt, ApiResExt}, api_token::hash_secret, auth::{check_challenge, check_email_allowed, check_sso_policy, sso_provider_for_email, ChallengeRoute, durable_objects::token::{AuthTokenDO, AuthTokenKind, AuthTokenOpenId, AuthTokenValidateResponse}, handler::util::{derived_password_salt, hash_password, password_params, password_params_upgrade, password_salt_key, random_password_salt, upgrade_password, validate_password_salt, verify_email_code}, record_auth_event, AuthCredentials}, config::{AUTH_MAGIC_LINK_TOKEN_EXPIRES, AUTH_RATE_LIMIT_SIGNIN_EMAIL, AUTH_RESET_PASSWORD_TOKEN_EXPIRES, AUTH_VERIFY_EMAIL_TOKEN_EXPIRES, FRONTEND_DOMAIN, FRONTEND_ROOT_PATH, OAUTH_REGISTER_PASSWORD_LENGTH, TERMS_VERSION}, db::{api_token::ApiToken, identity::UserIdentity, impersonation::ImpersonationLog, invite::Invite, profile::UserProfileDb, recovery_code::RecoveryCode, terms_acceptance::TermsAcceptance, user::UserAccount}, helpers::now_ms, invite::check_registration, recovery_code::hash_recovery_code, mailer::{self, MailerKind}, ApiContext
 };
-use self::{openid::OpenIdProcessor, util::{delete_signin_cookie, set_signin_cookie, validate_oob_token}};
+use self::{openid::{OpenIdProcessor, OpenIdProviderConfig}, rate_limit::{RateLimitScope, RateLimiter}, util::{set_signin_cookie, validate_oob_token}};
+pub(crate) use self::util::{verify_password, delete_signin_cookie};
 use super::durable_objects::{openid::{OpenIdSession, OpenIdSessionDO, OpenIdSessionFinalizeInfo}, token::{AuthTokenAfterValidation, AuthTokenCreateResponse}};
 use shared::frontend::route::{Route as FrontendRoute, Landing as FrontendLanding, AuthRoute as FrontendAuthRoute};
 
@@ -21,27 +24,38 @@ impl ApiBothWithExtraExt for AuthSignin {
     type Extra = AuthTokenCreateResponse;
 
     async fn handle(ctx: &ApiContext, data: AuthSigninRequest) -> ApiResult<(Self::Res, Self::Extra)> {
-        async fn inner(ctx: &ApiContext, data: AuthSigninRequest) -> ApiResult<(AuthSigninResponse, AuthTokenCreateResponse)> {
-            let AuthSigninRequest { email, password } = data;
-            let user = UserAccount::load_by_email(&ctx.env, &email).await?;
-
-            // see registration, this is *not* the user's plaintext password, it's just the argon2 output hash
-            // we need to get the salt from the db and hash it again for comparison, however
-            let db_salt = &base64::engine::general_purpose::URL_SAFE_NO_PAD
-                .decode(&user.password)
-                .map_err(|err| ApiError::from(err.to_string()))?
-                [0..32];
-
-            let req_password = hash_password(&password, Some(db_salt))?;
-
-            // see if they match
-            if user.password != req_password {
-                return Err("mismatched password".into())
+        // why a signin failed, this is only for the audit log
+        struct SigninFailure {
+            uid: Option<UserId>,
+            reason: &'static str,
+        }
+
+        async fn inner(ctx: &ApiContext, data: AuthSigninRequest) -> Result<(AuthSigninResponse, AuthTokenCreateResponse), SigninFailure> {
+            let AuthSigninRequest { email, password, remember_me, upgrade_password: upgrade, upgrade_salt } = data;
+            let user = UserAccount::load_by_email(&ctx.env, &email).await.map_err(|_| SigninFailure { uid: None, reason: "no_account" })?;
+
+            verify_password(&user, &password).map_err(|_| SigninFailure { uid: Some(user.id.clone()), reason: "wrong_password" })?;
+
+            // now that we know the password, bring its hash up to date if needed
+            // not worth failing the signin over, it'll be tried again next time
+            match upgrade_password(&user, &password, upgrade.as_deref(), upgrade_salt.as_deref()) {
+                Ok(Some(upgraded)) => {
+                    if let Err(err) = UserAccount::update_password(&ctx.env, &user.id, &upgraded.password, upgraded.client_salt.as_deref()).await {
+                        worker::console_warn!("failed to upgrade password hash: {:?}", err);
+                    }
+                },
+                Ok(None) => {},
+                Err(err) => {
+                    worker::console_warn!("failed to upgrade password hash: {:?}", err);
+                }
             }
 
             // sign the user in and return
             let uid = user.id;
-            let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::Signin, uid.clone(), user.user_token, AUTH_SIGNIN_TOKEN_EXPIRES).await?;
+            let auth_token = AuthTokenDO::create_signin(&ctx.env, uid.clone(), user.user_token, remember_me).await.map_err(|err| {
+                worker::console_warn!("failed to create signin token: {:?}", err);
+                SigninFailure { uid: Some(uid.clone()), reason: "error" }
+            })?;
             let auth_key = auth_token.key.clone();
             Ok((AuthSigninResponse{
                 uid,
@@ -50,20 +64,95 @@ impl ApiBothWithExtraExt for AuthSignin {
             }, auth_token))
         }
 
-        inner(ctx, data).await.map_err(|_| {
-            // can log the specific error here, but clients only see InvalidSignin
-            // to avoid leaking semi-sensitive info (like who has an account etc.)
-            AuthError::InvalidSignin.into()
-        })
+        // the password isn't even looked at for a domain that must use its sso provider
+        // so it doesn't count towards the lockout either
+        if let Err(err) = check_sso_policy(&ctx.env, &data.email, None).await {
+            if let ApiError::Auth(AuthError::SsoRequired { .. }) = err {
+                let uid = signin_event_uid(ctx, &data.email).await;
+                record_auth_event(ctx, AuthEventKind::SigninFailure, uid.as_ref(), Some(&data.email), Some("sso_required")).await;
+            }
+            return Err(err);
+        }
+
+        // counted per email whether or not the account exists, so this doesn't leak anything either
+        // reserved up front, so parallel guesses are all counted
+        let rate_limiter = RateLimiter::new(ctx, RateLimitScope::Signin, &data.email);
+        let reservation = match rate_limiter.reserve().await {
+            Ok(reservation) => reservation,
+            Err(err) => {
+                if let ApiError::Auth(AuthError::TooManyAttempts { .. }) = err {
+                    let uid = signin_event_uid(ctx, &data.email).await;
+                    record_auth_event(ctx, AuthEventKind::SigninFailure, uid.as_ref(), Some(&data.email), Some("too_many_attempts")).await;
+                }
+                return Err(err);
+            }
+        };
+
+        let email = data.email.clone();
+        match inner(ctx, data).await {
+            Ok(res) => {
+                record_auth_event(ctx, AuthEventKind::SigninSuccess, Some(&res.0.uid), Some(&email), Some("password")).await;
+                rate_limiter.succeeded(reservation).await?;
+                Ok(res)
+            },
+            Err(failure) => {
+                record_auth_event(ctx, AuthEventKind::SigninFailure, failure.uid.as_ref(), Some(&email), Some(failure.reason)).await;
+                if reservation.locked {
+                    // let the real owner know, if there is one
+                    if let Ok(user) = UserAccount::load_by_email(&ctx.env, &email).await {
+                        if let Err(err) = mailer::send(ctx, &user.email, MailerKind::AccountLocked { lockout_ms: AUTH_RATE_LIMIT_SIGNIN_EMAIL.lockout }).await {
+                            worker::console_warn!("failed to send lockout email: {:?}", err);
+                        }
+                    }
+                }
+                // the reason is only in the audit log, clients only see InvalidSignin
+                // to avoid leaking semi-sensitive info (like who has an account etc.)
+                Err(AuthError::InvalidSignin.into())
+            }
+        }
     }
 
     fn response(_ctx: &ApiContext, data: AuthSigninResponse, auth_token: AuthTokenCreateResponse) -> Response {
         let res = Response::new_json(&data);
-        set_signin_cookie(&res, &auth_token.id);
+        set_signin_cookie(&res, &auth_token);
         res
     }
 }
 
+// failures that are caught before the password is checked still belong to the account, if there is one
+// only for the audit log, the client never sees it
+async fn signin_event_uid(ctx: &ApiContext, email: &str) -> Option<UserId> {
+    UserAccount::load_by_email(&ctx.env, email).await.ok().map(|account| account.id)
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for AuthPasswordParams {
+    type Req = <AuthPasswordParams as ApiBoth>::Req;
+    type Res = <AuthPasswordParams as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: AuthPasswordParamsRequest) -> ApiResult<AuthPasswordParamsResponse> {
+        // computed for every email, whether it's used or not, so both paths fail and take the same
+        let derived_salt = derived_password_salt(&password_salt_key(&ctx.env)?, &data.email)?;
+
+        let params = match UserAccount::load_by_email(&ctx.env, &data.email).await {
+            Ok(account) => password_params(&account)?,
+            // looks just like an up-to-date account
+            Err(_) => PasswordParams {
+                kdf: PasswordKdf::current(),
+                salt: Some(derived_salt.clone()),
+            }
+        };
+
+        // the upgrade is stable across lookups, and worked out the same way for the fake params
+        let upgrade = password_params_upgrade(&params, &derived_salt);
+
+        Ok(AuthPasswordParamsResponse {
+            params,
+            upgrade,
+        })
+    }
+}
+
 #[async_trait(?Send)]
 impl ApiBothWithExtraExt for AuthRegister {
     type Req = <AuthRegister as ApiBoth>::Req;
@@ -71,26 +160,37 @@ impl ApiBothWithExtraExt for AuthRegister {
     type Extra = AuthTokenCreateResponse;
 
     async fn handle(ctx: &ApiContext, data: AuthRegisterRequest) -> ApiResult<(Self::Res, Self::Extra)> {
-        let AuthRegisterRequest {email, password} = data;
+        let AuthRegisterRequest {email, password, salt, invite_code, challenge_token} = data;
+        check_challenge(ctx, ChallengeRoute::Register, challenge_token.as_deref()).await?;
+        validate_password_salt(&salt)?;
+
+        RateLimiter::new(ctx, RateLimitScope::Register, &email).hit().await?;
+        // accounts on a claimed domain are created by signing in through its provider instead
+        check_sso_policy(&ctx.env, &email, None).await?;
 
         if UserAccount::exists_by_email(&ctx.env, &email).await? {
             return Err(AuthError::EmailAlreadyExists.into())
         }
 
-        let password = hash_password(&password, None)?;
+        check_email_allowed(&ctx.env, &email)?;
+        let registration = check_registration(&ctx.env, &email, invite_code.as_deref()).await?;
+
+        let password = hash_password(&password, PasswordKdf::current())?;
 
         // create a new user account
         let uid = UserId::new(uuid::Uuid::now_v7());
         let user_token = uuid::Uuid::now_v7().as_simple().to_string();
-        UserAccount::insert(&ctx.env, &uid, &password, &email, &user_token).await?;
+        UserAccount::insert(&ctx.env, &uid, &password, &salt, &email, &user_token).await?;
+        registration.consume(&ctx.env, &uid, &email).await?;
+        // the register page says that signing up is agreeing to the terms
+        TermsAcceptance::insert(&ctx.env, &uid, TERMS_VERSION, ctx.client_ip().as_deref()).await?;
+        record_auth_event(ctx, AuthEventKind::Register, Some(&uid), Some(&email), Some("password")).await;
 
         // sign the user in and return
-        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::Signin, uid.clone(), user_token.clone(), AUTH_SIGNIN_TOKEN_EXPIRES).await?;
+        // only the signin form asks about remember-me, every other way in is remembered
+        let auth_token = AuthTokenDO::create_signin(&ctx.env, uid.clone(), user_token.clone(), true).await?;
 
-        mailer::send(&ctx, &email, MailerKind::EmailVerification { 
-            oob_token_id: auth_token.id.clone(), 
-            oob_token_key: auth_token.key.clone()
-        }).await?;
+        send_verify_email(ctx, &uid, &user_token, &email).await?;
 
         let auth_key = auth_token.key.clone();
         Ok((AuthRegisterResponse{
@@ -102,7 +202,7 @@ impl ApiBothWithExtraExt for AuthRegister {
 
     fn response(_ctx: &ApiContext, data: AuthRegisterResponse, auth_token: AuthTokenCreateResponse) -> Response {
         let res = Response::new_json(&data);
-        set_signin_cookie(&res, &auth_token.id);
+        set_signin_cookie(&res, &auth_token);
         res
     }
 }
@@ -113,21 +213,40 @@ impl ApiResExt for AuthCheck {
     type Res = <AuthCheck as ApiRes>::Res;
 
     async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
-        let uid = ctx.uid_unchecked();
+        let user = ctx.user.as_ref().unwrap();
         Ok(AuthCheckResponse {
-            uid
+            uid: user.account.id.clone(),
+            roles: user.roles.clone(),
         })
     }
 }
 
 
+#[async_trait(?Send)]
+impl ApiResExt for AuthOpenIdProviders {
+    type Res = <AuthOpenIdProviders as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
+        let providers = OpenIdProviderConfig::load_all(&ctx.env)?
+            .into_iter()
+            .map(|config| AuthOpenIdProviderInfo {
+                provider: config.slug,
+                name: config.name,
+                icon: config.icon,
+            })
+            .collect();
+
+        Ok(AuthOpenIdProvidersResponse { providers })
+    }
+}
+
 #[async_trait(?Send)]
 impl ApiBothExt for AuthOpenIdConnect {
     type Req = <AuthOpenIdConnect as ApiBoth>::Req;
     type Res = <AuthOpenIdConnect as ApiBoth>::Res;
 
     async fn handle(ctx: &ApiContext, data: AuthOpenIdConnectRequest) -> ApiResult<Self::Res> {
-        let url = OpenIdProcessor::new(data.provider).get_auth_url(&ctx.env).await?;
+        let url = OpenIdProcessor::new(&ctx.env, &data.provider)?.get_auth_url(&ctx.env, None, data.invite_code).await?;
         Ok(AuthOpenIdConnectResponse{url})
     }
 }
@@ -141,10 +260,10 @@ impl ApiEmptyDynRouteWithExtraExt for AuthOpenIdAccessTokenHook {
         let url = web_sys::Url::new(&ctx.req.url())?;
         let search_params = url.search_params();
 
-        let processor = OpenIdProcessor::new(self.provider);
+        let processor = OpenIdProcessor::new(&ctx.env, &self.provider)?;
         match (search_params.get("code"), search_params.get("state")) {
             (Some(code), Some(state)) => {
-                let (session, _) = processor.validate_token_claims(ctx, code, state).await?;
+                let session = processor.validate_token_claims(ctx, code, state).await?;
                 Ok(Ok(session))
             }
             _ => {
@@ -178,15 +297,26 @@ impl ApiBothExt for AuthOpenIdFinalizeQuery {
     type Res = <AuthOpenIdFinalizeQuery as ApiBoth>::Res;
 
     async fn handle(ctx: &ApiContext, data: AuthOpenIdFinalizeRequest) -> ApiResult<AuthOpenIdFinalizeQueryResponse> {
-        let AuthOpenIdFinalizeRequest{session_id, session_key} = data;
+        let AuthOpenIdFinalizeRequest{session_id, session_key, ..} = data;
         let session = OpenIdSession{id: session_id, key: session_key};
 
-        let OpenIdSessionFinalizeInfo{ email, .. } = OpenIdSessionDO::finalize_query(&ctx.env, session.clone()).await?; 
-        let user_exists = UserAccount::load_by_email(&ctx.env, &email).await.is_ok();
+        let finalize_info = OpenIdSessionDO::finalize_query(&ctx.env, session).await?;
+        let target = OpenIdFinalizeTarget::resolve(&ctx.env, &finalize_info).await?;
+
+        // so the user finds out before being asked to agree to anything
+        // linking only adds a way to sign in to an account that's already allowed
+        if !matches!(target, OpenIdFinalizeTarget::Link(_)) {
+            check_email_allowed(&ctx.env, &finalize_info.email)?;
+        }
+        if let OpenIdFinalizeTarget::Register = target {
+            check_registration(&ctx.env, &finalize_info.email, finalize_info.invite_code.as_deref()).await?;
+        }
+        let status = target.status();
 
         Ok(AuthOpenIdFinalizeQueryResponse{
-            email,
-            user_exists
+            email: finalize_info.email,
+            provider: finalize_info.provider,
+            status
         })
     }
 
@@ -199,93 +329,315 @@ impl ApiBothWithExtraExt for AuthOpenIdFinalizeExec {
     type Extra = AuthTokenCreateResponse;
 
     async fn handle(ctx: &ApiContext, data: AuthOpenIdFinalizeRequest) -> ApiResult<(AuthOpenIdFinalizeExecResponse, AuthTokenCreateResponse)> {
-        let AuthOpenIdFinalizeRequest{session_id, session_key} = data;
+        let AuthOpenIdFinalizeRequest{session_id, session_key, password} = data;
         let session = OpenIdSession{id: session_id, key: session_key};
 
-        let OpenIdSessionFinalizeInfo{ email, email_verified, .. } = OpenIdSessionDO::finalize_exec(&ctx.env, session.clone()).await?; 
+        // only query for now, the session is consumed at the end
+        // so that a failed password check on an email collision can be retried
+        let finalize_info = OpenIdSessionDO::finalize_query(&ctx.env, session.clone()).await?;
+        let OpenIdSessionFinalizeInfo{ provider, subject, email, email_verified, .. } = &finalize_info;
 
-        let mut user = match UserAccount::load_by_email(&ctx.env, &email).await.ok() {
-            // user already exists, just sign them in
-            Some(user) => {
-                worker::console_log!("user already exists, signing in");
+        // applies to signing in too, not just registering, in case the rules changed since
+        check_email_allowed(&ctx.env, email)?;
+        // a claimed domain can't sign in through some other provider either
+        check_sso_policy(&ctx.env, email, Some(provider)).await?;
+
+        let mut user = match OpenIdFinalizeTarget::resolve(&ctx.env, &finalize_info).await? {
+            OpenIdFinalizeTarget::Link(_) => {
+                return Err("link sessions must be finalized with openid-link-finalize".into());
+            },
+
+            // identity is already linked, just sign them in
+            OpenIdFinalizeTarget::Linked(user) => user,
+
+            // the provider and the account both vouch for the email
+            OpenIdFinalizeTarget::AutoLink(user) => {
+                UserIdentity::insert(&ctx.env, provider, subject, &user.id, email).await?;
+                record_auth_event(ctx, AuthEventKind::OpenIdLinked, Some(&user.id), Some(email), Some(provider.as_str())).await;
                 user
-            }, 
+            },
 
-            // user doesn't exist, register them
-            None => {
-                worker::console_log!("user does not exist, registering");
-                // theoretically we could use finalize_info.access_token to load profile info etc.
-                // but, meh, let the user just set it all fresh - makes it easier to integrate
-                // with various providers too
+            // someone else may have registered with this email, so the existing account must be proven
+            OpenIdFinalizeTarget::EmailCollision(user) => {
+                let password = password.ok_or(AuthError::OpenIdEmailCollision)?;
+
+                // same limits as a regular signin, since it's the same password
+                let rate_limiter = RateLimiter::new(ctx, RateLimitScope::Signin, &user.email);
+                let reservation = rate_limiter.reserve().await?;
+
+                if verify_password(&user, &password).is_err() {
+                    record_auth_event(ctx, AuthEventKind::SigninFailure, Some(&user.id), Some(&user.email), Some("wrong_password")).await;
+                    return Err(AuthError::InvalidSignin.into());
+                }
+                rate_limiter.succeeded(reservation).await?;
+
+                UserIdentity::insert(&ctx.env, provider, subject, &user.id, email).await?;
+                record_auth_event(ctx, AuthEventKind::OpenIdLinked, Some(&user.id), Some(email), Some(provider.as_str())).await;
+                user
+            },
+
+            // no account at all, register them
+            OpenIdFinalizeTarget::Register => {
+                let registration = check_registration(&ctx.env, email, finalize_info.invite_code.as_deref()).await?;
 
                 // create a new user account
+                // the profile (if the provider gives one) is filled in below, same as any signin
                 let uid = UserId::new(uuid::Uuid::now_v7());
                 let user_token = uuid::Uuid::now_v7().as_simple().to_string();
                 // random password
                 let password = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&rand::thread_rng().gen::<[u8; OAUTH_REGISTER_PASSWORD_LENGTH]>());
 
-                UserAccount::insert(&ctx.env, &uid, &password, &email, &user_token).await?;
-                
-                UserAccount::load_by_email(&ctx.env, &email).await?
+                UserAccount::insert(&ctx.env, &uid, &password, &random_password_salt(), email, &user_token).await?;
+                registration.consume(&ctx.env, &uid, email).await?;
+                UserIdentity::insert(&ctx.env, provider, subject, &uid, email).await?;
+                // the frontend asks the user to agree to the terms before this is called, see AuthOpenIdFinalizeStatus::Register
+                TermsAcceptance::insert(&ctx.env, &uid, TERMS_VERSION, ctx.client_ip().as_deref()).await?;
+                record_auth_event(ctx, AuthEventKind::Register, Some(&uid), Some(email), Some(provider.as_str())).await;
+
+                UserAccount::load_by_id(&ctx.env, &uid).await?
             }
         };
 
+        // a linked identity may have a different email than the account, whose domain counts too
+        if user.email != *email {
+            check_sso_policy(&ctx.env, &user.email, Some(provider)).await?;
+        }
+
+        OpenIdSessionDO::finalize_exec(&ctx.env, session).await?;
+
+        // keeps the profile in sync with the provider, unless the user has edited it
+        if let Some(profile) = &finalize_info.profile {
+            UserProfileDb::upsert_from_provider(&ctx.env, &user.id, profile).await?;
+        }
+
         // update the user's email_verified status if it's changed to true
+        // only if it's the same email, since a linked identity may report a different one
+        let email_verified = *email_verified && user.email == *email;
         if !user.email_verified && email_verified {
             UserAccount::update_email_verified(&ctx.env, &user.id, email_verified).await?;
+            record_auth_event(ctx, AuthEventKind::EmailVerified, Some(&user.id), Some(email), Some(provider.as_str())).await;
             user.email_verified = true;
         }
 
         // sign the user in and return
-        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::Signin, user.id.clone(), user.user_token.clone(), AUTH_SIGNIN_TOKEN_EXPIRES).await?;
+        let openid = AuthTokenOpenId { provider: provider.clone(), id_token: finalize_info.id_token.clone() };
+        let auth_token = AuthTokenDO::create_signin_openid(&ctx.env, user.id.clone(), user.user_token.clone(), true, openid).await?;
+        record_auth_event(ctx, AuthEventKind::SigninSuccess, Some(&user.id), Some(&user.email), Some(provider.as_str())).await;
         let auth_key = auth_token.key.clone();
         Ok((AuthOpenIdFinalizeExecResponse{
             uid: user.id,
-            email_verified: user.email_verified || email_verified,
+            email_verified: user.email_verified,
             auth_key
         }, auth_token))
     }
 
     fn response(_ctx: &ApiContext, data: AuthOpenIdFinalizeExecResponse, auth_token: AuthTokenCreateResponse) -> Response {
         let res = Response::new_json(&data);
-        set_signin_cookie(&res, &auth_token.id);
+        set_signin_cookie(&res, &auth_token);
         res
     }
 }
 
+// What finalizing an openid session will do, without doing it yet
+enum OpenIdFinalizeTarget {
+    // the session was started by a signed-in user, to link to their account
+    Link(UserId),
+    // the identity is already linked to this account
+    Linked(UserAccount),
+    // not linked, but the email matches an account and is trusted enough to link automatically
+    AutoLink(UserAccount),
+    // not linked, and the email matches an account that must be proven first
+    EmailCollision(UserAccount),
+    // nothing matches
+    Register,
+}
+
+impl OpenIdFinalizeTarget {
+    async fn resolve(env: &worker::Env, finalize_info: &OpenIdSessionFinalizeInfo) -> ApiResult<Self> {
+        if let Some(link_uid) = &finalize_info.link_uid {
+            return Ok(Self::Link(link_uid.clone()));
+        }
+
+        // identities are matched by the provider's subject, never by email
+        if let Some(identity) = UserIdentity::load(env, &finalize_info.provider, &finalize_info.subject).await? {
+            return Ok(Self::Linked(UserAccount::load_by_id(env, &identity.uid).await?));
+        }
+
+        match UserAccount::load_by_email(env, &finalize_info.email).await.ok() {
+            Some(user) => {
+                // the provider a domain is claimed for speaks for that domain's emails, whatever its config says
+                let trusted = finalize_info.email_verified
+                    && user.email_verified
+                    && (OpenIdProviderConfig::load(env, &finalize_info.provider)?.trust_email_verified
+                        || sso_provider_for_email(env, &finalize_info.email).await?.as_ref() == Some(&finalize_info.provider));
+
+                if trusted {
+                    Ok(Self::AutoLink(user))
+                } else {
+                    Ok(Self::EmailCollision(user))
+                }
+            },
+            None => Ok(Self::Register)
+        }
+    }
+
+    fn status(&self) -> AuthOpenIdFinalizeStatus {
+        match self {
+            Self::Link(_) => AuthOpenIdFinalizeStatus::Link,
+            Self::Linked(_) | Self::AutoLink(_) => AuthOpenIdFinalizeStatus::Signin,
+            Self::EmailCollision(_) => AuthOpenIdFinalizeStatus::EmailCollision,
+            Self::Register => AuthOpenIdFinalizeStatus::Register,
+        }
+    }
+}
+
 #[async_trait(?Send)]
-impl ApiEmptyExt for AuthSignout {
+impl ApiBothExt for AuthOpenIdLinkConnect {
+    type Req = <AuthOpenIdLinkConnect as ApiBoth>::Req;
+    type Res = <AuthOpenIdLinkConnect as ApiBoth>::Res;
 
-    async fn handle(ctx: &ApiContext) -> ApiResult<()> {
-        let user = ctx.user.as_ref().unwrap();
-        AuthTokenDO::destroy(&ctx.env, &user.token_id).await?;
+    async fn handle(ctx: &ApiContext, data: AuthOpenIdConnectRequest) -> ApiResult<Self::Res> {
+        let url = OpenIdProcessor::new(&ctx.env, &data.provider)?.get_auth_url(&ctx.env, Some(ctx.uid_unchecked()), None).await?;
+        Ok(AuthOpenIdConnectResponse{url})
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AuthOpenIdLinkFinalize {
+    type Req = <AuthOpenIdLinkFinalize as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AuthOpenIdFinalizeRequest) -> ApiResult<()> {
+        let AuthOpenIdFinalizeRequest{session_id, session_key, ..} = data;
+        let session = OpenIdSession{id: session_id, key: session_key};
+        let uid = ctx.uid_unchecked();
+
+        let OpenIdSessionFinalizeInfo{ provider, subject, email, link_uid, .. } = OpenIdSessionDO::finalize_query(&ctx.env, session.clone()).await?;
+
+        // the session must have been started by this same user
+        if link_uid.as_ref() != Some(&uid) {
+            return Err("openid session was not started for linking by this user".into());
+        }
+
+        match UserIdentity::load(&ctx.env, &provider, &subject).await? {
+            Some(identity) if identity.uid != uid => {
+                return Err(AuthError::OpenIdIdentityAlreadyLinked.into());
+            },
+            // already linked to this account, nothing to do
+            Some(_) => {},
+            None => {
+                UserIdentity::insert(&ctx.env, &provider, &subject, &uid, &email).await?;
+                record_auth_event(ctx, AuthEventKind::OpenIdLinked, Some(&uid), Some(&email), Some(provider.as_str())).await;
+            }
+        }
+
+        OpenIdSessionDO::finalize_exec(&ctx.env, session).await?;
 
         Ok(())
     }
-    fn response(_ctx: &ApiContext) -> Response {
-        let res = Response::new_empty();
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AuthIdentities {
+    type Res = <AuthIdentities as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
+        let identities = UserIdentity::list_by_uid(&ctx.env, &ctx.uid_unchecked()).await?;
+
+        Ok(AuthIdentitiesResponse {
+            identities: identities.into_iter().map(|x| x.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AuthIdentityUnlink {
+    type Req = <AuthIdentityUnlink as ApiReq>::Req;
+
+    // the account still has its password (random, for openid registrations), which can always be reset by email
+    // so unlinking can't lock the user out
+    async fn handle(ctx: &ApiContext, data: AuthIdentityUnlinkRequest) -> ApiResult<()> {
+        let uid = ctx.uid_unchecked();
+        UserIdentity::delete(&ctx.env, &uid, &data.provider, &data.subject).await?;
+        record_auth_event(ctx, AuthEventKind::OpenIdUnlinked, Some(&uid), None, Some(data.provider.as_str())).await;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AuthSignout {
+    type Res = <AuthSignout as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<AuthSignoutResponse> {
+        let user = ctx.user.as_ref().unwrap();
+        let openid = match user.signin_token_id() {
+            Some(token_id) => AuthTokenDO::destroy_signin(&ctx.env, token_id).await?,
+            None => None
+        };
+        record_auth_event(ctx, AuthEventKind::Signout, Some(&user.account.id), None, openid.as_ref().map(|openid| openid.provider.as_str())).await;
+
+        // we're signed out either way, the provider is a nice-to-have
+        let logout_url = match openid {
+            Some(AuthTokenOpenId { provider, id_token }) => {
+                let logout_url = match OpenIdProcessor::new(&ctx.env, &provider) {
+                    Ok(processor) => processor.logout_url(&ctx.env, id_token.as_deref()).await,
+                    Err(err) => Err(err)
+                };
+                logout_url.unwrap_or_else(|err| {
+                    worker::console_warn!("failed to get logout url for openid provider {}: {:?}", provider.as_str(), err);
+                    None
+                })
+            },
+            None => None
+        };
+
+        Ok(AuthSignoutResponse { logout_url })
+    }
+    fn response(_ctx: &ApiContext, data: AuthSignoutResponse) -> Response {
+        let res = Response::new_json(&data);
         delete_signin_cookie(&res);
         res
     }
 }
 
 #[async_trait(?Send)]
-impl ApiEmptyExt for AuthSendVerifyEmail {
+impl ApiEmptyExt for AuthImpersonationStop {
+
     async fn handle(ctx: &ApiContext) -> ApiResult<()> {
         let user = ctx.user.as_ref().unwrap();
 
-        // create a new oob token
-        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::VerifyEmail, user.account.id.clone(), user.account.user_token.clone(), AUTH_VERIFY_EMAIL_TOKEN_EXPIRES).await?;
+        match &user.credentials {
+            AuthCredentials::Impersonation { token_id, actor } => {
+                ImpersonationLog::insert(&ctx.env, actor, &user.account.id, ImpersonationEvent::Stop, None).await?;
+                AuthTokenDO::destroy(&ctx.env, token_id).await
+            },
+            _ => Err("not impersonating".into())
+        }
+    }
+}
 
-        mailer::send(ctx, &user.account.email, MailerKind::EmailVerification { 
-            oob_token_id: auth_token.id, 
-            oob_token_key: auth_token.key 
-        }).await?;
+#[async_trait(?Send)]
+impl ApiEmptyExt for AuthSendVerifyEmail {
+    async fn handle(ctx: &ApiContext) -> ApiResult<()> {
+        let user = ctx.user.as_ref().unwrap();
+        // each resend is a fresh token, and lands in the inbox
+        RateLimiter::new(ctx, RateLimitScope::VerifyEmailSend, &user.account.email).hit().await?;
 
-        Ok(())
+        send_verify_email(ctx, &user.account.id, &user.account.user_token, &user.account.email).await
     }
 }
 
+// the link and the code are both in the same token, so using either one uses up both
+async fn send_verify_email(ctx: &ApiContext, uid: &UserId, user_token: &str, email: &str) -> ApiResult<()> {
+    let code = verify_email_code();
+    let auth_token = AuthTokenDO::create_verify_email(&ctx.env, uid.clone(), user_token.to_string(), AUTH_VERIFY_EMAIL_TOKEN_EXPIRES, hash_secret(&code)).await?;
+
+    mailer::send(ctx, email, MailerKind::EmailVerification {
+        oob_token_id: auth_token.id,
+        oob_token_key: auth_token.key,
+        code,
+    }).await
+}
+
 #[async_trait(?Send)]
 impl ApiReqExt for AuthConfirmVerifyEmail {
     type Req = <AuthConfirmVerifyEmail as ApiReq>::Req;
@@ -296,6 +648,36 @@ impl ApiReqExt for AuthConfirmVerifyEmail {
 
         // now update the DB
         UserAccount::update_email_verified(&ctx.env, &account.id, true).await?;
+        record_auth_event(ctx, AuthEventKind::EmailVerified, Some(&account.id), Some(&account.email), None).await;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AuthConfirmVerifyEmailCode {
+    type Req = <AuthConfirmVerifyEmailCode as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AuthConfirmVerifyEmailCodeRequest) -> ApiResult<()> {
+        let user = ctx.user.as_ref().unwrap();
+        // people copy codes with spaces and dashes in them
+        let code:String = data.code.chars().filter(|c| c.is_ascii_digit()).collect();
+
+        // the token only allows a few wrong codes, but a resend makes a new token
+        // so wrong codes are also limited per account, across tokens
+        let rate_limiter = RateLimiter::new(ctx, RateLimitScope::VerifyEmailCode, &user.account.email);
+        let reservation = rate_limiter.reserve().await?;
+
+        // only the signed-in user's own token, so the code alone is useless
+        let id = AuthTokenDO::verify_email_id(&ctx.env, &user.account.id)?;
+        let AuthTokenValidateResponse {uid, user_token, ..} = AuthTokenDO::validate_code(&ctx.env, AuthTokenKind::VerifyEmail, &id, hash_secret(&code), AuthTokenAfterValidation::Delete).await?;
+        rate_limiter.succeeded(reservation).await?;
+        if uid != user.account.id || user_token != user.account.user_token {
+            return Err(format!("user token mismatch for user id {uid}").into())
+        }
+
+        UserAccount::update_email_verified(&ctx.env, &uid, true).await?;
+        record_auth_event(ctx, AuthEventKind::EmailVerified, Some(&uid), Some(&user.account.email), Some("code")).await;
 
         Ok(())
     }
@@ -307,6 +689,12 @@ impl ApiReqExt for AuthSendResetPasswordAny {
     type Req = <AuthSendResetPasswordAny as ApiReq>::Req;
 
     async fn handle(ctx: &ApiContext, data: AuthSendResetPasswordRequestAny) -> ApiResult<()> {
+        // this can be called by anyone, for any email, so it must not be usable to spam someone's inbox
+        check_challenge(ctx, ChallengeRoute::SendPasswordResetAny, data.challenge_token.as_deref()).await?;
+        RateLimiter::new(ctx, RateLimitScope::ResetPassword, &data.email).hit().await?;
+        // there's no password to reset for a claimed domain, its provider handles that
+        check_sso_policy(&ctx.env, &data.email, None).await?;
+
         let account = UserAccount::load_by_email(&ctx.env, &data.email).await.map_err(|_| AuthError::NoUserPasswordReset)?;
         helper_send_password_reset(ctx, &account).await
     }
@@ -330,6 +718,7 @@ pub async fn helper_send_password_reset(ctx: &ApiContext, account: &UserAccount)
         oob_token_id: auth_token.id, 
         oob_token_key: auth_token.key
     }).await?;
+    record_auth_event(ctx, AuthEventKind::PasswordResetRequested, Some(&account.id), Some(&account.email), None).await;
     Ok(())
 }
 
@@ -341,16 +730,25 @@ impl ApiBothWithExtraExt for AuthConfirmResetPassword {
     type Extra = AuthTokenCreateResponse;
 
     async fn handle(ctx: &ApiContext, data: AuthConfirmResetPasswordRequest) -> ApiResult<(AuthConfirmResetPasswordResponse, AuthTokenCreateResponse)> {
-        let AuthConfirmResetPasswordRequest{oob_token_id, oob_token_key, password} = data;
+        let AuthConfirmResetPasswordRequest{oob_token_id, oob_token_key, password, salt} = data;
+        validate_password_salt(&salt)?;
 
         let account = validate_oob_token(&ctx.env, AuthTokenKind::PasswordReset, oob_token_id, oob_token_key, AuthTokenAfterValidation::Delete).await?;
-        let password = hash_password(&password, None)?;
+        // the link may be from before the domain was claimed
+        check_sso_policy(&ctx.env, &account.email, None).await?;
+        let password = hash_password(&password, PasswordKdf::current())?;
         let user_token = uuid::Uuid::now_v7().as_simple().to_string();
 
-        UserAccount::reset_password(&ctx.env, &account.id, &password, &user_token).await?;
+        UserAccount::reset_password(&ctx.env, &account.id, &password, &salt, &user_token).await?;
+        // api tokens don't follow the UserToken, so they're revoked separately
+        ApiToken::delete_all_by_uid(&ctx.env, &account.id).await?;
+        record_auth_event(ctx, AuthEventKind::PasswordResetCompleted, Some(&account.id), Some(&account.email), None).await;
+        // the new user_token signs out everywhere else
+        record_auth_event(ctx, AuthEventKind::SessionRevoked, Some(&account.id), Some(&account.email), Some("password_reset")).await;
 
         // note that this uses the new user_token
-        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::Signin, account.id.clone(), user_token.clone(), AUTH_SIGNIN_TOKEN_EXPIRES).await?;
+        let auth_token = AuthTokenDO::create_signin(&ctx.env, account.id.clone(), user_token.clone(), true).await?;
+        record_auth_event(ctx, AuthEventKind::SigninSuccess, Some(&account.id), Some(&account.email), Some("password_reset")).await;
         let auth_key = auth_token.key.clone();
         Ok((AuthConfirmResetPasswordResponse{
             uid: account.id.clone(),
@@ -361,11 +759,94 @@ impl ApiBothWithExtraExt for AuthConfirmResetPassword {
 
     fn response(_ctx: &ApiContext, data: AuthConfirmResetPasswordResponse, auth_token: AuthTokenCreateResponse) -> Response {
         let res = Response::new_json(&data);
-        set_signin_cookie(&res, &auth_token.id);
+        set_signin_cookie(&res, &auth_token);
         res
     }
 }
 
+#[async_trait(?Send)]
+impl ApiBothExt for AuthSigninRecoveryCode {
+    type Req = <AuthSigninRecoveryCode as ApiBoth>::Req;
+    type Res = <AuthSigninRecoveryCode as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: AuthSigninRecoveryCodeRequest) -> ApiResult<AuthSigninRecoveryCodeResponse> {
+        let AuthSigninRecoveryCodeRequest { email, code } = data;
+
+        // a recovery code is as good as the password, so it's limited the same way
+        // and it only leads to setting a password, which a claimed domain doesn't have
+        check_sso_policy(&ctx.env, &email, None).await?;
+        let rate_limiter = RateLimiter::new(ctx, RateLimitScope::Signin, &email);
+        let reservation = rate_limiter.reserve().await?;
+
+        let account = match UserAccount::load_by_email(&ctx.env, &email).await.ok() {
+            Some(account) if RecoveryCode::consume(&ctx.env, &account.id, &hash_recovery_code(&ctx.env, &code)?).await? => account,
+            account => {
+                record_auth_event(ctx, AuthEventKind::SigninFailure, account.as_ref().map(|account| &account.id), Some(&email), Some("wrong_recovery_code")).await;
+                return Err(AuthError::InvalidSignin.into());
+            }
+        };
+        rate_limiter.succeeded(reservation).await?;
+
+        // whoever has the account now (if it was taken over) is signed out everywhere
+        // and the password must be reset before signing in again
+        // api tokens don't follow the UserToken, so they're revoked separately
+        let user_token = UserAccount::rotate_user_token(&ctx.env, &account.id).await?;
+        ApiToken::delete_all_by_uid(&ctx.env, &account.id).await?;
+        record_auth_event(ctx, AuthEventKind::RecoveryCodeUsed, Some(&account.id), Some(&account.email), None).await;
+        record_auth_event(ctx, AuthEventKind::SessionRevoked, Some(&account.id), Some(&account.email), Some("recovery_code")).await;
+
+        // the same token a reset email would have, just handed over directly
+        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::PasswordReset, account.id.clone(), user_token, AUTH_RESET_PASSWORD_TOKEN_EXPIRES).await?;
+        record_auth_event(ctx, AuthEventKind::PasswordResetRequested, Some(&account.id), Some(&account.email), Some("recovery_code")).await;
+
+        Ok(AuthSigninRecoveryCodeResponse {
+            oob_token_id: auth_token.id,
+            oob_token_key: auth_token.key,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for AuthTermsStatus {
+    type Res = <AuthTermsStatus as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<Self::Res> {
+        Ok(AuthTermsStatusResponse {
+            version: TERMS_VERSION,
+            accepted_version: TermsAcceptance::latest_version(&ctx.env, &ctx.uid_unchecked()).await?,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AuthTermsAccept {
+    type Req = <AuthTermsAccept as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AuthTermsAcceptRequest) -> ApiResult<()> {
+        // the page was showing older terms, it needs to reload and show these ones
+        if data.version != TERMS_VERSION {
+            return Err(format!("terms version {} isn't the current one ({TERMS_VERSION})", data.version).into());
+        }
+
+        TermsAcceptance::insert(&ctx.env, &ctx.uid_unchecked(), TERMS_VERSION, ctx.client_ip().as_deref()).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for AuthInviteCheck {
+    type Req = <AuthInviteCheck as ApiBoth>::Req;
+    type Res = <AuthInviteCheck as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: AuthInviteCheckRequest) -> ApiResult<AuthInviteCheckResponse> {
+        match Invite::load(&ctx.env, &data.code).await? {
+            Some(invite) if invite.is_available(now_ms()) => Ok(AuthInviteCheckResponse {
+                email: invite.email
+            }),
+            _ => Err(AuthError::InvalidInvite.into())
+        }
+    }
+}
+
 #[async_trait(?Send)]
 impl ApiBothExt for AuthCheckResetPassword {
     type Req = <AuthCheckResetPassword as ApiBoth>::Req;
@@ -380,4 +861,70 @@ impl ApiBothExt for AuthCheckResetPassword {
             email: account.email
         })
     }
-}
\ No newline at end of file
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for AuthSendMagicLink {
+    type Req = <AuthSendMagicLink as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: AuthSendMagicLinkRequest) -> ApiResult<()> {
+        // like password reset, anyone can call this for any email
+        RateLimiter::new(ctx, RateLimitScope::MagicLink, &data.email).hit().await?;
+        check_sso_policy(&ctx.env, &data.email, None).await?;
+
+        // unlike password reset, there's no error for an unknown email
+        // since this is offered right on the signin page, it shouldn't tell anyone who has an account
+        let account = match UserAccount::load_by_email(&ctx.env, &data.email).await {
+            Ok(account) => account,
+            Err(_) => return Ok(())
+        };
+
+        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::MagicLink, account.id.clone(), account.user_token.clone(), AUTH_MAGIC_LINK_TOKEN_EXPIRES).await?;
+
+        mailer::send(&ctx, &account.email, MailerKind::MagicLink {
+            oob_token_id: auth_token.id,
+            oob_token_key: auth_token.key
+        }).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothWithExtraExt for AuthConfirmMagicLink {
+    type Req = <AuthConfirmMagicLink as ApiBoth>::Req;
+    type Res = <AuthConfirmMagicLink as ApiBoth>::Res;
+    type Extra = AuthTokenCreateResponse;
+
+    async fn handle(ctx: &ApiContext, data: AuthConfirmMagicLinkRequest) -> ApiResult<(AuthConfirmMagicLinkResponse, AuthTokenCreateResponse)> {
+        let AuthConfirmMagicLinkRequest{oob_token_id, oob_token_key} = data;
+
+        // one-time use
+        let account = validate_oob_token(&ctx.env, AuthTokenKind::MagicLink, oob_token_id, oob_token_key, AuthTokenAfterValidation::Delete).await?;
+        // the link may be from before the domain was claimed
+        check_sso_policy(&ctx.env, &account.email, None).await?;
+
+        // getting here proves control of the inbox, same as verifying the email
+        if !account.email_verified {
+            UserAccount::update_email_verified(&ctx.env, &account.id, true).await?;
+            record_auth_event(ctx, AuthEventKind::EmailVerified, Some(&account.id), Some(&account.email), Some("magic_link")).await;
+        }
+
+        // and is just as good as a correct password, so it lifts any signin lockout too
+        RateLimiter::new(ctx, RateLimitScope::Signin, &account.email).clear_email().await?;
+
+        // sign the user in and return
+        let auth_token = AuthTokenDO::create_signin(&ctx.env, account.id.clone(), account.user_token.clone(), true).await?;
+        record_auth_event(ctx, AuthEventKind::SigninSuccess, Some(&account.id), Some(&account.email), Some("magic_link")).await;
+        let auth_key = auth_token.key.clone();
+        Ok((AuthConfirmMagicLinkResponse{
+            uid: account.id,
+            email_verified: true,
+            auth_key
+        }, auth_token))
+    }
+
+    fn response(_ctx: &ApiContext, data: AuthConfirmMagicLinkResponse, auth_token: AuthTokenCreateResponse) -> Response {
+        let res = Response::new_json(&data);
+        set_signin_cookie(&res, &auth_token);
+        res
+    }
+}
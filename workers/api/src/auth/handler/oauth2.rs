@@ -0,0 +1,139 @@
+use openidconnect::{http::{self, header, HeaderMap, HeaderValue}, url::{form_urlencoded, Url}, HttpRequest, PkceCodeChallenge};
+use serde::Deserialize;
+use shared::user::UserProfile;
+use crate::{config::HTTP_USER_AGENT, prelude::*};
+
+use super::openid::{openid_http_client, OpenIdClaimMapping};
+
+// Some providers (e.g. GitHub, Discord) only do plain OAuth2, with no id token or discovery document
+// so the endpoints are configured directly, and the email comes from their api instead of id token claims
+// the session/state handling is all the same as openid connect
+#[derive(Deserialize, Debug, Clone)]
+pub struct OAuth2Endpoints {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    // called with the access token, and the claim mapping is applied to its JSON response
+    pub userinfo_endpoint: String,
+    // optional, for providers that only list emails separately (e.g. GitHub)
+    // must return an array of {"email", "primary", "verified"}
+    #[serde(default)]
+    pub emails_endpoint: Option<String>,
+}
+
+pub struct OAuth2User {
+    pub subject: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub access_token: String,
+    pub profile: Option<UserProfile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OAuth2TokenResponse {
+    access_token: Option<String>,
+    // some providers (e.g. GitHub) return errors with a 200 status
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OAuth2Email {
+    email: String,
+    #[serde(default)]
+    primary: bool,
+    #[serde(default)]
+    verified: bool,
+}
+
+pub fn auth_url(endpoints: &OAuth2Endpoints, scopes: &[String], client_id: &str, redirect_uri: &str, state: &str, pkce_challenge: &PkceCodeChallenge) -> ApiResult<String> {
+    let mut url = Url::parse(&endpoints.authorization_endpoint).map_err(|err| err.to_string())?;
+
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("scope", &scopes.join(" "))
+        .append_pair("state", state)
+        // providers that don't support pkce ignore these
+        .append_pair("code_challenge", pkce_challenge.as_str())
+        .append_pair("code_challenge_method", pkce_challenge.method().as_str());
+
+    Ok(url.to_string())
+}
+
+pub async fn exchange_code(endpoints: &OAuth2Endpoints, claims: &OpenIdClaimMapping, client_id: &str, client_secret: &str, redirect_uri: &str, code: &str, pkce_verifier: &str) -> ApiResult<OAuth2User> {
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("grant_type", "authorization_code")
+        .append_pair("code", code)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("client_id", client_id)
+        .append_pair("client_secret", client_secret)
+        .append_pair("code_verifier", pkce_verifier)
+        .finish();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"));
+    // github responds with a form-encoded body otherwise
+    headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+    headers.insert(header::USER_AGENT, HeaderValue::from_static(HTTP_USER_AGENT));
+
+    let res = openid_http_client(HttpRequest {
+        url: Url::parse(&endpoints.token_endpoint).map_err(|err| err.to_string())?,
+        method: http::Method::POST,
+        headers,
+        body: body.into_bytes(),
+    }).await?;
+
+    if !res.status_code.is_success() {
+        return Err(format!("oauth2 token exchange failed, status code: {}", res.status_code).into());
+    }
+
+    let token:OAuth2TokenResponse = serde_json::from_slice(&res.body).map_err(|err| err.to_string())?;
+    let access_token = match (token.access_token, token.error) {
+        (Some(access_token), _) => access_token,
+        (None, error) => return Err(format!("oauth2 token exchange failed, error: {}", error.unwrap_or_default()).into())
+    };
+
+    let userinfo:serde_json::Map<String, serde_json::Value> = get_json(&endpoints.userinfo_endpoint, &access_token).await?;
+    let subject = claims.subject(&userinfo).ok_or("no subject from oauth2 provider")?;
+    let mut email = claims.email(&userinfo);
+    let mut email_verified = claims.email_verified(&userinfo);
+
+    if let Some(emails_endpoint) = &endpoints.emails_endpoint {
+        let emails:Vec<OAuth2Email> = get_json(emails_endpoint, &access_token).await?;
+        // prefer the primary email, but any verified one is better than an unverified one
+        let verified = emails.iter().find(|x| x.primary && x.verified).or_else(|| emails.iter().find(|x| x.verified));
+
+        if let Some(verified) = verified {
+            email = Some(verified.email.clone());
+            email_verified = true;
+        }
+    }
+
+    Ok(OAuth2User {
+        subject,
+        email: email.ok_or("no email from oauth2 provider")?,
+        email_verified,
+        access_token,
+        profile: claims.profile(&userinfo),
+    })
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: &str, access_token: &str) -> ApiResult<T> {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {access_token}")).map_err(|err| err.to_string())?);
+    headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+    headers.insert(header::USER_AGENT, HeaderValue::from_static(HTTP_USER_AGENT));
+
+    let res = openid_http_client(HttpRequest {
+        url: Url::parse(url).map_err(|err| err.to_string())?,
+        method: http::Method::GET,
+        headers,
+        body: Vec::new(),
+    }).await?;
+
+    if !res.status_code.is_success() {
+        return Err(format!("oauth2 request to {} failed, status code: {}", url, res.status_code).into());
+    }
+
+    serde_json::from_slice(&res.body).map_err(|err| err.to_string().into())
+}
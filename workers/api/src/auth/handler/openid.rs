@@ -1,27 +1,265 @@
 use std::{borrow::Cow, str::FromStr, time::Duration};
 
 use openidconnect::{
-    core::{CoreAuthPrompt, CoreClient, CoreIdTokenClaims, CoreIdTokenVerifier, CoreProviderMetadata, CoreResponseType}, http::{self, HeaderMap, HeaderName, HeaderValue}, AuthenticationFlow, AuthorizationCode, ClientId, ClientSecret, IssuerUrl, Nonce, OAuth2TokenResponse, RedirectUrl, Scope, TokenUrl
+    core::{CoreAuthPrompt, CoreClient, CoreIdToken, CoreIdTokenVerifier, CoreProviderMetadata, CoreResponseType}, http::{self, HeaderMap, HeaderName, HeaderValue}, AuthUrl, AuthenticationFlow, AuthorizationCode, ClientId, ClientSecret, EndSessionUrl, IssuerUrl, LogoutRequest, Nonce, OAuth2TokenResponse, PkceCodeChallenge, PkceCodeVerifier, PostLogoutRedirectUrl, ProviderMetadataWithLogout, RedirectUrl, Scope, TokenUrl
 };
-use shared::backend::route::{AuthRoute, OpenIdProvider, Route};
+use base64::Engine;
+use serde::Deserialize;
+use shared::{backend::route::{AuthRoute, OpenIdProvider, Route}, frontend::route::{Landing as FrontendLanding, Route as FrontendRoute}, user::{UserId, UserProfile}};
 use worker::{js_sys::{self, try_iter}, wasm_bindgen_futures::JsFuture};
 use web_sys::WorkerGlobalScope;
-use crate::{auth::durable_objects::openid::OpenIdSessionNonce, config::{API_DOMAIN, API_ROOT_PATH}, prelude::*};
+use crate::{auth::durable_objects::openid::{OpenIdSessionClaims, OpenIdSessionNonce}, config::{API_DOMAIN, API_ROOT_PATH, FRONTEND_DOMAIN, FRONTEND_ROOT_PATH}, prelude::*};
+
+use super::{super::durable_objects::openid::{OpenIdSession, OpenIdSessionDO}, oauth2::{self, OAuth2Endpoints}};
+
+// Describes an OpenID Connect (or plain OAuth2) provider, so new ones can be added without code changes
+// the list is in openid_providers.json, the same for every environment
+// an environment can still replace it with the OPENID_PROVIDERS var (the same JSON array), e.g. "[]" to turn openid off
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpenIdProviderConfig {
+    // used in urls, e.g. "google"
+    pub slug: OpenIdProvider,
+    // shown to users, e.g. "Google"
+    pub name: String,
+    // absolute url, or relative to the frontend media root
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(flatten)]
+    pub kind: OpenIdProviderKind,
+    // these are the *names* of the worker secrets, not the values
+    pub client_id_secret: String,
+    pub client_secret_secret: String,
+    // "openid" is always requested
+    #[serde(default = "OpenIdProviderConfig::default_scopes")]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub claims: OpenIdClaimMapping,
+    // whether to fill in the user's profile (name, locale, picture) from the provider's claims
+    // for openid connect, this also requests the "profile" scope
+    #[serde(default)]
+    pub profile: bool,
+    // forces the user to re-authenticate with the provider every time (openid connect only)
+    #[serde(default = "OpenIdProviderConfig::default_force_login")]
+    pub force_login: bool,
+    // whether a verified email from this provider is proof enough to link to an existing account with the same verified email
+    // only set this for providers that own the email domain, or are otherwise known to verify emails properly (e.g. google)
+    // otherwise, the user must sign in to the existing account first (see AuthOpenIdFinalizeStatus::EmailCollision)
+    #[serde(default)]
+    pub trust_email_verified: bool,
+    // on signout, also sign the user out of the provider, via its end_session_endpoint (openid connect only)
+    // the provider must allow the frontend's welcome page as a post-logout redirect
+    #[serde(default)]
+    pub logout_from_provider: bool,
+}
 
-use super::super::durable_objects::openid::{OpenIdSession, OpenIdSessionDO};
+impl OpenIdProviderConfig {
+    const ENV_VAR: &'static str = "OPENID_PROVIDERS";
+    const DEFAULT: &'static str = include_str!("../openid_providers.json");
 
-pub struct OpenIdProcessor {
-    pub provider: OpenIdProvider
+    pub fn load_all(env: &worker::Env) -> ApiResult<Vec<Self>> {
+        match env.var(Self::ENV_VAR) {
+            Ok(var) => serde_json::from_str(&var.to_string()).map_err(|err| format!("invalid {}: {}", Self::ENV_VAR, err).into()),
+            Err(_) => serde_json::from_str(Self::DEFAULT).map_err(|err| format!("invalid openid_providers.json: {}", err).into())
+        }
+    }
+
+    pub fn load(env: &worker::Env, provider: &OpenIdProvider) -> ApiResult<Self> {
+        Self::load_all(env)?
+            .into_iter()
+            .find(|config| config.slug == *provider)
+            .ok_or_else(|| format!("unknown openid provider: {}", provider.as_str()).into())
+    }
+
+    fn default_scopes() -> Vec<String> {
+        vec!["email".to_string()]
+    }
+
+    fn default_force_login() -> bool {
+        true
+    }
 }
-impl OpenIdProcessor {
-    pub fn new(provider: OpenIdProvider) -> Self {
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum OpenIdProviderKind {
+    #[serde(rename = "openid_connect")]
+    OpenIdConnect {
+        // where the discovery document is, e.g. "https://accounts.google.com"
+        issuer: String,
+        #[serde(default)]
+        metadata: OpenIdMetadataOverrides,
+    },
+    // no id token or discovery, see oauth2.rs
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuth2Endpoints),
+}
+
+// Which claims in the id token (or the oauth2 userinfo response) hold the info we need
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpenIdClaimMapping {
+    // the provider's stable id for the user, which identities are linked by
+    #[serde(default = "OpenIdClaimMapping::default_subject")]
+    pub subject: String,
+    #[serde(default = "OpenIdClaimMapping::default_email")]
+    pub email: String,
+    // set to null for providers that don't support it (e.g. facebook), in which case it's always false
+    #[serde(default = "OpenIdClaimMapping::default_email_verified")]
+    pub email_verified: Option<String>,
+    // the rest are only read for providers configured with `profile`
+    #[serde(default = "OpenIdClaimMapping::default_name")]
+    pub name: String,
+    #[serde(default = "OpenIdClaimMapping::default_given_name")]
+    pub given_name: String,
+    #[serde(default = "OpenIdClaimMapping::default_family_name")]
+    pub family_name: String,
+    #[serde(default = "OpenIdClaimMapping::default_locale")]
+    pub locale: String,
+    #[serde(default = "OpenIdClaimMapping::default_picture")]
+    pub picture: String,
+}
+
+impl Default for OpenIdClaimMapping {
+    fn default() -> Self {
         Self {
-            provider
+            subject: Self::default_subject(),
+            email: Self::default_email(),
+            email_verified: Self::default_email_verified(),
+            name: Self::default_name(),
+            given_name: Self::default_given_name(),
+            family_name: Self::default_family_name(),
+            locale: Self::default_locale(),
+            picture: Self::default_picture(),
         }
     }
+}
+
+impl OpenIdClaimMapping {
+    pub fn subject(&self, claims: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+        // some providers (e.g. github) use numeric ids
+        match claims.get(&self.subject)? {
+            serde_json::Value::String(x) => Some(x.clone()),
+            serde_json::Value::Number(x) => Some(x.to_string()),
+            _ => None
+        }
+    }
+
+    pub fn email(&self, claims: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+        claims.get(&self.email).and_then(|x| x.as_str()).map(|x| x.to_string())
+    }
+
+    pub fn email_verified(&self, claims: &serde_json::Map<String, serde_json::Value>) -> bool {
+        self.email_verified
+            .as_ref()
+            .and_then(|claim| claims.get(claim))
+            // some providers send it as a string
+            .and_then(|x| x.as_bool().or_else(|| x.as_str().map(|x| x == "true")))
+            .unwrap_or(false)
+    }
+
+    // None if the provider sent none of the profile claims
+    pub fn profile(&self, claims: &serde_json::Map<String, serde_json::Value>) -> Option<UserProfile> {
+        let get = |claim: &str| {
+            claims.get(claim)
+                .and_then(|x| x.as_str())
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+        };
+
+        let profile = UserProfile {
+            name: get(&self.name),
+            given_name: get(&self.given_name),
+            family_name: get(&self.family_name),
+            locale: get(&self.locale),
+            // only ever shown as an image, but still shouldn't be some other scheme
+            picture_url: get(&self.picture).filter(|x| x.starts_with("https://")),
+        };
+
+        (!profile.is_empty()).then_some(profile)
+    }
+
+    fn default_subject() -> String {
+        "sub".to_string()
+    }
+
+    fn default_email() -> String {
+        "email".to_string()
+    }
+
+    fn default_email_verified() -> Option<String> {
+        Some("email_verified".to_string())
+    }
+
+    fn default_name() -> String {
+        "name".to_string()
+    }
+
+    fn default_given_name() -> String {
+        "given_name".to_string()
+    }
+
+    fn default_family_name() -> String {
+        "family_name".to_string()
+    }
+
+    fn default_locale() -> String {
+        "locale".to_string()
+    }
+
+    fn default_picture() -> String {
+        "picture".to_string()
+    }
+}
+
+// For providers whose discovery document is incomplete
+// see: https://github.com/ramosbugs/openidconnect-rs/issues/155#issuecomment-2044618322
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct OpenIdMetadataOverrides {
+    #[serde(default)]
+    pub authorization_endpoint: Option<String>,
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    #[serde(default)]
+    pub end_session_endpoint: Option<String>,
+}
+
+pub struct OpenIdProcessor {
+    pub config: OpenIdProviderConfig
+}
+impl OpenIdProcessor {
+    pub fn new(env: &worker::Env, provider: &OpenIdProvider) -> ApiResult<Self> {
+        Ok(Self {
+            config: OpenIdProviderConfig::load(env, provider)?
+        })
+    }
+
+    // link_uid is set when a signed-in user is linking this provider to their account
+    // invite_code is kept for registering, if that's what finalizing the session ends up doing
+    pub async fn get_auth_url(&self, env: &worker::Env, link_uid: Option<UserId>, invite_code: Option<String>) -> ApiResult<String> {
+        // have to create the session _before_ we can set the nonce (since we need the csrf_token first, which is synonymous with session (and state))
+        let session = OpenIdSessionDO::create(&env, self.config.slug.clone(), link_uid, invite_code).await?;
+        let object_id = session.id.clone();
+
+        // pkce for every provider, some require it even though we're a confidential client
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let (authorize_url, nonce) = match &self.config.kind {
+            OpenIdProviderKind::OpenIdConnect { .. } => self.get_auth_url_oidc(env, session, pkce_challenge).await?,
+            OpenIdProviderKind::OAuth2(endpoints) => {
+                let authorize_url = oauth2::auth_url(endpoints, &self.config.scopes, self.client_id(env)?.as_str(), &self.redirect_uri(), session.to_csrf_token().secret(), &pkce_challenge)?;
+                // there's no id token to check this against, but the session flow expects one all the same
+                (authorize_url, Nonce::new_random())
+            }
+        };
+
+        // store the nonce and pkce verifier in the session durable object for later validation
+        OpenIdSessionDO::set_nonce(&env, &object_id, nonce.secret().to_string(), pkce_verifier.secret().to_string()).await?;
+
+        Ok(authorize_url)
+    }
 
     // example: https://github.com/ramosbugs/openidconnect-rs/blob/main/examples/google.rs
-    pub async fn get_auth_url(&self, env: &worker::Env) -> ApiResult<String> {
+    async fn get_auth_url_oidc(&self, env: &worker::Env, session: OpenIdSession, pkce_challenge: PkceCodeChallenge) -> ApiResult<(String, Nonce)> {
         let client_id = self.client_id(env)?;
         let client_secret = self.client_secret(env)?;
         let provider_metadata = self.provider_metadata().await?;
@@ -30,50 +268,88 @@ impl OpenIdProcessor {
             provider_metadata,
             client_id,
             Some(client_secret),
-        ).set_redirect_uri(RedirectUrl::new(Route::Auth(AuthRoute::OpenIdAccessTokenHook(self.provider)).link(API_DOMAIN, API_ROOT_PATH)).map_err(|err| err.to_string())?)
+        ).set_redirect_uri(RedirectUrl::new(self.redirect_uri()).map_err(|err| err.to_string())?)
         .set_auth_type(openidconnect::AuthType::RequestBody);
 
-        // have to create the session _before_ we can set the nonce (since we need the csrf_token first, which is synonymous with session (and state))
-        let session = OpenIdSessionDO::create(&env, self.provider).await?;
-        let object_id = session.id.clone();
-
         // Generate the authorization URL to which we'll redirect the user.
         // this will come back to the server and pick up the state from session durable object
-        let (authorize_url, _, nonce) = client
+        let mut request = client
         .authorize_url(
             AuthenticationFlow::<CoreResponseType>::AuthorizationCode,
             move || session.to_csrf_token(),
             Nonce::new_random,
         )
-        .add_scope(Scope::new("email".to_string()))
-        // setting these two will force re-authentication every time
-        .add_prompt(CoreAuthPrompt::Login)
-        .set_max_age(Duration::ZERO)
-        // this doesn't work in a test scenario, maybe requires publishing / sensitive verification?
-        //.add_scope(Scope::new("profile".to_string()))
-        .url();
-
-        // store the nonce in the session durable object for later validation
-        OpenIdSessionDO::set_nonce(&env, &object_id, nonce.secret().to_string()).await?;
-
-        Ok(authorize_url.to_string())
+        .set_pkce_challenge(pkce_challenge);
+
+        for scope in self.config.scopes.iter() {
+            request = request.add_scope(Scope::new(scope.clone()));
+        }
+
+        if self.config.profile && !self.config.scopes.iter().any(|scope| scope == "profile") {
+            request = request.add_scope(Scope::new("profile".to_string()));
+        }
+
+        if self.config.force_login {
+            // setting these two will force re-authentication every time
+            request = request
+                .add_prompt(CoreAuthPrompt::Login)
+                .set_max_age(Duration::ZERO);
+        }
+
+        let (authorize_url, _, nonce) = request.url();
+
+        Ok((authorize_url.to_string(), nonce))
     }
 
-    // this came via the redirect from the openid provider
-    pub async fn validate_token_claims(&self, ctx: &ApiContext, code: String, state: String) -> ApiResult<(OpenIdSession, CoreIdTokenClaims) > {
-        let code = AuthorizationCode::new(code);
+    // this came via the redirect from the provider
+    pub async fn validate_token_claims(&self, ctx: &ApiContext, code: String, state: String) -> ApiResult<OpenIdSession> {
         let session = OpenIdSession::try_from_str(&state).map_err(|err| err.to_string())?;
 
         // pick up the state from the "state" parameter
         // validation gets us the originally set provider and nonce
         // the nonce will be used to validate the claims below
-        let OpenIdSessionNonce {provider, nonce} = OpenIdSessionDO::get_nonce(&ctx.env, session.clone()).await?;
+        let OpenIdSessionNonce {provider, nonce, pkce_verifier} = OpenIdSessionDO::get_nonce(&ctx.env, session.clone()).await?;
 
         // very unlikely to happen, but, simple sanity check that can help debugging
-        if provider != self.provider {
+        if provider != self.config.slug {
             return Err("mismatched provider".into());
         }
 
+        let mut claims = match &self.config.kind {
+            OpenIdProviderKind::OpenIdConnect { .. } => self.validate_token_claims_oidc(ctx, code, nonce, pkce_verifier).await?,
+            OpenIdProviderKind::OAuth2(endpoints) => {
+                let user = oauth2::exchange_code(
+                    endpoints,
+                    &self.config.claims,
+                    self.client_id(&ctx.env)?.as_str(),
+                    self.client_secret(&ctx.env)?.secret(),
+                    &self.redirect_uri(),
+                    &code,
+                    &pkce_verifier
+                ).await?;
+
+                OpenIdSessionClaims {
+                    access_token: user.access_token,
+                    subject: user.subject,
+                    email: user.email,
+                    email_verified: user.email_verified,
+                    id_token: None,
+                    profile: user.profile,
+                }
+            }
+        };
+
+        if !self.config.profile {
+            claims.profile = None;
+        }
+
+        // all is good, now we can set the access token so this session can be finalized
+        let _ = OpenIdSessionDO::set_access_token(&ctx.env, &session.id, claims).await?;
+        Ok(session)
+    }
+
+    async fn validate_token_claims_oidc(&self, ctx: &ApiContext, code: String, nonce: Nonce, pkce_verifier: String) -> ApiResult<OpenIdSessionClaims> {
+        let code = AuthorizationCode::new(code);
         let client_id = self.client_id(&ctx.env)?;
         let client_secret = self.client_secret(&ctx.env)?;
         let provider_metadata = self.provider_metadata().await?;
@@ -86,71 +362,116 @@ impl OpenIdProcessor {
         ).set_auth_type(openidconnect::AuthType::RequestBody);
 
 
-        let redirect_uri = Route::Auth(AuthRoute::OpenIdAccessTokenHook(self.provider)).link(API_DOMAIN, API_ROOT_PATH);
-
         let token_response = client
             .exchange_code(code)
-            .set_redirect_uri(Cow::Owned(RedirectUrl::new(redirect_uri).map_err(|err| err.to_string())?))
+            .set_redirect_uri(Cow::Owned(RedirectUrl::new(self.redirect_uri()).map_err(|err| err.to_string())?))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier))
             .request_async(openid_http_client).await.map_err(|err| err.to_string())?;
 
         
         let id_token_verifier: CoreIdTokenVerifier = client.id_token_verifier();
-        let claims: CoreIdTokenClaims = token_response
+        let id_token = token_response
             .extra_fields()
             .id_token()
-            .expect("Server did not return an ID token")
+            .ok_or("Server did not return an ID token")?;
+
+        id_token
             .claims(&id_token_verifier, &nonce)
-            .map_err(|err| err.to_string())?
-            .clone();
+            .map_err(|err| err.to_string())?;
 
-        let email = claims.email().ok_or("no email in claims")?.to_string();
+        // the claims are verified at this point, so the raw payload can be trusted too
+        // it's used to look up claims by their configured names, which may be non-standard
+        let payload = id_token_payload(&id_token.to_string())?;
+
+        let subject = self.config.claims.subject(&payload).ok_or("no subject in claims")?;
+        let email = self.config.claims.email(&payload).ok_or("no email in claims")?;
         // google supports the email_verified claim and we should use that
         // facebook doesn't support email_verified, and we default to false
         // see here for facebook's recommendation to verify ourselves: https://developers.facebook.com/docs/facebook-login/guides/advanced/existing-system#postfb1
-        let email_verified = claims.email_verified().unwrap_or(false);
+        let email_verified = self.config.claims.email_verified(&payload);
         let access_token = token_response.access_token().secret().to_string();
 
-        // all is good, now we can set the access token so this session can be finalized
-        let _ = OpenIdSessionDO::set_access_token(&ctx.env, &session.id, access_token, email, email_verified).await?;
-        Ok((session, claims))
+        Ok(OpenIdSessionClaims {
+            access_token,
+            subject,
+            email,
+            email_verified,
+            id_token: Some(id_token.to_string()),
+            profile: self.config.claims.profile(&payload),
+        })
     }
 
-    fn client_id(&self, env: &worker::Env) -> ApiResult<ClientId> {
-        match self.provider {
-            OpenIdProvider::Google => Ok(ClientId::new(get_secret(env, "OAUTH_GOOGLE_CLIENT_ID")?)),
-            OpenIdProvider::Facebook => Ok(ClientId::new(get_secret(env, "OAUTH_FACEBOOK_CLIENT_ID")?))
+    // where to send the browser on signout, to sign out of the provider too
+    // None if the provider isn't configured for it, or doesn't support it
+    pub async fn logout_url(&self, env: &worker::Env, id_token: Option<&str>) -> ApiResult<Option<String>> {
+        if !self.config.logout_from_provider {
+            return Ok(None);
+        }
+
+        let (issuer, metadata) = match &self.config.kind {
+            OpenIdProviderKind::OpenIdConnect { issuer, metadata } => (issuer, metadata),
+            OpenIdProviderKind::OAuth2(_) => return Ok(None)
+        };
+
+        let end_session_endpoint = match &metadata.end_session_endpoint {
+            Some(end_session_endpoint) => EndSessionUrl::new(end_session_endpoint.clone()).map_err(|err| err.to_string())?,
+            None => {
+                let issuer_url = IssuerUrl::new(issuer.clone()).map_err(|err| err.to_string())?;
+                let provider_metadata = ProviderMetadataWithLogout::discover_async(issuer_url, openid_http_client).await.map_err(|err| ApiError::from(err.to_string()))?;
+                match provider_metadata.additional_metadata().end_session_endpoint.clone() {
+                    Some(end_session_endpoint) => end_session_endpoint,
+                    None => {
+                        worker::console_warn!("openid provider {} has no end_session_endpoint, not signing out of it", self.config.slug.as_str());
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        let post_logout_redirect_uri = FrontendRoute::Landing(FrontendLanding::Welcome).link(FRONTEND_DOMAIN, FRONTEND_ROOT_PATH);
+
+        let mut request = LogoutRequest::from(end_session_endpoint)
+            .set_client_id(self.client_id(env)?)
+            .set_post_logout_redirect_uri(PostLogoutRedirectUrl::new(post_logout_redirect_uri).map_err(|err| err.to_string())?);
 
+        if let Some(id_token) = id_token.and_then(|id_token| CoreIdToken::from_str(id_token).ok()) {
+            request = request.set_id_token_hint(&id_token);
         }
+
+        Ok(Some(request.http_get_url().to_string()))
     }
 
-    fn client_secret(&self, env: &worker::Env) -> ApiResult<ClientSecret> {
-        match self.provider {
-            OpenIdProvider::Google => Ok(ClientSecret::new(get_secret(env, "OAUTH_GOOGLE_CLIENT_SECRET")?)),
-            OpenIdProvider::Facebook => Ok(ClientSecret::new(get_secret(env, "OAUTH_FACEBOOK_CLIENT_SECRET")?))
-        }
+    fn redirect_uri(&self) -> String {
+        Route::Auth(AuthRoute::OpenIdAccessTokenHook(self.config.slug.clone())).link(API_DOMAIN, API_ROOT_PATH)
     }
 
-    fn issuer_url(&self) -> ApiResult<IssuerUrl> {
-        match self.provider {
-            OpenIdProvider::Google => IssuerUrl::new("https://accounts.google.com".to_string()).map_err(|err| err.to_string().into()),
-            OpenIdProvider::Facebook => IssuerUrl::new("https://www.facebook.com".to_string()).map_err(|err| err.to_string().into())
-        }
+    fn client_id(&self, env: &worker::Env) -> ApiResult<ClientId> {
+        Ok(ClientId::new(get_secret(env, &self.config.client_id_secret)?))
+    }
+
+    fn client_secret(&self, env: &worker::Env) -> ApiResult<ClientSecret> {
+        Ok(ClientSecret::new(get_secret(env, &self.config.client_secret_secret)?))
     }
 
-    // see: https://github.com/ramosbugs/openidconnect-rs/issues/155#issuecomment-2044618322
     async fn provider_metadata(&self) -> ApiResult<CoreProviderMetadata> {
-        let issuer_url = self.issuer_url()?;
+        let (issuer, metadata) = match &self.config.kind {
+            OpenIdProviderKind::OpenIdConnect { issuer, metadata } => (issuer, metadata),
+            OpenIdProviderKind::OAuth2(_) => return Err("oauth2 providers have no openid metadata".into())
+        };
+
+        let issuer_url = IssuerUrl::new(issuer.clone()).map_err(|err| err.to_string())?;
         let mut provider_metadata = CoreProviderMetadata::discover_async(issuer_url, openid_http_client).await.map_err(|err| ApiError::from(err.to_string()))?;
 
+        if let Some(authorization_endpoint) = &metadata.authorization_endpoint {
+            provider_metadata = provider_metadata.set_authorization_endpoint(AuthUrl::new(authorization_endpoint.clone()).map_err(|err| err.to_string())?);
+        }
+
+        if let Some(token_endpoint) = &metadata.token_endpoint {
+            provider_metadata = provider_metadata.set_token_endpoint(Some(TokenUrl::new(token_endpoint.clone()).map_err(|err| err.to_string())?));
+        }
+
         if provider_metadata.token_endpoint().is_none() {
-            match self.provider {
-                OpenIdProvider::Facebook => {
-                    provider_metadata = provider_metadata.set_token_endpoint(Some(TokenUrl::new("https://graph.facebook.com/oauth/access_token".to_string()).map_err(|err| err.to_string())?));
-                },
-                OpenIdProvider::Google => {
-                    provider_metadata = provider_metadata.set_token_endpoint(Some(TokenUrl::new("https://oauth2.googleapis.com/token".to_string()).map_err(|err| err.to_string())?));
-                },
-            }
+            return Err(format!("no token endpoint for openid provider {}, set one in the metadata overrides", self.config.slug.as_str()).into());
         }
 
         if provider_metadata.token_endpoint_auth_methods_supported().is_none() {
@@ -161,7 +482,14 @@ impl OpenIdProcessor {
     }
 }
 
-async fn openid_http_client(req: openidconnect::HttpRequest) -> ApiResult<openidconnect::HttpResponse> {
+// decodes the (middle) payload part of a JWT, without any verification
+fn id_token_payload(jwt: &str) -> ApiResult<serde_json::Map<String, serde_json::Value>> {
+    let payload = jwt.split('.').nth(1).ok_or("invalid id token")?;
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).map_err(|err| err.to_string())?;
+    serde_json::from_slice(&payload).map_err(|err| err.to_string().into())
+}
+
+pub(super) async fn openid_http_client(req: openidconnect::HttpRequest) -> ApiResult<openidconnect::HttpResponse> {
     fn map_request(req: openidconnect::HttpRequest) -> ApiResult<web_sys::Request> {
         let mut init:web_sys::RequestInit = web_sys::RequestInit::new();
 
@@ -0,0 +1,128 @@
+use crate::{
+    auth::durable_objects::rate_limit::{RateLimitDO, RateLimitPolicy, RateLimitReserveResponse},
+    config::{AUTH_RATE_LIMIT_MAGIC_LINK_EMAIL, AUTH_RATE_LIMIT_MAGIC_LINK_IP, AUTH_RATE_LIMIT_REGISTER_EMAIL, AUTH_RATE_LIMIT_REGISTER_IP, AUTH_RATE_LIMIT_RESET_PASSWORD_EMAIL, AUTH_RATE_LIMIT_RESET_PASSWORD_IP, AUTH_RATE_LIMIT_SIGNIN_EMAIL, AUTH_RATE_LIMIT_SIGNIN_IP, AUTH_RATE_LIMIT_VERIFY_EMAIL_CODE_EMAIL, AUTH_RATE_LIMIT_VERIFY_EMAIL_CODE_IP, AUTH_RATE_LIMIT_VERIFY_EMAIL_SEND_EMAIL, AUTH_RATE_LIMIT_VERIFY_EMAIL_SEND_IP},
+    prelude::*
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum RateLimitScope {
+    Signin,
+    ResetPassword,
+    Register,
+    MagicLink,
+    VerifyEmailSend,
+    VerifyEmailCode,
+}
+
+impl RateLimitScope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Signin => "signin",
+            Self::ResetPassword => "reset-password",
+            Self::Register => "register",
+            Self::MagicLink => "magic-link",
+            Self::VerifyEmailSend => "verify-email-send",
+            Self::VerifyEmailCode => "verify-email-code",
+        }
+    }
+
+    fn email_policy(&self) -> RateLimitPolicy {
+        match self {
+            Self::Signin => AUTH_RATE_LIMIT_SIGNIN_EMAIL,
+            Self::ResetPassword => AUTH_RATE_LIMIT_RESET_PASSWORD_EMAIL,
+            Self::Register => AUTH_RATE_LIMIT_REGISTER_EMAIL,
+            Self::MagicLink => AUTH_RATE_LIMIT_MAGIC_LINK_EMAIL,
+            Self::VerifyEmailSend => AUTH_RATE_LIMIT_VERIFY_EMAIL_SEND_EMAIL,
+            Self::VerifyEmailCode => AUTH_RATE_LIMIT_VERIFY_EMAIL_CODE_EMAIL,
+        }
+    }
+
+    fn ip_policy(&self) -> RateLimitPolicy {
+        match self {
+            Self::Signin => AUTH_RATE_LIMIT_SIGNIN_IP,
+            Self::ResetPassword => AUTH_RATE_LIMIT_RESET_PASSWORD_IP,
+            Self::Register => AUTH_RATE_LIMIT_REGISTER_IP,
+            Self::MagicLink => AUTH_RATE_LIMIT_MAGIC_LINK_IP,
+            Self::VerifyEmailSend => AUTH_RATE_LIMIT_VERIFY_EMAIL_SEND_IP,
+            Self::VerifyEmailCode => AUTH_RATE_LIMIT_VERIFY_EMAIL_CODE_IP,
+        }
+    }
+}
+
+// Limits attempts for a given scope, both by the email being targeted and by the client ip
+// limiting by email protects a single account (or inbox) from a distributed attack
+// limiting by ip protects many accounts from a single client
+pub struct RateLimiter<'a> {
+    ctx: &'a ApiContext,
+    scope: RateLimitScope,
+    email_key: String,
+    ip_key: Option<String>,
+}
+
+impl <'a> RateLimiter<'a> {
+    pub fn new(ctx: &'a ApiContext, scope: RateLimitScope, email: &str) -> Self {
+        Self {
+            ctx,
+            scope,
+            email_key: format!("{}:email:{}", scope.as_str(), email.trim().to_lowercase()),
+            // the ip header is always set in production, but just in case, fall back to email-only limiting
+            ip_key: ctx.client_ip().map(|ip| format!("{}:ip:{}", scope.as_str(), ip)),
+        }
+    }
+
+    // errors with TooManyAttempts if either key must wait, otherwise records the attempt against both
+    // it's counted as a failure until succeeded() says otherwise, so it must be called before the attempt is made
+    pub async fn reserve(&self) -> ApiResult<RateLimitReservation> {
+        let ip_attempt_id = match &self.ip_key {
+            Some(ip_key) => Some(into_result(RateLimitDO::reserve(&self.ctx.env, ip_key, self.scope.ip_policy()).await?)?.0),
+            None => None
+        };
+
+        match into_result(RateLimitDO::reserve(&self.ctx.env, &self.email_key, self.scope.email_policy()).await?) {
+            Ok((_, locked)) => Ok(RateLimitReservation { ip_attempt_id, locked }),
+            Err(err) => {
+                // the email rejected it, so it wasn't an attempt at all
+                self.release_ip(ip_attempt_id).await?;
+                Err(err)
+            }
+        }
+    }
+
+    // a success wipes the slate clean for the email, but only takes back this attempt for the ip
+    // otherwise an attacker could interleave attempts against their own account
+    pub async fn succeeded(&self, reservation: RateLimitReservation) -> ApiResult<()> {
+        self.release_ip(reservation.ip_attempt_id).await?;
+        self.clear_email().await
+    }
+
+    pub async fn clear_email(&self) -> ApiResult<()> {
+        RateLimitDO::clear(&self.ctx.env, &self.email_key).await
+    }
+
+    // for actions where every attempt counts, not just failures
+    // errors with TooManyAttempts if either key must wait, otherwise records the attempt
+    pub async fn hit(&self) -> ApiResult<()> {
+        self.reserve().await.map(|_| ())
+    }
+
+    async fn release_ip(&self, ip_attempt_id: Option<u32>) -> ApiResult<()> {
+        match (&self.ip_key, ip_attempt_id) {
+            (Some(ip_key), Some(attempt_id)) => RateLimitDO::release(&self.ctx.env, ip_key, self.scope.ip_policy(), attempt_id).await,
+            _ => Ok(())
+        }
+    }
+}
+
+// An attempt that's already counted, see RateLimiter::reserve()
+pub struct RateLimitReservation {
+    ip_attempt_id: Option<u32>,
+    // whether this attempt locked out the email, if it fails
+    pub locked: bool,
+}
+
+fn into_result(res: RateLimitReserveResponse) -> ApiResult<(u32, bool)> {
+    match res {
+        RateLimitReserveResponse::Wait { retry_after_ms } => Err(AuthError::TooManyAttempts { retry_after_ms }.into()),
+        RateLimitReserveResponse::Reserved { attempt_id, locked } => Ok((attempt_id, locked)),
+    }
+}
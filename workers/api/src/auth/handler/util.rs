@@ -1,10 +1,12 @@
 use base64::Engine;
+use hmac::{Hmac, Mac};
 use rand::Rng;
 use sha2::{Digest, Sha256};
-use shared::auth::AUTH_TOKEN_ID_NAME;
-use crate::{db::user::UserAccount, prelude::*};
+use shared::auth::{PasswordKdf, PasswordParams, AUTH_TOKEN_ID_NAME, PASSWORD_SALT_LENGTH};
+use subtle::ConstantTimeEq;
+use crate::{config::AUTH_VERIFY_EMAIL_CODE_DIGITS, db::user::UserAccount, prelude::*};
 
-use super::super::durable_objects::token::{AuthTokenAfterValidation, AuthTokenDO, AuthTokenKind, AuthTokenValidateResponse};
+use super::super::durable_objects::token::{AuthTokenAfterValidation, AuthTokenCreateResponse, AuthTokenDO, AuthTokenKind, AuthTokenValidateResponse};
 
 // the password was sent as an argon2 hash from the client
 // but we must hash it again, otherwise that might as well just be plaintext
@@ -13,29 +15,238 @@ use super::super::durable_objects::token::{AuthTokenAfterValidation, AuthTokenDO
 // plaintext isn't the original password, it's the argon2 output bytes
 // and so an attacker would need to brute force sha256 guesses against the argon2 output space
 // for simplicity, the salt is stored alongside the password
-pub fn hash_password(password:&str, salt: Option<&[u8]>) -> ApiResult<String> {
-    let password = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(password).map_err(|err| ApiError::from(err.to_string()))?;
-    let salt = match salt {
-        Some(salt) => salt.try_into().map_err(|_| ApiError::from("salt must be 32 bytes".to_string()))?,
-        None => {
-            rand::thread_rng().gen::<[u8; 32]>()
+// and the result is prefixed with the versions, see StoredPassword
+pub fn hash_password(password:&str, kdf: PasswordKdf) -> ApiResult<String> {
+    let salt = rand::thread_rng().gen::<[u8; 32]>();
+    let hash = ServerPasswordHash::current().hash(password, &salt)?;
+    Ok(StoredPassword { server: ServerPasswordHash::current(), kdf, hash, legacy: false }.to_string())
+}
+
+// see registration, the request password is *not* the user's plaintext password, it's just the argon2 output hash
+// we need to get the salt from the db and hash it again for comparison, however
+pub fn verify_password(account: &UserAccount, password: &str) -> ApiResult<()> {
+    let stored = StoredPassword::parse(&account.password)?;
+    let db_hash = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(&stored.hash)
+        .map_err(|err| ApiError::from(err.to_string()))?;
+    let db_salt = db_hash.get(0..32).ok_or("stored password is too short")?;
+
+    let req_password = stored.server.hash(password, db_salt)?;
+
+    // see if they match, in constant time so the stored hash can't be worked out from response timing
+    if !bool::from(stored.hash.as_bytes().ct_eq(req_password.as_bytes())) {
+        return Err("mismatched password".into())
+    }
+
+    Ok(())
+}
+
+// A re-hashed password to store, along with the client salt it was derived with
+pub struct UpgradedPassword {
+    pub password: String,
+    pub client_salt: Option<String>,
+}
+
+// after a successful signin, the stored password re-hashed with the current versions, if it's outdated
+// the client hash is only good for the kdf and salt it was made with, so upgrading either of those
+// needs the upgrade_password (and upgrade_salt) from the client
+pub fn upgrade_password(account: &UserAccount, password: &str, upgrade_password: Option<&str>, upgrade_salt: Option<&str>) -> ApiResult<Option<UpgradedPassword>> {
+    let stored = StoredPassword::parse(&account.password)?;
+
+    let server_upgrade = || -> ApiResult<Option<UpgradedPassword>> {
+        if stored.needs_server_upgrade() {
+            Ok(Some(UpgradedPassword { password: hash_password(password, stored.kdf)?, client_salt: account.client_salt.clone() }))
+        } else {
+            Ok(None)
         }
     };
-    let msg = [salt.as_slice(), password.as_slice()].concat(); 
-    let hash = Sha256::digest(msg);
-    let password = [salt.as_slice(), hash.as_slice()].concat();
-    let password = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&password);
-    Ok(password)
+
+    if params_need_upgrade(&password_params(account)?) {
+        match (upgrade_password, upgrade_salt) {
+            (Some(upgrade_password), Some(upgrade_salt)) => {
+                validate_password_salt(upgrade_salt)?;
+                Ok(Some(UpgradedPassword {
+                    password: hash_password(upgrade_password, PasswordKdf::current())?,
+                    client_salt: Some(upgrade_salt.to_string()),
+                }))
+            },
+            // an old client, try again next time
+            _ => server_upgrade()
+        }
+    } else {
+        server_upgrade()
+    }
 }
 
-#[cfg(debug_assertions)]
-pub fn set_signin_cookie(res: &Response, auth_token_id: &str) {
+// the params the client hashes the password with for this account
+pub fn password_params(account: &UserAccount) -> ApiResult<PasswordParams> {
+    Ok(PasswordParams {
+        kdf: StoredPassword::parse(&account.password)?.kdf,
+        salt: account.client_salt.clone(),
+    })
+}
+
+fn params_need_upgrade(params: &PasswordParams) -> bool {
+    params.kdf != PasswordKdf::current() || params.salt.is_none()
+}
+
+// the params to upgrade to on the next signin, if these are outdated
+// params without a salt get the derived one (see derived_password_salt), which is only stored once the upgraded hash is
+// it's the same for real accounts and the fake params of unknown emails, so both go through here
+pub fn password_params_upgrade(params: &PasswordParams, derived_salt: &str) -> Option<PasswordParams> {
+    if params_need_upgrade(params) {
+        Some(PasswordParams {
+            kdf: PasswordKdf::current(),
+            salt: Some(params.salt.clone().unwrap_or_else(|| derived_salt.to_string())),
+        })
+    } else {
+        None
+    }
+}
+
+// client salts are generated by the client (or by us, for upgrades and openid registrations)
+// either way, we only check that it's the right size
+pub fn validate_password_salt(salt: &str) -> ApiResult<()> {
+    let salt = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(salt).map_err(|err| ApiError::from(err.to_string()))?;
+    if salt.len() != PASSWORD_SALT_LENGTH {
+        return Err(format!("password salt must be {PASSWORD_SALT_LENGTH} bytes").into());
+    }
+    Ok(())
+}
 
-    res.headers().set("Set-Cookie", &format!("{AUTH_TOKEN_ID_NAME}={auth_token_id}; Path=/; HttpOnly; Secure; SameSite=None; Partitioned; Expires=Tue, 19 Jan 2038 03:14:07 GMT")).unwrap();
+pub fn random_password_salt() -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; PASSWORD_SALT_LENGTH]>())
+}
+
+// zero-padded, so every code has the same number of digits
+pub fn verify_email_code() -> String {
+    let max = 10u32.pow(AUTH_VERIFY_EMAIL_CODE_DIGITS);
+    format!("{:0width$}", rand::thread_rng().gen_range(0..max), width = AUTH_VERIFY_EMAIL_CODE_DIGITS as usize)
+}
+
+// the key for derived_password_salt
+// loaded before looking up the account, so a missing secret fails the same way for every email
+pub fn password_salt_key(env: &Env) -> ApiResult<String> {
+    get_secret(env, "PASSWORD_SALT_HMAC_KEY").map_err(|err| err.into())
+}
+
+// a salt that looks random but is always the same for that email
+// the fake salt of an email without an account, and the upgrade salt of an account without one
+// so that looking up the params doesn't reveal whether the account exists, and doesn't change between lookups
+pub fn derived_password_salt(key: &str, email: &str) -> ApiResult<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).map_err(|err| ApiError::from(err.to_string()))?;
+    mac.update(email.to_lowercase().as_bytes());
+    let salt = mac.finalize().into_bytes();
+
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&salt[..PASSWORD_SALT_LENGTH]))
+}
+
+// How the server hashes the client's argon2 output
+// "s1" is salted sha256, add new variants rather than changing it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServerPasswordHash {
+    Sha256,
+}
+
+impl ServerPasswordHash {
+    pub fn current() -> Self {
+        Self::Sha256
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "s1",
+        }
+    }
+
+    fn try_from_str(s: &str) -> Option<Self> {
+        match s {
+            "s1" => Some(Self::Sha256),
+            _ => None
+        }
+    }
+
+    // returns the base64 of salt || hash
+    fn hash(&self, password: &str, salt: &[u8]) -> ApiResult<String> {
+        let password = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(password).map_err(|err| ApiError::from(err.to_string()))?;
+        let salt: [u8; 32] = salt.try_into().map_err(|_| ApiError::from("salt must be 32 bytes".to_string()))?;
+
+        match self {
+            Self::Sha256 => {
+                let msg = [salt.as_slice(), password.as_slice()].concat();
+                let hash = Sha256::digest(msg);
+                Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode([salt.as_slice(), hash.as_slice()].concat()))
+            }
+        }
+    }
+}
+
+// The password column: "$<server hash version>$k<kdf version>$<base64 of salt || hash>"
+// passwords from before this was versioned are just the base64, and are s1 + k1
+pub struct StoredPassword {
+    pub server: ServerPasswordHash,
+    pub kdf: PasswordKdf,
+    pub hash: String,
+    // stored without the versions
+    pub legacy: bool,
+}
+
+impl StoredPassword {
+    pub fn parse(stored: &str) -> ApiResult<Self> {
+        if !stored.starts_with('$') {
+            return Ok(Self {
+                server: ServerPasswordHash::Sha256,
+                kdf: PasswordKdf::from_version(1).ok_or("missing kdf version 1")?,
+                hash: stored.to_string(),
+                legacy: true,
+            });
+        }
+
+        match stored.splitn(4, '$').collect::<Vec<_>>().as_slice() {
+            ["", server, kdf, hash] => {
+                let server = ServerPasswordHash::try_from_str(server).ok_or_else(|| format!("unknown server password hash {server}"))?;
+                let kdf = kdf.strip_prefix('k')
+                    .and_then(|version| version.parse().ok())
+                    .and_then(PasswordKdf::from_version)
+                    .ok_or_else(|| format!("unknown password kdf {kdf}"))?;
+
+                Ok(Self { server, kdf, hash: hash.to_string(), legacy: false })
+            },
+            _ => Err("invalid stored password".into())
+        }
+    }
+
+    pub fn needs_server_upgrade(&self) -> bool {
+        self.legacy || self.server != ServerPasswordHash::current()
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("${}$k{}${}", self.server.as_str(), self.kdf.version, self.hash)
+    }
+}
+
+#[cfg(debug_assertions)]
+pub fn set_signin_cookie(res: &Response, auth_token: &AuthTokenCreateResponse) {
+    let auth_token_id = &auth_token.id;
+    let expires = signin_cookie_expires(auth_token);
+    res.headers().set("Set-Cookie", &format!("{AUTH_TOKEN_ID_NAME}={auth_token_id}; Path=/; HttpOnly; Secure; SameSite=None; Partitioned{expires}")).unwrap();
 }
 #[cfg(not(debug_assertions))]
-pub fn set_signin_cookie(res: &Response, auth_token_id: &str) {
-    res.headers().set("Set-Cookie", &format!("{AUTH_TOKEN_ID_NAME}={auth_token_id}; Path=/; HttpOnly; Secure; SameSite=Strict; Partitioned; Expires=Tue, 19 Jan 2038 03:14:07 GMT")).unwrap();
+pub fn set_signin_cookie(res: &Response, auth_token: &AuthTokenCreateResponse) {
+    let auth_token_id = &auth_token.id;
+    let expires = signin_cookie_expires(auth_token);
+    res.headers().set("Set-Cookie", &format!("{AUTH_TOKEN_ID_NAME}={auth_token_id}; Path=/; HttpOnly; Secure; SameSite=Strict; Partitioned{expires}")).unwrap();
+}
+
+// matches the token's max lifetime, or nothing for a session cookie
+fn signin_cookie_expires(auth_token: &AuthTokenCreateResponse) -> String {
+    match auth_token.cookie_expires_at {
+        Some(expires_at) => {
+            let date: String = worker::js_sys::Date::new(&JsValue::from_f64(expires_at as f64)).to_utc_string().into();
+            format!("; Expires={date}")
+        },
+        None => String::new()
+    }
 }
 
 pub fn delete_signin_cookie(res: &Response) {
@@ -43,7 +254,7 @@ pub fn delete_signin_cookie(res: &Response) {
 }
 
 pub async fn validate_oob_token(env: &Env, kind: AuthTokenKind, oob_token_id: String, oob_token_key: String, after_validate: AuthTokenAfterValidation) -> ApiResult<UserAccount> {
-    let AuthTokenValidateResponse {uid, user_token} = AuthTokenDO::validate(env, kind, &oob_token_id, oob_token_key, after_validate).await?;
+    let AuthTokenValidateResponse {uid, user_token, ..} = AuthTokenDO::validate(env, kind, &oob_token_id, oob_token_key, after_validate).await?;
 
     let account = UserAccount::load_by_id(env, &uid).await?;
     if account.user_token != user_token {
@@ -51,3 +262,47 @@ pub async fn validate_oob_token(env: &Env, kind: AuthTokenKind, oob_token_id: St
     }
     Ok(account)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_legacy_password() {
+        let stored = StoredPassword::parse("c2FsdGFuZGhhc2g").unwrap();
+        assert!(stored.legacy);
+        assert!(stored.needs_server_upgrade());
+        assert_eq!(stored.server, ServerPasswordHash::Sha256);
+        assert_eq!(stored.kdf.version, 1);
+        assert_eq!(stored.hash, "c2FsdGFuZGhhc2g");
+    }
+
+    #[test]
+    fn parses_versioned_password() {
+        let stored = StoredPassword::parse("$s1$k1$c2FsdGFuZGhhc2g").unwrap();
+        assert!(!stored.legacy);
+        assert!(!stored.needs_server_upgrade());
+        assert_eq!(stored.server, ServerPasswordHash::Sha256);
+        assert_eq!(stored.kdf.version, 1);
+        assert_eq!(stored.hash, "c2FsdGFuZGhhc2g");
+        assert_eq!(stored.to_string(), "$s1$k1$c2FsdGFuZGhhc2g");
+    }
+
+    #[test]
+    fn legacy_password_is_written_back_versioned() {
+        let stored = StoredPassword::parse("c2FsdGFuZGhhc2g").unwrap();
+        assert_eq!(stored.to_string(), "$s1$k1$c2FsdGFuZGhhc2g");
+    }
+
+    #[test]
+    fn rejects_malformed_password() {
+        assert!(StoredPassword::parse("$").is_err());
+        assert!(StoredPassword::parse("$s1").is_err());
+        assert!(StoredPassword::parse("$s1$k1").is_err());
+        assert!(StoredPassword::parse("$s9$k1$c2FsdGFuZGhhc2g").is_err());
+        assert!(StoredPassword::parse("$s1$k0$c2FsdGFuZGhhc2g").is_err());
+        assert!(StoredPassword::parse("$s1$1$c2FsdGFuZGhhc2g").is_err());
+        assert!(StoredPassword::parse("$s1$kx$c2FsdGFuZGhhc2g").is_err());
+        assert!(StoredPassword::parse("$s1$k$c2FsdGFuZGhhc2g").is_err());
+    }
+}
@@ -1,5 +1,18 @@
 mod user;
 mod handler;
+mod event;
+mod origin;
+mod email_rules;
+mod challenge;
+mod sso;
 pub(super) mod durable_objects;
 
-pub use user::AuthUser;
\ No newline at end of file
+pub use user::{AuthCredentials, AuthUser};
+pub use event::record_auth_event;
+pub use origin::check_request_origin;
+pub use email_rules::check_email_allowed;
+pub use challenge::{check_challenge, ChallengeRoute};
+pub use sso::{check_sso_policy, check_sso_provider, generate_sso_verification_token, has_txt_record, normalize_domain, sso_provider_for_email};
+pub use durable_objects::rate_limit::RateLimitPolicy;
+pub use durable_objects::token::AuthTokenLifetime;
+pub(crate) use handler::{verify_password, delete_signin_cookie};
\ No newline at end of file
@@ -0,0 +1,29 @@
+use shared::backend::route::Route;
+use crate::{config::ALLOWED_ORIGINS, prelude::*};
+
+// Rejects state-changing requests that a browser made on behalf of some other site
+// the token key header already covers fully signed-in routes, this covers the rest (see RouteAuthKind::requires_allowed_origin)
+// browsers always send Origin on non-GET requests, and Sec-Fetch-Site on any request
+// a request with neither isn't from a browser (curl, servers, api token clients), so it has no ambient cookies to abuse
+pub fn check_request_origin(req: &Request, route: &Route) -> ApiResult<()> {
+    let method = req.method().to_uppercase();
+    if method == "GET" || method == "HEAD" || !route.auth_kind().requires_allowed_origin() {
+        return Ok(());
+    }
+
+    let origin = req.headers().get("Origin")?;
+    let fetch_site = req.headers().get("Sec-Fetch-Site")?;
+
+    let allowed = match (&origin, &fetch_site) {
+        (Some(origin), _) => ALLOWED_ORIGINS.iter().any(|x| x == origin),
+        (None, Some(fetch_site)) => fetch_site == "same-origin",
+        (None, None) => true,
+    };
+
+    if !allowed {
+        worker::console_log!("rejected {} request to {} from origin {:?} (sec-fetch-site {:?})", method, route, origin, fetch_site);
+        return Err(AuthError::InvalidOrigin.into());
+    }
+
+    Ok(())
+}
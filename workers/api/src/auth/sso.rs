@@ -0,0 +1,128 @@
+use base64::Engine;
+use rand::Rng;
+use serde::Deserialize;
+use shared::backend::route::OpenIdProvider;
+use web_sys::{js_sys, Headers, RequestInit, WorkerGlobalScope};
+use crate::{config::{SSO_DOMAIN_DNS_QUERY_URL, SSO_DOMAIN_TOKEN_LENGTH}, db::sso_domain::SsoDomain, prelude::*};
+use super::handler::openid::OpenIdProviderConfig;
+
+// Organizations can claim their email domains, once the claim is verified
+// emails on the domain may only sign in through the organization's OpenID provider
+// only the exact domain is claimed, subdomains need their own claim
+
+// fails with SsoRequired if the email's domain is claimed, unless it's going through the claimed provider
+// via is the provider the flow goes through, None for password-based flows (signin, register, reset, etc.)
+pub async fn check_sso_policy(env: &Env, email: &str, via: Option<&OpenIdProvider>) -> ApiResult<()> {
+    match sso_provider_for_email(env, email).await? {
+        Some(provider) if via != Some(&provider) => Err(AuthError::SsoRequired { provider }.into()),
+        _ => Ok(())
+    }
+}
+
+// the provider the email's domain must sign in through, if it's claimed
+pub async fn sso_provider_for_email(env: &Env, email: &str) -> ApiResult<Option<OpenIdProvider>> {
+    match email.rsplit_once('@').and_then(|(_, domain)| normalize_domain(domain)) {
+        Some(domain) => Ok(SsoDomain::load_verified(env, &domain).await?.map(|sso_domain| sso_domain.provider)),
+        // not an email the rest of the flow will accept anyway
+        None => Ok(None)
+    }
+}
+
+// lowercase, without a trailing dot, and only what can be in a hostname
+// None if it can't be a domain at all
+pub fn normalize_domain(domain: &str) -> Option<String> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+
+    let valid = domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+        && domain.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-');
+
+    valid.then_some(domain)
+}
+
+// the provider must be one that's configured, otherwise nobody on the domain could sign in at all
+pub fn check_sso_provider(env: &Env, provider: &OpenIdProvider) -> ApiResult<()> {
+    OpenIdProviderConfig::load(env, provider).map(|_| ())
+}
+
+pub fn generate_sso_verification_token() -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; SSO_DOMAIN_TOKEN_LENGTH]>())
+}
+
+#[derive(Deserialize)]
+struct DnsQueryResponse {
+    // 0 is NOERROR
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DnsAnswer {
+    #[serde(rename = "type")]
+    kind: u32,
+    data: String,
+}
+
+const DNS_TYPE_TXT: u32 = 16;
+
+// whether any TXT record at name has exactly this value
+// https://developers.cloudflare.com/1.1.1.1/encryption/dns-over-https/make-api-requests/dns-json/
+pub async fn has_txt_record(name: &str, value: &str) -> ApiResult<bool> {
+    let mut init = RequestInit::new();
+    init.method("GET");
+    let headers = Headers::new()?;
+    headers.set("accept", "application/dns-json")?;
+    init.headers(&headers);
+
+    // name is made of a normalized domain, so there's nothing to escape
+    let url = format!("{SSO_DOMAIN_DNS_QUERY_URL}?name={name}&type=TXT");
+    let req = Request::new_with_str_and_init(&url, &init)?;
+    let promise = js_sys::global().unchecked_into::<WorkerGlobalScope>().fetch_with_request(&req);
+    let res = JsFuture::from(promise).await?.unchecked_into::<Response>();
+
+    if !res.ok() {
+        return Err(format!("dns query failed, status code: {}", res.status()).into());
+    }
+
+    let text = JsFuture::from(res.text()?).await?.as_string().unwrap_or_default();
+    let res: DnsQueryResponse = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+
+    if res.status != 0 {
+        return Ok(false);
+    }
+
+    // the data is quoted, and long records come as several quoted strings that belong together
+    Ok(res.answer.iter()
+        .filter(|answer| answer.kind == DNS_TYPE_TXT)
+        .any(|answer| answer.data.trim_matches('"').replace("\" \"", "") == value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_domain;
+
+    #[test]
+    fn normalizes_case_and_trailing_dot() {
+        assert_eq!(normalize_domain("Example.COM").as_deref(), Some("example.com"));
+        assert_eq!(normalize_domain(" example.com. ").as_deref(), Some("example.com"));
+        assert_eq!(normalize_domain("example.com...").as_deref(), Some("example.com"));
+        assert_eq!(normalize_domain("Mail.Example.com").as_deref(), Some("mail.example.com"));
+    }
+
+    #[test]
+    fn rejects_what_cant_be_a_domain() {
+        assert_eq!(normalize_domain(""), None);
+        assert_eq!(normalize_domain("."), None);
+        assert_eq!(normalize_domain("localhost"), None);
+        assert_eq!(normalize_domain(".example.com"), None);
+        assert_eq!(normalize_domain("example..com"), None);
+        assert_eq!(normalize_domain("-example.com"), None);
+        assert_eq!(normalize_domain("example-.com"), None);
+        assert_eq!(normalize_domain("exa mple.com"), None);
+        assert_eq!(normalize_domain("user@example.com"), None);
+        assert_eq!(normalize_domain("example.com/path"), None);
+        assert_eq!(normalize_domain("exämple.com"), None);
+    }
+}
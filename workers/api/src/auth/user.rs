@@ -1,24 +1,49 @@
-use shared::{auth::{AUTH_TOKEN_ID_NAME, AUTH_TOKEN_KEY_NAME}, backend::route::{Route, RouteAuthKind}};
+use shared::{api::admin::ImpersonationEvent, auth::{AUTH_IMPERSONATION_TOKEN_ID_NAME, AUTH_TOKEN_ID_NAME, AUTH_TOKEN_KEY_NAME}, backend::route::{Route, RouteAuthKind}, user::{Permission, Role, UserId}};
+use subtle::ConstantTimeEq;
 use worker::Env;
 
-use crate::{prelude::*, config::AUTH_SIGNIN_TOKEN_EXPIRES, db::user::UserAccount};
+use crate::{prelude::*, api_token::{hash_secret, parse_token}, config::{API_TOKEN_LAST_USED_RESOLUTION, AUTH_FRESH_SIGNIN_MAX_AGE, AUTH_STEP_UP_MAX_AGE, TERMS_VERSION}, db::{api_token::ApiToken, impersonation::ImpersonationLog, role::UserRole, terms_acceptance::TermsAcceptance, user::UserAccount}, organization::ActiveOrganization};
 
 use super::durable_objects::token::{AuthTokenAfterValidation, AuthTokenDO, AuthTokenKind, AuthTokenValidateResponse};
 
 pub struct AuthUser {
     pub account: UserAccount,
-    pub token_id: String,
-    pub token_key: String,
+    pub roles: Vec<Role>,
+    pub credentials: AuthCredentials,
+    // None if there's no active organization, or it's not (any longer) one the user belongs to
+    pub organization: Option<ActiveOrganization>,
+}
+
+// how the request was authenticated
+pub enum AuthCredentials {
+    // a browser (or other interactive) session, from signing in
+    Signin {
+        token_id: String,
+        token_key: String,
+        // unix timestamp in milliseconds of when this signin token was created
+        created_at: u64,
+        // the terms version cached in the signin token, see AuthUser::check_terms
+        terms_version: Option<u32>,
+    },
+    // a personal api token, sent as "Authorization: Bearer"
+    ApiToken {
+        id: String,
+    },
+    // an admin acting as this user, the account is the impersonated one
+    Impersonation {
+        token_id: String,
+        actor: UserId,
+    }
 }
 
 impl AuthUser {
     pub async fn try_new(env: &Env, req: &Request, route: &Route) -> ApiResult<Option<AuthUser>> {
         // early exit or get the auth token
-        let user = match route.auth_kind() {
+        let mut user = match route.auth_kind() {
             RouteAuthKind::None | RouteAuthKind::CookiesOnly => {
                 None
             },
-            auth_kind => match AuthUser::validate(&env, &req, auth_kind).await {
+            auth_kind => match AuthUser::validate(&env, &req, route, auth_kind).await {
                 Ok(user) => {
                     Some(user)
                 },
@@ -37,10 +62,119 @@ impl AuthUser {
             }
         };
 
+        // like the email check, but passed through as-is from here so the client can ask to accept the new terms
+        if let Some(user) = &user {
+            if route.requires_terms() && !user.check_terms(env).await? {
+                worker::console_log!("user {} hasn't accepted terms version {}", user.account.id, TERMS_VERSION);
+                return Err(AuthError::TermsNotAccepted.into());
+            }
+        }
+
+        // authorization, only once we know who it is
+        // this error is passed through as-is, so the client knows it's signed in but not allowed
+        if let (Some(user), Some(permission)) = (&user, route.required_permission()) {
+            if !user.has_permission(permission) {
+                worker::console_log!("user {} is missing {:?} for {}", user.account.id, permission, route);
+                return Err(AuthError::Forbidden.into());
+            }
+        }
+
+        // step-up, also passed through as-is so the client can ask the user to sign in again
+        if let Some(user) = &user {
+            if route.requires_recent_signin() && !user.signed_in_within(AUTH_STEP_UP_MAX_AGE) {
+                worker::console_log!("user {} needs a recent signin for {}", user.account.id, route);
+                return Err(AuthError::RecentSigninRequired.into());
+            }
+        }
+
+        if let Some(user) = &mut user {
+            user.organization = ActiveOrganization::load(env, &user.account).await?;
+        }
+
+        // every impersonated request is recorded, and some aren't allowed at all
+        if let Some(user) = &user {
+            if let Some(actor) = user.impersonator() {
+                if !route.allows_impersonation() {
+                    worker::console_log!("admin {} can't call {} while impersonating {}", actor, route, user.account.id);
+                    return Err(AuthError::Forbidden.into());
+                }
+                ImpersonationLog::insert(env, actor, &user.account.id, ImpersonationEvent::Request, Some(&route.to_string())).await?;
+            }
+        }
+
         Ok(user)
     }
 
-    async fn validate(env: &Env, req: &Request, auth_kind: RouteAuthKind) -> ApiResult<AuthUser> {
+    // whether the user has accepted the current terms, or doesn't need to here
+    // an impersonating admin isn't the one agreeing to them, and can't accept them for the user either
+    // api tokens are left alone too, so scripts don't break every time the terms change
+    // a signin token remembers the version once it's seen it, so only an outdated one asks the db
+    // (the user may have accepted in another session)
+    async fn check_terms(&self, env: &Env) -> ApiResult<bool> {
+        match &self.credentials {
+            AuthCredentials::Signin { token_id, terms_version, .. } => {
+                if terms_version.is_some_and(|version| version >= TERMS_VERSION) {
+                    return Ok(true);
+                }
+
+                match TermsAcceptance::latest_version(env, &self.account.id).await? {
+                    Some(version) if version >= TERMS_VERSION => {
+                        AuthTokenDO::set_terms_version(env, token_id, version).await?;
+                        Ok(true)
+                    },
+                    _ => Ok(false)
+                }
+            },
+            AuthCredentials::ApiToken { .. } | AuthCredentials::Impersonation { .. } => Ok(true),
+        }
+    }
+
+    // the admin acting as this user, if it's an impersonation
+    pub fn impersonator(&self) -> Option<&UserId> {
+        match &self.credentials {
+            AuthCredentials::Impersonation { actor, .. } => Some(actor),
+            _ => None,
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        Role::has_permission(&self.roles, permission)
+    }
+
+    // whether this session was created recently enough to stand in for re-entering the password
+    // api tokens never count, they aren't proof that a person is present
+    pub fn signed_in_recently(&self) -> bool {
+        self.signed_in_within(AUTH_FRESH_SIGNIN_MAX_AGE)
+    }
+
+    pub fn signed_in_within(&self, max_age: u64) -> bool {
+        match &self.credentials {
+            AuthCredentials::Signin { created_at, .. } => now_ms().saturating_sub(*created_at) <= max_age,
+            AuthCredentials::ApiToken { .. } | AuthCredentials::Impersonation { .. } => false,
+        }
+    }
+
+    pub fn signin_token_id(&self) -> Option<&str> {
+        match &self.credentials {
+            AuthCredentials::Signin { token_id, .. } => Some(token_id),
+            AuthCredentials::ApiToken { .. } | AuthCredentials::Impersonation { .. } => None,
+        }
+    }
+
+    async fn validate(env: &Env, req: &Request, route: &Route, auth_kind: RouteAuthKind) -> ApiResult<AuthUser> {
+        if let Some(authorization) = req.headers().get("authorization")? {
+            if let Some(token) = authorization.strip_prefix("Bearer ") {
+                return Self::validate_api_token(env, route, auth_kind, token.trim()).await;
+            }
+        }
+
+        // token key is always from header
+        let token_key = req.headers().get(AUTH_TOKEN_KEY_NAME)?.ok_or(ApiError::from("missing token key".to_string()))?;
+
+        if let Some(token_id) = req.headers().get(AUTH_IMPERSONATION_TOKEN_ID_NAME)? {
+            return Self::validate_impersonation(env, auth_kind, token_id, token_key).await;
+        }
+
         // first try and get it from the header, e.g. for non-browser clients
         let mut token_id = req.headers().get(AUTH_TOKEN_ID_NAME)?;
 
@@ -57,14 +191,52 @@ impl AuthUser {
         }
 
         let token_id = token_id.ok_or(ApiError::from("missing token id".to_string()))?;
-        // token key is always from header
-        let token_key = req.headers().get(AUTH_TOKEN_KEY_NAME)?.ok_or(ApiError::from("missing token key".to_string()))?;
-
 
         // validate the token id and key
-        let AuthTokenValidateResponse {uid, user_token} = AuthTokenDO::validate(env, AuthTokenKind::Signin, &token_id, token_key.clone(), AuthTokenAfterValidation::ExtendExpiresMs(AUTH_SIGNIN_TOKEN_EXPIRES)).await?;
+        let AuthTokenValidateResponse {uid, user_token, created_at, terms_version, ..} = AuthTokenDO::validate(env, AuthTokenKind::Signin, &token_id, token_key.clone(), AuthTokenAfterValidation::ExtendIdle).await?;
 
-        let account = UserAccount::load_by_id(env, &uid).await?;
+        let account = Self::load_account(env, auth_kind, &uid, &user_token).await?;
+        let roles = UserRole::list_by_uid(env, &account.id).await?;
+
+        Ok(AuthUser {
+            account,
+            roles,
+            organization: None,
+            credentials: AuthCredentials::Signin {
+                token_id,
+                token_key,
+                created_at,
+                terms_version,
+            }
+        })
+    }
+
+    // the impersonation token's expiry is absolute, and the acting admin must still be allowed to impersonate
+    async fn validate_impersonation(env: &Env, auth_kind: RouteAuthKind, token_id: String, token_key: String) -> ApiResult<AuthUser> {
+        let AuthTokenValidateResponse {uid, user_token, actor, ..} = AuthTokenDO::validate(env, AuthTokenKind::Impersonation, &token_id, token_key, AuthTokenAfterValidation::Keep).await?;
+        let actor = actor.ok_or(ApiError::from("impersonation token has no actor".to_string()))?;
+
+        if !Role::has_permission(&UserRole::list_by_uid(env, &actor).await?, Permission::Impersonate) {
+            return Err(format!("{actor} is no longer allowed to impersonate").into());
+        }
+
+        let account = Self::load_account(env, auth_kind, &uid, &user_token).await?;
+        let roles = UserRole::list_by_uid(env, &account.id).await?;
+
+        Ok(AuthUser {
+            account,
+            roles,
+            organization: None,
+            credentials: AuthCredentials::Impersonation {
+                token_id,
+                actor,
+            }
+        })
+    }
+
+    // the checks after a signin (or impersonation) token is validated
+    async fn load_account(env: &Env, auth_kind: RouteAuthKind, uid: &UserId, user_token: &str) -> ApiResult<UserAccount> {
+        let account = UserAccount::load_by_id(env, uid).await?;
 
         match auth_kind {
             // no need to handle all the variants here, we've early-exited for non-auth routes
@@ -89,10 +261,50 @@ impl AuthUser {
             }
         }
 
+        Ok(account)
+    }
+
+    // api tokens are independent of the signin tokens (and UserToken), they're only revoked explicitly
+    async fn validate_api_token(env: &Env, route: &Route, auth_kind: RouteAuthKind, token: &str) -> ApiResult<AuthUser> {
+        let scope = route.api_token_scope().ok_or_else(|| ApiError::from(format!("api tokens can't be used for {route}")))?;
+        let (id, secret) = parse_token(token)?;
+
+        let api_token = ApiToken::load(env, id).await?.ok_or_else(|| ApiError::from(format!("no such api token {id}")))?;
+
+        // constant-time, so the stored hash can't be worked out from response timing
+        if !bool::from(api_token.token_hash.as_bytes().ct_eq(hash_secret(secret).as_bytes())) {
+            return Err(format!("api token secret mismatch for {id}").into());
+        }
+
+        let now = now_ms();
+        if api_token.expires_at.is_some_and(|expires_at| now >= expires_at) {
+            return Err(format!("api token {id} has expired").into());
+        }
+
+        if !api_token.scopes.contains(&scope) {
+            return Err(format!("api token {id} is missing the {scope:?} scope").into());
+        }
+
+        let account = UserAccount::load_by_id(env, &api_token.uid).await?;
+
+        if auth_kind == RouteAuthKind::Full && !account.email_verified {
+            return Err(AuthError::EmailNotVerified.into())
+        }
+
+        // don't write on every request
+        if api_token.last_used_at.map_or(true, |last_used_at| now.saturating_sub(last_used_at) >= API_TOKEN_LAST_USED_RESOLUTION) {
+            ApiToken::update_last_used(env, id, now).await?;
+        }
+
+        let roles = UserRole::list_by_uid(env, &account.id).await?;
+
         Ok(AuthUser {
             account,
-            token_id,
-            token_key
+            roles,
+            organization: None,
+            credentials: AuthCredentials::ApiToken {
+                id: api_token.id
+            }
         })
     }
 }
\ No newline at end of file
@@ -1,19 +1,180 @@
-use crate::context::ContentLanguage;
+use crate::{auth::{AuthTokenLifetime, RateLimitPolicy}, context::ContentLanguage};
 
 const MS_PER_MIN:u64 = 1000 * 60;
 const MS_PER_HOUR:u64 = 60 * MS_PER_MIN;
-const MS_PER_DAY:u64 = 24 * MS_PER_HOUR;
+pub(crate) const MS_PER_DAY:u64 = 24 * MS_PER_HOUR;
 const MS_PER_WEEK:u64 = 7 * MS_PER_DAY;
 
-pub const AUTH_SIGNIN_TOKEN_EXPIRES:u64 = MS_PER_WEEK * 2;
+// signins are extended by use, up to a max lifetime, "remember me" is chosen at signin
+pub const AUTH_SIGNIN_LIFETIME_SESSION:AuthTokenLifetime = AuthTokenLifetime {
+    idle: MS_PER_HOUR * 2,
+    max: MS_PER_HOUR * 12,
+};
+pub const AUTH_SIGNIN_LIFETIME_REMEMBER:AuthTokenLifetime = AuthTokenLifetime {
+    idle: MS_PER_WEEK * 2,
+    max: MS_PER_DAY * 30,
+};
 pub const AUTH_RESET_PASSWORD_TOKEN_EXPIRES:u64 = MS_PER_HOUR;
 pub const AUTH_VERIFY_EMAIL_TOKEN_EXPIRES:u64 = MS_PER_DAY * 3;
+// the code sent along with the verification link, for typing in on another device
+// it's short, so a few wrong guesses use up the whole token (link included)
+pub const AUTH_VERIFY_EMAIL_CODE_DIGITS:u32 = 6;
+pub const AUTH_VERIFY_EMAIL_CODE_MAX_ATTEMPTS:u32 = 5;
+// short-lived, since it's a full signin for whoever has the link
+pub const AUTH_MAGIC_LINK_TOKEN_EXPIRES:u64 = MS_PER_MIN * 15;
 pub const AUTH_OPEN_ID_SESSION_EXPIRES:u64 = MS_PER_HOUR;
+// a signin this recent is accepted in place of a password for sensitive actions (e.g. deleting the account)
+pub const AUTH_FRESH_SIGNIN_MAX_AGE:u64 = MS_PER_MIN * 5;
+// step-up, for routes with requires_recent_signin()
+pub const AUTH_STEP_UP_MAX_AGE:u64 = MS_PER_MIN * 15;
+// not extended by use, the admin has to start again
+pub const AUTH_IMPERSONATION_TOKEN_EXPIRES:u64 = MS_PER_MIN * 30;
+pub const IMPERSONATION_LOG_LIST_LIMIT:u32 = 200;
+// how many of their own auth events a user sees in the dashboard
+pub const AUTH_EVENT_LIST_LIMIT:u32 = 50;
+// per field, in characters (names, locale, picture url)
+pub const PROFILE_FIELD_MAX_LENGTH:usize = 500;
+
+// organization invites are links in an email, like email verification
+pub const ORGANIZATION_INVITE_EXPIRES:u64 = MS_PER_DAY * 7;
+// in characters
+pub const ORGANIZATION_NAME_MAX_LENGTH:usize = 100;
+// claiming a domain for SSO is proven with a TXT record at "<prefix>.<domain>", with "<value prefix><token>" in it
+pub const SSO_DOMAIN_TXT_RECORD_PREFIX:&'static str = "_sso-verification";
+pub const SSO_DOMAIN_TXT_VALUE_PREFIX:&'static str = "sso-verification=";
+// random bytes in the verification token, before base64
+pub const SSO_DOMAIN_TOKEN_LENGTH:usize = 16;
+// DNS over HTTPS with the json api, since workers can't make dns queries directly
+pub const SSO_DOMAIN_DNS_QUERY_URL:&'static str = "https://cloudflare-dns.com/dns-query";
+
+// personal api tokens look like "pat_<id>_<secret>", the prefix makes leaked tokens easy to scan for
+pub const API_TOKEN_PREFIX:&'static str = "pat_";
+pub const API_TOKEN_SECRET_LENGTH:usize = 32;
+pub const API_TOKEN_MAX_PER_USER:u32 = 20;
+// random bytes in an invite code, before base64
+pub const INVITE_CODE_LENGTH:usize = 12;
+// characters per recovery code (~49 bits each), and how many in a set
+pub const RECOVERY_CODE_LENGTH:usize = 10;
+pub const RECOVERY_CODE_COUNT:usize = 10;
+// last_used_at is only written when it's this stale, rather than on every request
+pub const API_TOKEN_LAST_USED_RESOLUTION:u64 = MS_PER_MIN * 5;
+
+// users can cancel their account deletion until this passes
+pub const ACCOUNT_DELETION_GRACE_PERIOD:u64 = MS_PER_DAY * 14;
+
+// brute-force protection, per email and per client ip
+// ip limits are looser since many users can share an ip (offices, mobile carriers, etc.)
+pub const AUTH_RATE_LIMIT_SIGNIN_EMAIL:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_MIN * 15,
+    free_attempts: 3,
+    delay_base: 1000,
+    max_attempts: 10,
+    lockout: MS_PER_MIN * 15,
+};
+pub const AUTH_RATE_LIMIT_SIGNIN_IP:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_MIN * 15,
+    free_attempts: 20,
+    delay_base: 1000,
+    max_attempts: 100,
+    lockout: MS_PER_MIN * 15,
+};
+// every send counts here, not just failures, since each one lands in someone's inbox
+pub const AUTH_RATE_LIMIT_RESET_PASSWORD_EMAIL:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 1,
+    delay_base: MS_PER_MIN,
+    max_attempts: 5,
+    lockout: MS_PER_HOUR,
+};
+pub const AUTH_RATE_LIMIT_RESET_PASSWORD_IP:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 5,
+    delay_base: 10 * 1000,
+    max_attempts: 20,
+    lockout: MS_PER_HOUR,
+};
+// same as password reset, every send lands in someone's inbox
+pub const AUTH_RATE_LIMIT_MAGIC_LINK_EMAIL:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 1,
+    delay_base: MS_PER_MIN,
+    max_attempts: 5,
+    lockout: MS_PER_HOUR,
+};
+pub const AUTH_RATE_LIMIT_MAGIC_LINK_IP:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 5,
+    delay_base: 10 * 1000,
+    max_attempts: 20,
+    lockout: MS_PER_HOUR,
+};
+// every send counts, same as password reset
+pub const AUTH_RATE_LIMIT_VERIFY_EMAIL_SEND_EMAIL:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 1,
+    delay_base: MS_PER_MIN,
+    max_attempts: 5,
+    lockout: MS_PER_HOUR,
+};
+pub const AUTH_RATE_LIMIT_VERIFY_EMAIL_SEND_IP:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 5,
+    delay_base: 10 * 1000,
+    max_attempts: 20,
+    lockout: MS_PER_HOUR,
+};
+// wrong codes count across tokens, so resending doesn't buy more guesses
+pub const AUTH_RATE_LIMIT_VERIFY_EMAIL_CODE_EMAIL:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 3,
+    delay_base: 1000,
+    max_attempts: 10,
+    lockout: MS_PER_HOUR,
+};
+pub const AUTH_RATE_LIMIT_VERIFY_EMAIL_CODE_IP:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 20,
+    delay_base: 1000,
+    max_attempts: 100,
+    lockout: MS_PER_HOUR,
+};
+pub const AUTH_RATE_LIMIT_REGISTER_EMAIL:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 3,
+    delay_base: 1000,
+    max_attempts: 10,
+    lockout: MS_PER_HOUR,
+};
+pub const AUTH_RATE_LIMIT_REGISTER_IP:RateLimitPolicy = RateLimitPolicy {
+    window: MS_PER_HOUR,
+    free_attempts: 5,
+    delay_base: 10 * 1000,
+    max_attempts: 20,
+    lockout: MS_PER_HOUR,
+};
 
 // the key is never used in isolation, rather it's used in conjunction with the id
 // 16 bytes of randomness is more than enough
 pub const AUTH_TOKEN_KEY_LENGTH:usize = 16;
 
+// added to every response, see apply_security_headers()
+// routes can replace or add to these with Route::security_headers(), and Cache-Control is per route, see Route::allows_caching()
+pub const SECURITY_HEADERS: &[(&str, &str)] = &[
+    ("Strict-Transport-Security", "max-age=63072000; includeSubDomains"),
+    ("X-Content-Type-Options", "nosniff"),
+    // api urls can have oob tokens in them, which shouldn't leak anywhere
+    ("Referrer-Policy", "no-referrer"),
+    // it's json, there's nothing to allow
+    ("Permissions-Policy", "accelerometer=(), camera=(), geolocation=(), gyroscope=(), magnetometer=(), microphone=(), payment=(), usb=()"),
+];
+
+// some apis (e.g. github) reject requests without one
+pub const HTTP_USER_AGENT:&'static str = "fullstack-rust-starter";
+
+// bump this whenever the terms change
+// everyone is then asked to accept the new version before they can use fully protected routes again
+pub const TERMS_VERSION:u32 = 1;
+
 // just a random password when registering oauth users for the first time
 pub const OAUTH_REGISTER_PASSWORD_LENGTH:usize = 32;
 
@@ -47,8 +208,34 @@ cfg_if::cfg_if! {
 
 pub const DB_TABLE:DbTable = DbTable {
     user_account: "user_account",
+    user_identity: "user_identity",
+    api_token: "api_token",
+    user_role: "user_role",
+    impersonation_log: "impersonation_log",
+    auth_event: "auth_event",
+    user_profile: "user_profile",
+    invite: "invite",
+    recovery_code: "recovery_code",
+    terms_acceptance: "terms_acceptance",
+    organization: "organization",
+    membership: "membership",
+    organization_invite: "organization_invite",
+    sso_domain: "sso_domain",
 };
 
 pub struct DbTable {
     pub user_account: &'static str,
+    pub user_identity: &'static str,
+    pub api_token: &'static str,
+    pub user_role: &'static str,
+    pub impersonation_log: &'static str,
+    pub auth_event: &'static str,
+    pub user_profile: &'static str,
+    pub invite: &'static str,
+    pub recovery_code: &'static str,
+    pub terms_acceptance: &'static str,
+    pub organization: &'static str,
+    pub membership: &'static str,
+    pub organization_invite: &'static str,
+    pub sso_domain: &'static str,
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
 use shared::user::UserId;
 use unic_langid::LanguageIdentifier;
 use worker::{Context, Env};
-use crate::{auth::AuthUser, config::DEFAULT_CONTENT_LANG};
+use shared::backend::result::{ApiResult, AuthError};
+use crate::{auth::AuthUser, config::DEFAULT_CONTENT_LANG, organization::ActiveOrganization};
 
 pub struct ApiContext {
     pub req: web_sys::Request,
@@ -40,6 +41,25 @@ impl ApiContext {
     pub fn uid_unchecked(&self) -> UserId {
         self.user.as_ref().unwrap().account.id.clone()
     }
+
+    // for routes that act on the active organization
+    pub fn active_org(&self) -> ApiResult<&ActiveOrganization> {
+        self.user.as_ref().and_then(|user| user.organization.as_ref()).ok_or(AuthError::NoActiveOrganization.into())
+    }
+
+    // set by cloudflare, can't be spoofed by the client
+    pub fn client_ip(&self) -> Option<String> {
+        self.req.headers().get("CF-Connecting-IP").unwrap()
+    }
+
+    pub fn user_agent(&self) -> Option<String> {
+        self.req.headers().get("User-Agent").unwrap()
+    }
+
+    // also set by cloudflare, and shows up in its logs, so events can be matched up with them
+    pub fn request_id(&self) -> Option<String> {
+        self.req.headers().get("CF-Ray").unwrap()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::api_token::ApiTokenInfo, backend::route::ApiTokenScope, user::UserId};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+#[derive(Deserialize, Serialize, Debug)]
+struct ApiTokenDb {
+    pub id: String,
+    pub uid: UserId,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub created_at: String,
+    pub last_used_at: Option<u64>,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiToken {
+    pub id: String,
+    pub uid: UserId,
+    pub name: String,
+    // sha256 of the secret part, see api_token::hash_secret
+    pub token_hash: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub created_at: String,
+    // unix timestamps in milliseconds
+    pub last_used_at: Option<u64>,
+    pub expires_at: Option<u64>,
+}
+
+impl TryFrom<ApiTokenDb> for ApiToken {
+    type Error = ApiError;
+
+    fn try_from(db: ApiTokenDb) -> ApiResult<Self> {
+        Ok(Self {
+            id: db.id,
+            uid: db.uid,
+            name: db.name,
+            token_hash: db.token_hash,
+            scopes: serde_json::from_str(&db.scopes).map_err(|err| err.to_string())?,
+            created_at: db.created_at,
+            last_used_at: db.last_used_at,
+            expires_at: db.expires_at,
+        })
+    }
+}
+
+impl From<ApiToken> for ApiTokenInfo {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+impl ApiToken {
+    pub async fn load(env: &Env, id: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE id = ?1", DB_TABLE.api_token))
+            .bind(&[id.into()])?
+            .first::<ApiTokenDb>(None).await?
+            .map(ApiToken::try_from)
+            .transpose()
+    }
+
+    pub async fn list_by_uid(env: &Env, uid: &UserId) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE uid = ?1 ORDER BY created_at", DB_TABLE.api_token))
+            .bind(&[uid.into()])?
+            .all().await?
+            .results::<ApiTokenDb>()?
+            .into_iter()
+            .map(ApiToken::try_from)
+            .collect()
+    }
+
+    pub async fn count_by_uid(env: &Env, uid: &UserId) -> ApiResult<u32> {
+        let count = get_d1(env)?
+            .prepare(format!("SELECT COUNT(*) AS count FROM {} WHERE uid = ?1", DB_TABLE.api_token))
+            .bind(&[uid.into()])?
+            .first::<u32>(Some("count"))
+            .await?;
+
+        Ok(count.unwrap_or_default())
+    }
+
+    pub async fn insert(env: &Env, id: &str, uid: &UserId, name: &str, token_hash: &str, scopes: &[ApiTokenScope], expires_at: Option<u64>) -> ApiResult<()> {
+        let scopes = serde_json::to_string(scopes).map_err(|err| err.to_string())?;
+        let expires_at = match expires_at {
+            Some(expires_at) => (expires_at as f64).into(),
+            None => JsValue::NULL
+        };
+
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (id, uid, name, token_hash, scopes, expires_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", DB_TABLE.api_token))
+            .bind(&[id.into(), uid.into(), name.into(), token_hash.into(), scopes.into(), expires_at])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    pub async fn update_last_used(env: &Env, id: &str, last_used_at: u64) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET last_used_at = ?1 WHERE id = ?2", DB_TABLE.api_token))
+            .bind(&[(last_used_at as f64).into(), id.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // scoped to the uid, so a user can only revoke their own tokens
+    pub async fn delete(env: &Env, uid: &UserId, id: &str) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE uid = ?1 AND id = ?2", DB_TABLE.api_token))
+            .bind(&[uid.into(), id.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    pub async fn delete_all_by_uid(env: &Env, uid: &UserId) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.api_token))
+            .bind(&[uid.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
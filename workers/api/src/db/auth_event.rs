@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::account::{AuthEventInfo, AuthEventKind}, user::UserId};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+// security audit log of authentication events
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthEvent {
+    pub id: u64,
+    pub uid: Option<UserId>,
+    pub email: Option<String>,
+    pub kind: AuthEventKind,
+    pub detail: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: String,
+}
+
+impl From<AuthEvent> for AuthEventInfo {
+    fn from(event: AuthEvent) -> Self {
+        Self {
+            kind: event.kind,
+            detail: event.detail,
+            ip: event.ip,
+            user_agent: event.user_agent,
+            request_id: event.request_id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+// what's written, the rest comes from the request
+pub struct AuthEventInsert<'a> {
+    pub uid: Option<&'a UserId>,
+    pub email: Option<&'a str>,
+    pub kind: AuthEventKind,
+    pub detail: Option<&'a str>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl AuthEvent {
+    pub async fn list_by_uid(env: &Env, uid: &UserId, limit: u32) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE uid = ?1 ORDER BY id DESC LIMIT ?2", DB_TABLE.auth_event))
+            .bind(&[uid.into(), limit.into()])?
+            .all().await?
+            .results::<AuthEvent>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn list_all_by_uid(env: &Env, uid: &UserId) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE uid = ?1 ORDER BY id DESC", DB_TABLE.auth_event))
+            .bind(&[uid.into()])?
+            .all().await?
+            .results::<AuthEvent>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn insert(env: &Env, event: AuthEventInsert<'_>) -> ApiResult<()> {
+        fn nullable<T: Into<JsValue>>(value: Option<T>) -> JsValue {
+            value.map(|value| value.into()).unwrap_or(JsValue::NULL)
+        }
+
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (uid, email, kind, detail, ip, user_agent, request_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", DB_TABLE.auth_event))
+            .bind(&[
+                nullable(event.uid),
+                nullable(event.email),
+                event.kind.as_str().into(),
+                nullable(event.detail),
+                nullable(event.ip),
+                nullable(event.user_agent),
+                nullable(event.request_id),
+            ])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
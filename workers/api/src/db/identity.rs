@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::auth::AuthIdentity, backend::route::OpenIdProvider, user::UserId};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+// An external (openid / oauth2) identity, linked to an account
+// identified by the provider's stable subject id, not by email, since emails can change or be reused
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserIdentity {
+    pub provider: OpenIdProvider,
+    pub subject: String,
+    pub uid: UserId,
+    // the email the provider reported when it was linked, for display only
+    pub email: String,
+    pub created_at: String,
+}
+
+impl From<UserIdentity> for AuthIdentity {
+    fn from(identity: UserIdentity) -> Self {
+        Self {
+            provider: identity.provider,
+            subject: identity.subject,
+            email: identity.email,
+            created_at: identity.created_at,
+        }
+    }
+}
+
+impl UserIdentity {
+    pub async fn load(env: &Env, provider: &OpenIdProvider, subject: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE provider = ?1 AND subject = ?2", DB_TABLE.user_identity))
+            .bind(&[provider.as_str().into(), subject.into()])?
+            .first::<UserIdentity>(None).await
+            .map_err(|err| err.into())
+    }
+
+    pub async fn list_by_uid(env: &Env, uid: &UserId) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE uid = ?1 ORDER BY created_at", DB_TABLE.user_identity))
+            .bind(&[uid.into()])?
+            .all().await?
+            .results::<UserIdentity>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn insert(env: &Env, provider: &OpenIdProvider, subject: &str, uid: &UserId, email: &str) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (provider, subject, uid, email) VALUES (?1, ?2, ?3, ?4)", DB_TABLE.user_identity))
+            .bind(&[provider.as_str().into(), subject.into(), uid.into(), email.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // scoped to the uid, so a user can only unlink their own identities
+    pub async fn delete(env: &Env, uid: &UserId, provider: &OpenIdProvider, subject: &str) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE uid = ?1 AND provider = ?2 AND subject = ?3", DB_TABLE.user_identity))
+            .bind(&[uid.into(), provider.as_str().into(), subject.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::admin::{AdminImpersonationLogEntry, ImpersonationEvent}, user::UserId};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+// audit trail of admins impersonating users
+// deleting the target removes its entries, deleting the admin only anonymizes theirs (see UserAccount::delete)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ImpersonationLog {
+    pub id: u64,
+    pub actor_uid: UserId,
+    pub target_uid: UserId,
+    pub event: ImpersonationEvent,
+    pub route: Option<String>,
+    pub created_at: String,
+}
+
+impl From<ImpersonationLog> for AdminImpersonationLogEntry {
+    fn from(log: ImpersonationLog) -> Self {
+        Self {
+            actor_uid: log.actor_uid,
+            target_uid: log.target_uid,
+            event: log.event,
+            route: log.route,
+            created_at: log.created_at,
+        }
+    }
+}
+
+impl ImpersonationLog {
+    // stands in for an admin whose account was deleted
+    pub fn deleted_actor_uid() -> UserId {
+        UserId::new(uuid::Uuid::nil())
+    }
+
+    pub async fn list_recent(env: &Env, limit: u32) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} ORDER BY id DESC LIMIT ?1", DB_TABLE.impersonation_log))
+            .bind(&[limit.into()])?
+            .all().await?
+            .results::<ImpersonationLog>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn insert(env: &Env, actor_uid: &UserId, target_uid: &UserId, event: ImpersonationEvent, route: Option<&str>) -> ApiResult<()> {
+        let route = match route {
+            Some(route) => route.into(),
+            None => JsValue::NULL
+        };
+
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (actor_uid, target_uid, event, route) VALUES (?1, ?2, ?3, ?4)", DB_TABLE.impersonation_log))
+            .bind(&[actor_uid.into(), target_uid.into(), event.as_str().into(), route])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
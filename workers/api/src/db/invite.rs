@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::invite::InviteInfo, frontend::route::{AuthRoute as FrontendAuthRoute, Landing as FrontendLanding, Route as FrontendRoute}, user::UserId};
+use crate::{
+    config::{DB_TABLE, FRONTEND_DOMAIN, FRONTEND_ROOT_PATH},
+    prelude::*
+};
+
+// A registration invite, the code itself is what's shared with whoever is invited
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Invite {
+    pub code: String,
+    pub inviter_uid: UserId,
+    pub email: Option<String>,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub created_at: String,
+    // unix timestamp in milliseconds
+    pub expires_at: Option<u64>,
+}
+
+impl From<Invite> for InviteInfo {
+    fn from(invite: Invite) -> Self {
+        Self {
+            url: FrontendRoute::Landing(FrontendLanding::Auth(FrontendAuthRoute::RegisterInvite { invite_code: invite.code.clone() })).link(FRONTEND_DOMAIN, FRONTEND_ROOT_PATH),
+            code: invite.code,
+            inviter_uid: invite.inviter_uid,
+            email: invite.email,
+            max_uses: invite.max_uses,
+            uses: invite.uses,
+            created_at: invite.created_at,
+            expires_at: invite.expires_at,
+        }
+    }
+}
+
+impl Invite {
+    // not used up or expired, regardless of who uses it
+    pub fn is_available(&self, now: u64) -> bool {
+        self.uses < self.max_uses && self.expires_at.map_or(true, |expires_at| expires_at > now)
+    }
+
+    // whether this email could register with it right now
+    // consume() checks the same thing again, atomically
+    pub fn is_usable(&self, email: &str, now: u64) -> bool {
+        self.is_available(now) && self.email.as_ref().map_or(true, |invite_email| invite_email.eq_ignore_ascii_case(email))
+    }
+
+    pub async fn load(env: &Env, code: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE code = ?1", DB_TABLE.invite))
+            .bind(&[code.into()])?
+            .first::<Invite>(None).await
+            .map_err(|err| err.into())
+    }
+
+    pub async fn list_all(env: &Env) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} ORDER BY created_at DESC", DB_TABLE.invite))
+            .all().await?
+            .results::<Invite>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn insert(env: &Env, code: &str, inviter_uid: &UserId, email: Option<&str>, max_uses: u32, expires_at: Option<u64>) -> ApiResult<()> {
+        let email = email.map(JsValue::from).unwrap_or(JsValue::NULL);
+        let expires_at = match expires_at {
+            Some(expires_at) => (expires_at as f64).into(),
+            None => JsValue::NULL
+        };
+
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (code, inviter_uid, email, max_uses, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)", DB_TABLE.invite))
+            .bind(&[code.into(), inviter_uid.into(), email, max_uses.into(), expires_at])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // uses the invite up by one, if it's still usable by this email
+    // in a single statement, so concurrent registrations can't go over max_uses
+    pub async fn consume(env: &Env, code: &str, email: &str, now: u64) -> ApiResult<bool> {
+        let consumed = get_d1(env)?
+            .prepare(format!("UPDATE {} SET uses = uses + 1 \
+                WHERE code = ?1 AND uses < max_uses AND (expires_at IS NULL OR expires_at > ?2) AND (email IS NULL OR lower(email) = lower(?3)) \
+                RETURNING code", DB_TABLE.invite))
+            .bind(&[code.into(), (now as f64).into(), email.into()])?
+            .first::<String>(Some("code"))
+            .await?;
+
+        Ok(consumed.is_some())
+    }
+
+    pub async fn delete(env: &Env, code: &str) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE code = ?1", DB_TABLE.invite))
+            .bind(&[code.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
@@ -1 +1,12 @@
 pub mod user;
+pub mod identity;
+pub mod api_token;
+pub mod role;
+pub mod impersonation;
+pub mod auth_event;
+pub mod profile;
+pub mod invite;
+pub mod recovery_code;
+pub mod terms_acceptance;
+pub mod organization;
+pub mod sso_domain;
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::organization::{OrganizationInfo, OrganizationInviteInfo, OrganizationMemberInfo}, user::{OrganizationRole, UserId}};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    pub created_by: UserId,
+    pub created_at: String,
+}
+
+impl Organization {
+    pub async fn load(env: &Env, id: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE id = ?1", DB_TABLE.organization))
+            .bind(&[id.into()])?
+            .first::<Organization>(None).await
+            .map_err(|err| err.into())
+    }
+
+    // the creator is its first owner, in the same batch so there's never an organization without one
+    pub async fn insert_with_owner(env: &Env, id: &str, name: &str, uid: &UserId) -> ApiResult<()> {
+        let d1 = get_d1(env)?;
+
+        let statements = vec![
+            d1.prepare(format!("INSERT INTO {} (id, name, created_by) VALUES (?1, ?2, ?3)", DB_TABLE.organization))
+                .bind(&[id.into(), name.into(), uid.into()])?,
+            d1.prepare(format!("INSERT INTO {} (org_id, uid, role) VALUES (?1, ?2, ?3)", DB_TABLE.membership))
+                .bind(&[id.into(), uid.into(), OrganizationRole::Owner.as_str().into()])?,
+        ];
+
+        for res in d1.batch(statements).await? {
+            res.into_result()?;
+        }
+
+        Ok(())
+    }
+}
+
+// A user's organization, as they see it in the list
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserOrganization {
+    pub id: String,
+    pub name: String,
+    pub role: OrganizationRole,
+    pub created_at: String,
+}
+
+impl UserOrganization {
+    pub fn into_info(self, active_org_id: Option<&str>) -> OrganizationInfo {
+        OrganizationInfo {
+            active: active_org_id == Some(self.id.as_str()),
+            id: self.id,
+            name: self.name,
+            role: self.role,
+            created_at: self.created_at,
+        }
+    }
+
+    pub async fn list_by_uid(env: &Env, uid: &UserId) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT o.id, o.name, m.role, o.created_at FROM {} m JOIN {} o ON o.id = m.org_id WHERE m.uid = ?1 ORDER BY o.name", DB_TABLE.membership, DB_TABLE.organization))
+            .bind(&[uid.into()])?
+            .all().await?
+            .results::<UserOrganization>()
+            .map_err(|err| err.into())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Membership {
+    pub org_id: String,
+    pub uid: UserId,
+    pub role: OrganizationRole,
+    pub created_at: String,
+}
+
+// With the member's email, for the member list
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MembershipWithEmail {
+    pub uid: UserId,
+    pub email: String,
+    pub role: OrganizationRole,
+    pub created_at: String,
+}
+
+impl From<MembershipWithEmail> for OrganizationMemberInfo {
+    fn from(member: MembershipWithEmail) -> Self {
+        Self {
+            uid: member.uid,
+            email: member.email,
+            role: member.role,
+            created_at: member.created_at,
+        }
+    }
+}
+
+impl Membership {
+    pub async fn load(env: &Env, org_id: &str, uid: &UserId) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE org_id = ?1 AND uid = ?2", DB_TABLE.membership))
+            .bind(&[org_id.into(), uid.into()])?
+            .first::<Membership>(None).await
+            .map_err(|err| err.into())
+    }
+
+    pub async fn list_by_org(env: &Env, org_id: &str) -> ApiResult<Vec<MembershipWithEmail>> {
+        get_d1(env)?
+            .prepare(format!("SELECT m.uid, u.email, m.role, m.created_at FROM {} m JOIN {} u ON u.id = m.uid WHERE m.org_id = ?1 ORDER BY m.created_at", DB_TABLE.membership, DB_TABLE.user_account))
+            .bind(&[org_id.into()])?
+            .all().await?
+            .results::<MembershipWithEmail>()
+            .map_err(|err| err.into())
+    }
+
+    // joining again (e.g. a second invite) keeps the role they already have
+    pub async fn insert(env: &Env, org_id: &str, uid: &UserId, role: OrganizationRole) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT OR IGNORE INTO {} (org_id, uid, role) VALUES (?1, ?2, ?3)", DB_TABLE.membership))
+            .bind(&[org_id.into(), uid.into(), role.as_str().into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // an owner is only removed if another one is left, in a single statement so two owners
+    // leaving at the same time can't orphan the organization
+    // returns whether a membership was removed
+    pub async fn delete(env: &Env, org_id: &str, uid: &UserId) -> ApiResult<bool> {
+        let deleted = get_d1(env)?
+            .prepare(format!("DELETE FROM {0} WHERE org_id = ?1 AND uid = ?2 \
+                AND (role != ?3 OR (SELECT COUNT(*) FROM {0} WHERE org_id = ?1 AND role = ?3) > 1) \
+                RETURNING uid", DB_TABLE.membership))
+            .bind(&[org_id.into(), uid.into(), OrganizationRole::Owner.as_str().into()])?
+            .first::<String>(Some("uid"))
+            .await?;
+
+        Ok(deleted.is_some())
+    }
+}
+
+// A pending invite, the id is the id of the oob token in the email link
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OrganizationInvite {
+    pub id: String,
+    pub org_id: String,
+    pub email: String,
+    pub role: OrganizationRole,
+    pub invited_by: UserId,
+    // unix timestamp in milliseconds
+    pub expires_at: u64,
+    pub created_at: String,
+}
+
+impl From<OrganizationInvite> for OrganizationInviteInfo {
+    fn from(invite: OrganizationInvite) -> Self {
+        Self {
+            id: invite.id,
+            email: invite.email,
+            role: invite.role,
+            invited_by: invite.invited_by,
+            created_at: invite.created_at,
+            expires_at: invite.expires_at,
+        }
+    }
+}
+
+impl OrganizationInvite {
+    pub async fn load(env: &Env, id: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE id = ?1", DB_TABLE.organization_invite))
+            .bind(&[id.into()])?
+            .first::<OrganizationInvite>(None).await
+            .map_err(|err| err.into())
+    }
+
+    // expired ones are left out, their tokens are gone anyway
+    pub async fn list_by_org(env: &Env, org_id: &str, now: u64) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE org_id = ?1 AND expires_at > ?2 ORDER BY created_at DESC", DB_TABLE.organization_invite))
+            .bind(&[org_id.into(), (now as f64).into()])?
+            .all().await?
+            .results::<OrganizationInvite>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn insert(env: &Env, id: &str, org_id: &str, email: &str, role: OrganizationRole, invited_by: &UserId, expires_at: u64) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (id, org_id, email, role, invited_by, expires_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", DB_TABLE.organization_invite))
+            .bind(&[id.into(), org_id.into(), email.into(), role.as_str().into(), invited_by.into(), (expires_at as f64).into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // returns whether it was still there, so an invite can only be accepted (or declined) once
+    pub async fn delete(env: &Env, id: &str) -> ApiResult<bool> {
+        let deleted = get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE id = ?1 RETURNING id", DB_TABLE.organization_invite))
+            .bind(&[id.into()])?
+            .first::<String>(Some("id"))
+            .await?;
+
+        Ok(deleted.is_some())
+    }
+}
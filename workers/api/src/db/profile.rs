@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use shared::user::{UserId, UserProfile};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+// A row of the user_profile table, the api only ever sees the UserProfile part
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserProfileDb {
+    pub uid: UserId,
+    pub name: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub locale: Option<String>,
+    pub picture_url: Option<String>,
+    // once set, provider claims no longer overwrite the profile
+    pub user_edited: DbBool,
+    pub updated_at: String,
+}
+
+impl From<UserProfileDb> for UserProfile {
+    fn from(db: UserProfileDb) -> Self {
+        Self {
+            name: db.name,
+            given_name: db.given_name,
+            family_name: db.family_name,
+            locale: db.locale,
+            picture_url: db.picture_url,
+        }
+    }
+}
+
+impl UserProfileDb {
+    pub async fn load(env: &Env, uid: &UserId) -> ApiResult<Option<UserProfile>> {
+        let profile = get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE uid = ?1", DB_TABLE.user_profile))
+            .bind(&[uid.into()])?
+            .first::<UserProfileDb>(None).await?;
+
+        Ok(profile.map(|x| x.into()))
+    }
+
+    // from the openid provider's claims, on registration and every signin after
+    // a profile the user has edited is left alone
+    pub async fn upsert_from_provider(env: &Env, uid: &UserId, profile: &UserProfile) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (uid, name, given_name, family_name, locale, picture_url) VALUES (?1, ?2, ?3, ?4, ?5, ?6) \
+                ON CONFLICT (uid) DO UPDATE SET name = excluded.name, given_name = excluded.given_name, family_name = excluded.family_name, \
+                locale = excluded.locale, picture_url = excluded.picture_url, updated_at = CURRENT_TIMESTAMP \
+                WHERE user_edited = FALSE", DB_TABLE.user_profile))
+            .bind(&profile_values(uid, profile))?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    pub async fn update_by_user(env: &Env, uid: &UserId, profile: &UserProfile) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (uid, name, given_name, family_name, locale, picture_url, user_edited) VALUES (?1, ?2, ?3, ?4, ?5, ?6, TRUE) \
+                ON CONFLICT (uid) DO UPDATE SET name = excluded.name, given_name = excluded.given_name, family_name = excluded.family_name, \
+                locale = excluded.locale, picture_url = excluded.picture_url, user_edited = TRUE, updated_at = CURRENT_TIMESTAMP", DB_TABLE.user_profile))
+            .bind(&profile_values(uid, profile))?
+            .run()
+            .await?
+            .into_result()
+    }
+}
+
+fn profile_values(uid: &UserId, profile: &UserProfile) -> [JsValue; 6] {
+    fn nullable(value: &Option<String>) -> JsValue {
+        value.as_deref().map(JsValue::from).unwrap_or(JsValue::NULL)
+    }
+
+    [
+        uid.into(),
+        nullable(&profile.name),
+        nullable(&profile.given_name),
+        nullable(&profile.family_name),
+        nullable(&profile.locale),
+        nullable(&profile.picture_url),
+    ]
+}
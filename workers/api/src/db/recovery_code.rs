@@ -0,0 +1,56 @@
+use serde::Deserialize;
+use shared::user::UserId;
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+#[derive(Deserialize, Debug)]
+pub struct RecoveryCodeStatusDb {
+    pub remaining: u32,
+    pub created_at: Option<String>,
+}
+
+pub struct RecoveryCode { }
+
+impl RecoveryCode {
+    pub async fn status(env: &Env, uid: &UserId) -> ApiResult<RecoveryCodeStatusDb> {
+        let status = get_d1(env)?
+            .prepare(format!("SELECT COUNT(*) AS remaining, MAX(created_at) AS created_at FROM {} WHERE uid = ?1", DB_TABLE.recovery_code))
+            .bind(&[uid.into()])?
+            .first::<RecoveryCodeStatusDb>(None)
+            .await?;
+
+        Ok(status.unwrap_or(RecoveryCodeStatusDb { remaining: 0, created_at: None }))
+    }
+
+    // the old set is gone in the same batch, so there's never a mix of both
+    pub async fn replace_all(env: &Env, uid: &UserId, code_hashes: &[String]) -> ApiResult<()> {
+        let d1 = get_d1(env)?;
+
+        let mut statements = vec![
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.recovery_code)).bind(&[uid.into()])?,
+        ];
+        for code_hash in code_hashes {
+            statements.push(d1.prepare(format!("INSERT INTO {} (uid, code_hash) VALUES (?1, ?2)", DB_TABLE.recovery_code)).bind(&[uid.into(), code_hash.into()])?);
+        }
+
+        for res in d1.batch(statements).await? {
+            res.into_result()?;
+        }
+
+        Ok(())
+    }
+
+    // uses the code up, returns whether it was there to use
+    // a single statement, so the same code can't be used twice concurrently
+    pub async fn consume(env: &Env, uid: &UserId, code_hash: &str) -> ApiResult<bool> {
+        let deleted = get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE uid = ?1 AND code_hash = ?2 RETURNING uid", DB_TABLE.recovery_code))
+            .bind(&[uid.into(), code_hash.into()])?
+            .first::<String>(Some("uid"))
+            .await?;
+
+        Ok(deleted.is_some())
+    }
+}
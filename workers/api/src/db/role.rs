@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::admin::AdminRoleInfo, user::{Role, UserId}};
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserRole {
+    pub uid: UserId,
+    pub role: Role,
+    pub created_at: String,
+}
+
+impl From<UserRole> for AdminRoleInfo {
+    fn from(user_role: UserRole) -> Self {
+        Self {
+            uid: user_role.uid,
+            role: user_role.role,
+            created_at: user_role.created_at,
+        }
+    }
+}
+
+impl UserRole {
+    pub async fn list_by_uid(env: &Env, uid: &UserId) -> ApiResult<Vec<Role>> {
+        let roles = get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE uid = ?1", DB_TABLE.user_role))
+            .bind(&[uid.into()])?
+            .all().await?
+            .results::<UserRole>()?;
+
+        Ok(roles.into_iter().map(|x| x.role).collect())
+    }
+
+    pub async fn list_all(env: &Env) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} ORDER BY created_at", DB_TABLE.user_role))
+            .all().await?
+            .results::<UserRole>()
+            .map_err(|err| err.into())
+    }
+
+    // granting a role the user already has is a no-op
+    pub async fn insert(env: &Env, uid: &UserId, role: Role) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT OR IGNORE INTO {} (uid, role) VALUES (?1, ?2)", DB_TABLE.user_role))
+            .bind(&[uid.into(), role.as_str().into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    pub async fn delete(env: &Env, uid: &UserId, role: Role) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE uid = ?1 AND role = ?2", DB_TABLE.user_role))
+            .bind(&[uid.into(), role.as_str().into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
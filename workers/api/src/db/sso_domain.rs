@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use shared::{api::organization::SsoDomainInfo, backend::route::OpenIdProvider};
+use crate::{
+    config::{DB_TABLE, SSO_DOMAIN_TXT_RECORD_PREFIX, SSO_DOMAIN_TXT_VALUE_PREFIX},
+    prelude::*
+};
+
+// An email domain claimed by an organization
+// it's only enforced once verified, see auth::sso
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SsoDomain {
+    pub domain: String,
+    pub org_id: String,
+    pub provider: OpenIdProvider,
+    pub verification_token: String,
+    // unix timestamp in milliseconds
+    pub verified_at: Option<u64>,
+    pub created_at: String,
+}
+
+impl SsoDomain {
+    pub fn txt_record_name(&self) -> String {
+        format!("{SSO_DOMAIN_TXT_RECORD_PREFIX}.{}", self.domain)
+    }
+
+    pub fn txt_record_value(&self) -> String {
+        format!("{SSO_DOMAIN_TXT_VALUE_PREFIX}{}", self.verification_token)
+    }
+
+    // the claim that's enforced for a domain, if any
+    pub async fn load_verified(env: &Env, domain: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE domain = ?1 AND verified_at IS NOT NULL", DB_TABLE.sso_domain))
+            .bind(&[domain.into()])?
+            .first::<SsoDomain>(None).await
+            .map_err(|err| err.into())
+    }
+
+    pub async fn load(env: &Env, org_id: &str, domain: &str) -> ApiResult<Option<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE org_id = ?1 AND domain = ?2", DB_TABLE.sso_domain))
+            .bind(&[org_id.into(), domain.into()])?
+            .first::<SsoDomain>(None).await
+            .map_err(|err| err.into())
+    }
+
+    pub async fn list_by_org(env: &Env, org_id: &str) -> ApiResult<Vec<Self>> {
+        get_d1(env)?
+            .prepare(format!("SELECT * FROM {} WHERE org_id = ?1 ORDER BY domain", DB_TABLE.sso_domain))
+            .bind(&[org_id.into()])?
+            .all().await?
+            .results::<SsoDomain>()
+            .map_err(|err| err.into())
+    }
+
+    pub async fn insert(env: &Env, domain: &str, org_id: &str, provider: &OpenIdProvider, verification_token: &str) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT INTO {} (domain, org_id, provider, verification_token) VALUES (?1, ?2, ?3, ?4)", DB_TABLE.sso_domain))
+            .bind(&[domain.into(), org_id.into(), provider.as_str().into(), verification_token.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // the unique index makes this fail if another organization verified the domain in the meantime
+    pub async fn set_verified(env: &Env, org_id: &str, domain: &str, verified_at: u64) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET verified_at = ?3 WHERE org_id = ?1 AND domain = ?2", DB_TABLE.sso_domain))
+            .bind(&[org_id.into(), domain.into(), (verified_at as f64).into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // returns whether there was such a claim
+    pub async fn delete(env: &Env, org_id: &str, domain: &str) -> ApiResult<bool> {
+        let deleted = get_d1(env)?
+            .prepare(format!("DELETE FROM {} WHERE org_id = ?1 AND domain = ?2 RETURNING domain", DB_TABLE.sso_domain))
+            .bind(&[org_id.into(), domain.into()])?
+            .first::<String>(Some("domain"))
+            .await?;
+
+        Ok(deleted.is_some())
+    }
+}
+
+impl From<SsoDomain> for SsoDomainInfo {
+    fn from(sso_domain: SsoDomain) -> Self {
+        Self {
+            txt_record_name: sso_domain.txt_record_name(),
+            txt_record_value: sso_domain.txt_record_value(),
+            domain: sso_domain.domain,
+            provider: sso_domain.provider,
+            verified_at: sso_domain.verified_at,
+            created_at: sso_domain.created_at,
+        }
+    }
+}
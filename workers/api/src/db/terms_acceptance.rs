@@ -0,0 +1,30 @@
+use shared::user::UserId;
+use crate::{
+    config::DB_TABLE,
+    prelude::*
+};
+
+pub struct TermsAcceptance { }
+
+impl TermsAcceptance {
+    // the latest version the user accepted, None if they never accepted any
+    pub async fn latest_version(env: &Env, uid: &UserId) -> ApiResult<Option<u32>> {
+        let version = get_d1(env)?
+            .prepare(format!("SELECT MAX(version) AS version FROM {} WHERE uid = ?1", DB_TABLE.terms_acceptance))
+            .bind(&[uid.into()])?
+            .first::<Option<u32>>(Some("version"))
+            .await?;
+
+        Ok(version.flatten())
+    }
+
+    // accepting the same version again keeps the first record, that's the one that counts
+    pub async fn insert(env: &Env, uid: &UserId, version: u32, ip: Option<&str>) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("INSERT OR IGNORE INTO {} (uid, version, ip) VALUES (?1, ?2, ?3)", DB_TABLE.terms_acceptance))
+            .bind(&[uid.into(), version.into(), ip.map(|ip| ip.into()).unwrap_or(JsValue::NULL)])?
+            .run()
+            .await?
+            .into_result()
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
-use shared::user::UserId;
+use shared::user::{OrganizationRole, UserId};
 use crate::{
     config::DB_TABLE,
+    db::impersonation::ImpersonationLog,
     prelude::*
 };
 
@@ -9,20 +10,31 @@ use crate::{
 struct UserAccountDb{
     pub id: UserId,
     pub password: String,
+    pub client_salt: Option<String>,
     pub email: String,
     pub email_verified: DbBool,
     pub user_token: String,
     pub created_at: String,
+    pub delete_after: Option<u64>,
+    pub active_org_id: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct UserAccount{
     pub id: UserId,
     pub password: String,
+    // the salt the client derives the password hash with, see PasswordParams
+    // None for accounts from before per-account salts, until their next signin
+    pub client_salt: Option<String>,
     pub email: String,
     pub email_verified: bool,
     pub user_token: String,
     pub created_at: String,
+    // unix timestamp in milliseconds, set while an account deletion is pending
+    pub delete_after: Option<u64>,
+    // the organization requests act on, see ActiveOrganization
+    // not cleared when the membership goes away, it's checked against membership on every request instead
+    pub active_org_id: Option<String>,
 }
 
 impl From<UserAccountDb> for UserAccount {
@@ -30,10 +42,13 @@ impl From<UserAccountDb> for UserAccount {
         Self {
             id: db.id,
             password: db.password,
+            client_salt: db.client_salt,
             email: db.email,
             email_verified: db.email_verified.into(),
             user_token: db.user_token,
             created_at: db.created_at,
+            delete_after: db.delete_after,
+            active_org_id: db.active_org_id,
         }
     }
 }
@@ -68,10 +83,10 @@ impl UserAccount {
         Ok(exists)
     }
 
-    pub async fn insert(env: &Env, uid: &UserId, password: &str, email: &str, user_token: &str) -> ApiResult<()> {
+    pub async fn insert(env: &Env, uid: &UserId, password: &str, client_salt: &str, email: &str, user_token: &str) -> ApiResult<()> {
         get_d1(env)?
-            .prepare(format!("INSERT INTO {} (id, password, email, user_token) VALUES (?1, ?2, ?3, ?4)", DB_TABLE.user_account))
-            .bind(&[uid.into(), password.into(), email.into(), user_token.into()])?
+            .prepare(format!("INSERT INTO {} (id, password, client_salt, email, user_token) VALUES (?1, ?2, ?3, ?4, ?5)", DB_TABLE.user_account))
+            .bind(&[uid.into(), password.into(), client_salt.into(), email.into(), user_token.into()])?
             .run()
             .await?
             .into_result()
@@ -88,13 +103,114 @@ impl UserAccount {
             .into_result()
     }
 
-    pub async fn reset_password(env: &Env, uid: &UserId, password: &str, user_token: &str) -> ApiResult<()> {
+    pub async fn reset_password(env: &Env, uid: &UserId, password: &str, client_salt: &str, user_token: &str) -> ApiResult<()> {
         get_d1(env)?
-            .prepare(format!("UPDATE {} SET password = ?1, user_token = ?2 WHERE id = ?3", DB_TABLE.user_account))
-            .bind(&[password.into(), user_token.into(), uid.into()])?
+            .prepare(format!("UPDATE {} SET password = ?1, client_salt = ?2, user_token = ?3 WHERE id = ?4", DB_TABLE.user_account))
+            .bind(&[password.into(), client_salt.into(), user_token.into(), uid.into()])?
             .run()
             .await?
             .into_result()
     }
 
-}
\ No newline at end of file
+    // same password, just re-hashed (maybe with a new client salt), so nobody is signed out
+    pub async fn update_password(env: &Env, uid: &UserId, password: &str, client_salt: Option<&str>) -> ApiResult<()> {
+        let client_salt = client_salt.map(JsValue::from).unwrap_or(JsValue::NULL);
+
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET password = ?1, client_salt = ?2 WHERE id = ?3", DB_TABLE.user_account))
+            .bind(&[password.into(), client_salt, uid.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // invalidates every signin and oob token for the user, a.k.a. "sign out everywhere"
+    pub async fn rotate_user_token(env: &Env, uid: &UserId) -> ApiResult<String> {
+        let user_token = uuid::Uuid::now_v7().as_simple().to_string();
+
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET user_token = ?1 WHERE id = ?2", DB_TABLE.user_account))
+            .bind(&[user_token.as_str().into(), uid.into()])?
+            .run()
+            .await?
+            .into_result()?;
+
+        Ok(user_token)
+    }
+
+    pub async fn schedule_deletion(env: &Env, uid: &UserId, delete_after: u64) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET delete_after = ?1 WHERE id = ?2", DB_TABLE.user_account))
+            .bind(&[(delete_after as f64).into(), uid.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    pub async fn cancel_deletion(env: &Env, uid: &UserId) -> ApiResult<()> {
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET delete_after = NULL WHERE id = ?1", DB_TABLE.user_account))
+            .bind(&[uid.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    pub async fn set_active_org(env: &Env, uid: &UserId, org_id: Option<&str>) -> ApiResult<()> {
+        let org_id = org_id.map(JsValue::from).unwrap_or(JsValue::NULL);
+
+        get_d1(env)?
+            .prepare(format!("UPDATE {} SET active_org_id = ?1 WHERE id = ?2", DB_TABLE.user_account))
+            .bind(&[org_id, uid.into()])?
+            .run()
+            .await?
+            .into_result()
+    }
+
+    // removes the account and everything that belongs to it
+    // signin tokens can't be enumerated, but they fail validation once the account is gone
+    // (the user token was already rotated when the deletion was requested)
+    // and clean themselves up via their own alarms
+    pub async fn delete(env: &Env, uid: &UserId) -> ApiResult<()> {
+        let d1 = get_d1(env)?;
+
+        let statements = vec![
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.user_identity)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.api_token)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.user_role)).bind(&[uid.into()])?,
+            // by email too, to catch events that were logged without a uid
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1 OR email = (SELECT email FROM {} WHERE id = ?1)", DB_TABLE.auth_event, DB_TABLE.user_account)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.user_profile)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE inviter_uid = ?1", DB_TABLE.invite)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.recovery_code)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.terms_acceptance)).bind(&[uid.into()])?,
+            // what was done to this user goes with them, but what this user did as an admin stays in the audit trail
+            // just no longer pointing at them
+            d1.prepare(format!("DELETE FROM {} WHERE target_uid = ?1", DB_TABLE.impersonation_log)).bind(&[uid.into()])?,
+            d1.prepare(format!("UPDATE {} SET actor_uid = ?2 WHERE actor_uid = ?1", DB_TABLE.impersonation_log)).bind(&[uid.into(), ImpersonationLog::deleted_actor_uid().into()])?,
+            // organizations stay as long as anyone else is in them, otherwise they go too
+            // one this user is the last owner of gets a new owner first, the longest-standing admin (or member if there's none)
+            // so it's never left without anyone who can manage it (or its sso domains)
+            d1.prepare(format!("UPDATE {0} SET role = ?2 WHERE (org_id, uid) IN ( \
+                SELECT m.org_id, (SELECT o.uid FROM {0} o WHERE o.org_id = m.org_id AND o.uid != ?1 \
+                    ORDER BY CASE o.role WHEN ?3 THEN 0 ELSE 1 END, o.created_at, o.uid LIMIT 1) \
+                FROM {0} m \
+                WHERE m.uid = ?1 AND m.role = ?2 \
+                AND NOT EXISTS (SELECT 1 FROM {0} x WHERE x.org_id = m.org_id AND x.uid != ?1 AND x.role = ?2))", DB_TABLE.membership))
+                .bind(&[uid.into(), OrganizationRole::Owner.as_str().into(), OrganizationRole::Admin.as_str().into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE uid = ?1", DB_TABLE.membership)).bind(&[uid.into()])?,
+            // invites to the deleted email would let whoever registers it next walk into the organization
+            d1.prepare(format!("DELETE FROM {} WHERE lower(email) = lower((SELECT email FROM {} WHERE id = ?1))", DB_TABLE.organization_invite, DB_TABLE.user_account)).bind(&[uid.into()])?,
+            d1.prepare(format!("DELETE FROM {} WHERE org_id NOT IN (SELECT org_id FROM {})", DB_TABLE.organization_invite, DB_TABLE.membership)),
+            d1.prepare(format!("DELETE FROM {} WHERE org_id NOT IN (SELECT org_id FROM {})", DB_TABLE.sso_domain, DB_TABLE.membership)),
+            d1.prepare(format!("DELETE FROM {} WHERE id NOT IN (SELECT org_id FROM {})", DB_TABLE.organization, DB_TABLE.membership)),
+            d1.prepare(format!("DELETE FROM {} WHERE id = ?1", DB_TABLE.user_account)).bind(&[uid.into()])?,
+        ];
+
+        for res in d1.batch(statements).await? {
+            res.into_result()?;
+        }
+
+        Ok(())
+    }
+}
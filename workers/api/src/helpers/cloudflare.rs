@@ -82,3 +82,8 @@ pub fn get_secret(env: &Env, key: &str) -> worker::Result<String> {
             err
         })
 }
+
+// unix timestamp in milliseconds
+pub fn now_ms() -> u64 {
+    worker::Date::now().as_millis()
+}
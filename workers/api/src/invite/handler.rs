@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use shared::{api::{invite::{InviteCreate, InviteCreateRequest, InviteCreateResponse, InviteList, InviteListResponse, InviteRevoke, InviteRevokeRequest}, ApiBoth, ApiReq, ApiRes}, backend::result::ApiResult};
+use crate::{
+    api_ext::{ApiBothExt, ApiReqExt, ApiResExt}, config::MS_PER_DAY, db::invite::Invite, helpers::now_ms, ApiContext
+};
+use super::generate_invite_code;
+
+#[async_trait(?Send)]
+impl ApiBothExt for InviteCreate {
+    type Req = <InviteCreate as ApiBoth>::Req;
+    type Res = <InviteCreate as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: InviteCreateRequest) -> ApiResult<InviteCreateResponse> {
+        let InviteCreateRequest { email, max_uses, expires_in_days } = data;
+
+        if max_uses == 0 {
+            return Err("an invite needs at least one use".into());
+        }
+        let email = email.as_deref().map(str::trim).filter(|email| !email.is_empty());
+        if email.is_some_and(|email| !email.contains('@')) {
+            return Err("invalid invite email".into());
+        }
+
+        let code = generate_invite_code();
+        let expires_at = expires_in_days.map(|days| now_ms() + (days as u64 * MS_PER_DAY));
+
+        Invite::insert(&ctx.env, &code, &ctx.uid_unchecked(), email, max_uses, expires_at).await?;
+        worker::console_log!("{} created an invite for {} use(s)", ctx.uid_unchecked(), max_uses);
+
+        let info = Invite::load(&ctx.env, &code).await?.ok_or("invite was not inserted")?.into();
+
+        Ok(InviteCreateResponse {
+            info
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for InviteList {
+    type Res = <InviteList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<InviteListResponse> {
+        let invites = Invite::list_all(&ctx.env).await?;
+
+        Ok(InviteListResponse {
+            invites: invites.into_iter().map(|invite| invite.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for InviteRevoke {
+    type Req = <InviteRevoke as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: InviteRevokeRequest) -> ApiResult<()> {
+        Invite::delete(&ctx.env, &data.code).await
+    }
+}
@@ -0,0 +1,93 @@
+mod handler;
+
+use base64::Engine;
+use rand::Rng;
+use serde::Deserialize;
+use shared::user::UserId;
+use crate::{config::INVITE_CODE_LENGTH, db::{invite::Invite, user::UserAccount}, helpers::now_ms, prelude::*};
+
+// Who may register a new account, read from the REGISTRATION_POLICY var (a JSON object, see wrangler.toml)
+// a valid invite gets around everything but Closed, for both password and openid registrations
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RegistrationPolicy {
+    #[default]
+    Open,
+    InviteOnly,
+    // no new accounts at all, not even with an invite
+    Closed,
+    // only emails on these domains (e.g. "example.com", exact match), or with an invite
+    DomainAllowList {
+        domains: Vec<String>,
+    },
+}
+
+impl RegistrationPolicy {
+    const ENV_VAR: &'static str = "REGISTRATION_POLICY";
+
+    pub fn load(env: &Env) -> ApiResult<Self> {
+        match env.var(Self::ENV_VAR) {
+            Ok(var) => serde_json::from_str(&var.to_string()).map_err(|err| format!("invalid {}: {}", Self::ENV_VAR, err).into()),
+            // not configured means anyone can register
+            Err(_) => Ok(Self::default())
+        }
+    }
+}
+
+// Proof that an email may register, from check_registration()
+// the invite (if any) is only used up by consume(), right after the account is created
+pub struct RegistrationAllowed {
+    invite_code: Option<String>,
+}
+
+impl RegistrationAllowed {
+    // uid is the account that was just created for it
+    // so a failed insert (e.g. the email was taken in the meantime) doesn't use up the invite
+    pub async fn consume(self, env: &Env, uid: &UserId, email: &str) -> ApiResult<()> {
+        if let Some(invite_code) = self.invite_code {
+            // it may have been used up since it was checked, then the account can't stay
+            if !Invite::consume(env, &invite_code, email, now_ms()).await? {
+                UserAccount::delete(env, uid).await?;
+                return Err(AuthError::InvalidInvite.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// doesn't change anything, so it can also be used to fail early (e.g. when an openid session is queried)
+pub async fn check_registration(env: &Env, email: &str, invite_code: Option<&str>) -> ApiResult<RegistrationAllowed> {
+    let policy = RegistrationPolicy::load(env)?;
+
+    if let RegistrationPolicy::Closed = policy {
+        return Err(AuthError::RegistrationClosed.into());
+    }
+
+    // an invite that was given must be valid, even if the policy wouldn't need it
+    if let Some(invite_code) = invite_code {
+        return match Invite::load(env, invite_code).await? {
+            Some(invite) if invite.is_usable(email, now_ms()) => Ok(RegistrationAllowed { invite_code: Some(invite.code) }),
+            _ => Err(AuthError::InvalidInvite.into())
+        };
+    }
+
+    match policy {
+        RegistrationPolicy::Open => {},
+        RegistrationPolicy::InviteOnly | RegistrationPolicy::Closed => {
+            return Err(AuthError::InviteRequired.into());
+        },
+        RegistrationPolicy::DomainAllowList { domains } => {
+            let domain = email.rsplit_once('@').map(|(_, domain)| domain).unwrap_or_default();
+            if !domains.iter().any(|allowed| allowed.eq_ignore_ascii_case(domain)) {
+                return Err(AuthError::EmailDomainNotAllowed.into());
+            }
+        }
+    }
+
+    Ok(RegistrationAllowed { invite_code: None })
+}
+
+pub fn generate_invite_code() -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; INVITE_CODE_LENGTH]>())
+}
@@ -1,4 +1,10 @@
 mod auth;
+mod account;
+mod admin;
+mod api_token;
+mod invite;
+mod recovery_code;
+mod organization;
 mod context;
 mod config;
 mod prelude;
@@ -9,10 +15,10 @@ mod api_ext;
 mod helpers;
 mod mailer;
 
-use config::ALLOWED_ORIGINS;
+use config::{ALLOWED_ORIGINS, API_ROOT_PATH, SECURITY_HEADERS};
 use route::handle_route;
 // use route::handle_route;
-use shared::auth::{AUTH_TOKEN_ID_NAME, AUTH_TOKEN_KEY_NAME};
+use shared::{auth::{AUTH_IMPERSONATION_TOKEN_ID_NAME, AUTH_TOKEN_ID_NAME, AUTH_TOKEN_KEY_NAME}, backend::route::Route};
 use worker::{
     Env,
     event,
@@ -29,9 +35,11 @@ async fn main(req: Request, env: Env, ctx: Context) -> worker::Result<Response>
         None => req.headers().get("referrer")?
     };
 
+    let route = Route::try_from_url(&req.url(), API_ROOT_PATH);
+
     // early-exit for CORS options
     if req.method().to_uppercase() == "OPTIONS" {
-        Ok(apply_cors(origin, Response::new_empty()))
+        Ok(apply_security_headers(route.as_ref(), apply_cors(origin, Response::new_empty())))
     } else {
         let res = match handle_route(req, env, ctx).await {
             Ok(res) => res,
@@ -40,8 +48,24 @@ async fn main(req: Request, env: Env, ctx: Context) -> worker::Result<Response>
                 res.into()
             }
         };
-        Ok(apply_cors(origin, res))
+        Ok(apply_security_headers(route.as_ref(), apply_cors(origin, res)))
+    }
+}
+
+// the defaults, then whatever the route replaces or adds, and caching which depends on the route
+// unknown routes are not-found responses, nothing worth caching there either
+fn apply_security_headers(route: Option<&Route>, res: Response) -> Response {
+    let route_headers = route.map(|route| route.security_headers()).unwrap_or_default();
+
+    for (name, value) in SECURITY_HEADERS.iter().chain(route_headers) {
+        res.headers().set(name, value).unwrap();
     }
+
+    if !route.map(|route| route.allows_caching()).unwrap_or(false) {
+        res.headers().set("Cache-Control", "no-store").unwrap();
+    }
+
+    res
 }
 
 fn apply_cors(origin: Option<String>, res: Response) -> Response {
@@ -55,7 +79,7 @@ fn apply_cors(origin: Option<String>, res: Response) -> Response {
     res.headers().set("Access-Control-Allow-Credentials", "true").unwrap();
     res.headers().set("Access-Control-Max-Age", "86400").unwrap();
     res.headers().set("Access-Control-Allow-Methods", "GET, HEAD, POST, OPTIONS").unwrap();
-    res.headers().set("Access-Control-Allow-Headers", &format!("Content-Type, {AUTH_TOKEN_KEY_NAME}, {AUTH_TOKEN_ID_NAME}")).unwrap();
+    res.headers().set("Access-Control-Allow-Headers", &format!("Content-Type, {AUTH_TOKEN_KEY_NAME}, {AUTH_TOKEN_ID_NAME}, {AUTH_IMPERSONATION_TOKEN_ID_NAME}")).unwrap();
 
     res
 }
@@ -1,6 +1,6 @@
 use crate::{config::{DKIM_DOMAIN, DKIM_SELECTOR, FRONTEND_DOMAIN, FRONTEND_ROOT_PATH, MAILER_ADDRESS, MAILER_NAME, SEND_EMAIL}, context::ContentLanguage, prelude::*};
 use serde::Serialize;
-use shared::frontend::route::{Route as FrontendRoute, Landing as FrontendLanding, AuthRoute as FrontendAuthRoute};
+use shared::frontend::route::{Route as FrontendRoute, Landing as FrontendLanding, AuthRoute as FrontendAuthRoute, Dashboard as FrontendDashboard};
 use web_sys::{js_sys, Headers, RequestInit, WorkerGlobalScope};
 use worker::{console_log, console_warn};
 
@@ -8,18 +8,31 @@ pub enum MailerKind {
     EmailVerification {
         oob_token_id: String,
         oob_token_key: String,
+        code: String,
     },
     PasswordReset {
         oob_token_id: String,
         oob_token_key: String,
-    }
+    },
+    AccountLocked {
+        lockout_ms: u64,
+    },
+    MagicLink {
+        oob_token_id: String,
+        oob_token_key: String,
+    },
+    OrganizationInvite {
+        organization_name: String,
+        oob_token_id: String,
+        oob_token_key: String,
+    },
 }
 
 
 pub async fn send(ctx: &ApiContext, address: &str, kind: MailerKind) -> ApiResult<()> {
 
     let (subject, content) = match kind {
-        MailerKind::EmailVerification { oob_token_id, oob_token_key } => {
+        MailerKind::EmailVerification { oob_token_id, oob_token_key, code } => {
             // and the *frontend* url to email
             let oob_url = FrontendRoute::Landing(FrontendLanding::Auth(FrontendAuthRoute::VerifyEmailConfirm{
                 oob_token_id, 
@@ -32,8 +45,8 @@ pub async fn send(ctx: &ApiContext, address: &str, kind: MailerKind) -> ApiResul
             };
 
             let content = match ctx.lang {
-                ContentLanguage::English => format!("Click here to verify your email: {}", oob_url),
-                ContentLanguage::Hebrew => format!("לחץ כאן כדי לאמת את האימייל שלך: {}", oob_url)
+                ContentLanguage::English => format!("Click here to verify your email: {}. Or, enter this code where you signed up: {}", oob_url, code),
+                ContentLanguage::Hebrew => format!("לחץ כאן כדי לאמת את האימייל שלך: {}. או, הזן את הקוד הזה היכן שנרשמת: {}", oob_url, code)
             };
 
             (subject, content)
@@ -56,7 +69,62 @@ pub async fn send(ctx: &ApiContext, address: &str, kind: MailerKind) -> ApiResul
             };
 
             (subject, content)
-        }
+        },
+        MailerKind::AccountLocked { lockout_ms } => {
+            // the signin page is where they can also start a password reset
+            let signin_url = FrontendRoute::Landing(FrontendLanding::Auth(FrontendAuthRoute::Signin)).link(FRONTEND_DOMAIN, FRONTEND_ROOT_PATH);
+            let minutes = lockout_ms.div_ceil(1000 * 60);
+
+            let subject = match ctx.lang {
+                ContentLanguage::English => "Signin temporarily locked".to_string(),
+                ContentLanguage::Hebrew => "ההתחברות נחסמה זמנית".to_string()
+            };
+
+            let content = match ctx.lang {
+                ContentLanguage::English => format!("There were too many failed signin attempts on your account, so signin is locked for {} minutes. If this wasn't you, consider resetting your password: {}", minutes, signin_url),
+                ContentLanguage::Hebrew => format!("היו יותר מדי ניסיונות התחברות כושלים לחשבון שלך, ולכן ההתחברות נחסמה ל-{} דקות. אם זה לא היית אתה, מומלץ לאפס את הסיסמה: {}", minutes, signin_url)
+            };
+
+            (subject, content)
+        },
+        MailerKind::MagicLink { oob_token_id, oob_token_key } => {
+            // and the *frontend* url to email
+            let oob_url = FrontendRoute::Landing(FrontendLanding::Auth(FrontendAuthRoute::MagicLinkConfirm {
+                oob_token_id,
+                oob_token_key
+            })).link(FRONTEND_DOMAIN, FRONTEND_ROOT_PATH);
+
+            let subject = match ctx.lang {
+                ContentLanguage::English => "Your signin link".to_string(),
+                ContentLanguage::Hebrew => "קישור ההתחברות שלך".to_string()
+            };
+
+            let content = match ctx.lang {
+                ContentLanguage::English => format!("Click here to sign in, the link can only be used once: {}. If you didn't ask for this, you can safely ignore it", oob_url),
+                ContentLanguage::Hebrew => format!("לחץ כאן כדי להתחבר, ניתן להשתמש בקישור פעם אחת בלבד: {}. אם לא ביקשת זאת, ניתן להתעלם מהודעה זו", oob_url)
+            };
+
+            (subject, content)
+        },
+        MailerKind::OrganizationInvite { organization_name, oob_token_id, oob_token_key } => {
+            // a dashboard page, so they sign in (or register) with this email first
+            let oob_url = FrontendRoute::Dashboard(FrontendDashboard::OrganizationInvite {
+                oob_token_id,
+                oob_token_key
+            }).link(FRONTEND_DOMAIN, FRONTEND_ROOT_PATH);
+
+            let subject = match ctx.lang {
+                ContentLanguage::English => format!("You're invited to join {}", organization_name),
+                ContentLanguage::Hebrew => format!("הוזמנת להצטרף ל-{}", organization_name)
+            };
+
+            let content = match ctx.lang {
+                ContentLanguage::English => format!("You've been invited to join {}. Click here to accept or decline, signed in with this email address: {}", organization_name, oob_url),
+                ContentLanguage::Hebrew => format!("הוזמנת להצטרף ל-{}. לחץ כאן כדי לאשר או לדחות, כשאתה מחובר עם כתובת המייל הזו: {}", organization_name, oob_url)
+            };
+
+            (subject, content)
+        },
     };
 
     if !SEND_EMAIL {
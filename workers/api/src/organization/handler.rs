@@ -0,0 +1,365 @@
+use async_trait::async_trait;
+use shared::{api::{organization::{OrganizationCreate, OrganizationCreateRequest, OrganizationCreateResponse, OrganizationInviteAccept, OrganizationInviteCheck, OrganizationInviteCheckResponse, OrganizationInviteCreate, OrganizationInviteCreateRequest, OrganizationInviteCreateResponse, OrganizationInviteDecline, OrganizationInviteList, OrganizationInviteListResponse, OrganizationInviteRevoke, OrganizationInviteRevokeRequest, OrganizationInviteTokenRequest, OrganizationList, OrganizationListResponse, OrganizationMemberList, OrganizationMemberListResponse, OrganizationMemberRemove, OrganizationMemberRemoveRequest, OrganizationSsoDomainAdd, OrganizationSsoDomainAddRequest, OrganizationSsoDomainList, OrganizationSsoDomainListResponse, OrganizationSsoDomainRemove, OrganizationSsoDomainRequest, OrganizationSsoDomainResponse, OrganizationSsoDomainVerify, OrganizationSwitch, OrganizationSwitchRequest}, ApiBoth, ApiReq, ApiRes}, backend::result::{ApiResult, AuthError}, user::OrganizationRole};
+use crate::{
+    api_ext::{ApiBothExt, ApiReqExt, ApiResExt}, auth::{check_sso_provider, durable_objects::token::{AuthTokenAfterValidation, AuthTokenDO, AuthTokenKind}, generate_sso_verification_token, has_txt_record, normalize_domain}, config::{ORGANIZATION_INVITE_EXPIRES, ORGANIZATION_NAME_MAX_LENGTH}, db::{organization::{Membership, Organization, OrganizationInvite, UserOrganization}, sso_domain::SsoDomain, user::UserAccount}, helpers::now_ms, mailer::{self, MailerKind}, ApiContext
+};
+
+#[async_trait(?Send)]
+impl ApiBothExt for OrganizationCreate {
+    type Req = <OrganizationCreate as ApiBoth>::Req;
+    type Res = <OrganizationCreate as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationCreateRequest) -> ApiResult<OrganizationCreateResponse> {
+        let name = data.name.trim();
+        if name.is_empty() || name.chars().count() > ORGANIZATION_NAME_MAX_LENGTH {
+            return Err(format!("organization name must be 1-{ORGANIZATION_NAME_MAX_LENGTH} characters").into());
+        }
+
+        let uid = ctx.uid_unchecked();
+        let id = uuid::Uuid::now_v7().as_simple().to_string();
+
+        Organization::insert_with_owner(&ctx.env, &id, name, &uid).await?;
+        UserAccount::set_active_org(&ctx.env, &uid, Some(&id)).await?;
+        worker::console_log!("{} created organization {}", uid, id);
+
+        let organization = Organization::load(&ctx.env, &id).await?.ok_or("organization was not inserted")?;
+
+        Ok(OrganizationCreateResponse {
+            info: UserOrganization {
+                id: organization.id,
+                name: organization.name,
+                role: OrganizationRole::Owner,
+                created_at: organization.created_at,
+            }.into_info(Some(&id))
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for OrganizationList {
+    type Res = <OrganizationList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<OrganizationListResponse> {
+        let organizations = UserOrganization::list_by_uid(&ctx.env, &ctx.uid_unchecked()).await?;
+        // the validated one, not just whatever active_org_id says
+        let active_org_id = ctx.active_org().ok().map(|org| org.id.as_str());
+
+        Ok(OrganizationListResponse {
+            organizations: organizations.into_iter().map(|org| org.into_info(active_org_id)).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for OrganizationSwitch {
+    type Req = <OrganizationSwitch as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationSwitchRequest) -> ApiResult<()> {
+        let uid = ctx.uid_unchecked();
+
+        if let Some(id) = &data.id {
+            if Membership::load(&ctx.env, id, &uid).await?.is_none() {
+                worker::console_log!("{} can't switch to organization {} without being a member", uid, id);
+                return Err(AuthError::Forbidden.into());
+            }
+        }
+
+        UserAccount::set_active_org(&ctx.env, &uid, data.id.as_deref()).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for OrganizationMemberList {
+    type Res = <OrganizationMemberList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<OrganizationMemberListResponse> {
+        let org = ctx.active_org()?;
+        let members = Membership::list_by_org(&ctx.env, &org.id).await?;
+
+        Ok(OrganizationMemberListResponse {
+            members: members.into_iter().map(|member| member.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for OrganizationMemberRemove {
+    type Req = <OrganizationMemberRemove as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationMemberRemoveRequest) -> ApiResult<()> {
+        let org = ctx.active_org()?;
+        let uid = ctx.uid_unchecked();
+        let leaving = data.uid == uid;
+
+        // anyone can leave, otherwise it depends on both roles
+        if !leaving {
+            org.require_manage_members()?;
+            let member = Membership::load(&ctx.env, &org.id, &data.uid).await?.ok_or("no such member")?;
+            if !org.role.can_manage_role(member.role) {
+                worker::console_log!("{} ({:?}) can't remove {} ({:?}) from {}", uid, org.role, data.uid, member.role, org.id);
+                return Err(AuthError::Forbidden.into());
+            }
+        }
+
+        if !Membership::delete(&ctx.env, &org.id, &data.uid).await? {
+            return Err("the last owner can't leave the organization".into());
+        }
+
+        if leaving {
+            UserAccount::set_active_org(&ctx.env, &uid, None).await?;
+        }
+
+        worker::console_log!("{} removed {} from organization {}", uid, data.uid, org.id);
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for OrganizationInviteCreate {
+    type Req = <OrganizationInviteCreate as ApiBoth>::Req;
+    type Res = <OrganizationInviteCreate as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationInviteCreateRequest) -> ApiResult<OrganizationInviteCreateResponse> {
+        let org = ctx.active_org()?;
+        org.require_manage_members()?;
+        if !org.role.can_manage_role(data.role) {
+            return Err(AuthError::Forbidden.into());
+        }
+
+        let email = data.email.trim();
+        if !email.contains('@') {
+            return Err("invalid invite email".into());
+        }
+
+        let members = Membership::list_by_org(&ctx.env, &org.id).await?;
+        if members.iter().any(|member| member.email.eq_ignore_ascii_case(email)) {
+            return Err("this email already belongs to a member".into());
+        }
+
+        let organization = Organization::load(&ctx.env, &org.id).await?.ok_or("no such organization")?;
+        let account = &ctx.user.as_ref().unwrap().account;
+
+        // the token is only the proof of having the link, the row says what it's for
+        let auth_token = AuthTokenDO::create(&ctx.env, AuthTokenKind::OrganizationInvite, account.id.clone(), account.user_token.clone(), ORGANIZATION_INVITE_EXPIRES).await?;
+        let expires_at = now_ms() + ORGANIZATION_INVITE_EXPIRES;
+
+        OrganizationInvite::insert(&ctx.env, &auth_token.id, &org.id, email, data.role, &account.id, expires_at).await?;
+
+        mailer::send(ctx, email, MailerKind::OrganizationInvite {
+            organization_name: organization.name,
+            oob_token_id: auth_token.id.clone(),
+            oob_token_key: auth_token.key
+        }).await?;
+
+        worker::console_log!("{} invited someone to organization {} as {:?}", account.id, org.id, data.role);
+
+        let info = OrganizationInvite::load(&ctx.env, &auth_token.id).await?.ok_or("invite was not inserted")?.into();
+
+        Ok(OrganizationInviteCreateResponse {
+            info
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for OrganizationInviteList {
+    type Res = <OrganizationInviteList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<OrganizationInviteListResponse> {
+        let org = ctx.active_org()?;
+        org.require_manage_members()?;
+
+        let invites = OrganizationInvite::list_by_org(&ctx.env, &org.id, now_ms()).await?;
+
+        Ok(OrganizationInviteListResponse {
+            invites: invites.into_iter().map(|invite| invite.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for OrganizationInviteRevoke {
+    type Req = <OrganizationInviteRevoke as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationInviteRevokeRequest) -> ApiResult<()> {
+        let org = ctx.active_org()?;
+        org.require_manage_members()?;
+
+        match OrganizationInvite::load(&ctx.env, &data.id).await? {
+            Some(invite) if invite.org_id == org.id => {
+                OrganizationInvite::delete(&ctx.env, &invite.id).await?;
+                AuthTokenDO::destroy(&ctx.env, &invite.id).await
+            },
+            _ => Err("no such invite".into())
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for OrganizationInviteCheck {
+    type Req = <OrganizationInviteCheck as ApiBoth>::Req;
+    type Res = <OrganizationInviteCheck as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationInviteTokenRequest) -> ApiResult<OrganizationInviteCheckResponse> {
+        let invite = validate_invite(ctx, data).await?;
+        let organization = Organization::load(&ctx.env, &invite.org_id).await?.ok_or(AuthError::InvalidInvite)?;
+
+        Ok(OrganizationInviteCheckResponse {
+            organization_name: organization.name,
+            email: invite.email,
+            role: invite.role,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for OrganizationInviteAccept {
+    type Req = <OrganizationInviteAccept as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationInviteTokenRequest) -> ApiResult<()> {
+        let invite = validate_invite(ctx, data).await?;
+        let uid = ctx.uid_unchecked();
+
+        // whoever deletes the row gets to use it, so it can't be accepted twice
+        if !OrganizationInvite::delete(&ctx.env, &invite.id).await? {
+            return Err(AuthError::InvalidInvite.into());
+        }
+        AuthTokenDO::destroy(&ctx.env, &invite.id).await?;
+
+        Membership::insert(&ctx.env, &invite.org_id, &uid, invite.role).await?;
+        UserAccount::set_active_org(&ctx.env, &uid, Some(&invite.org_id)).await?;
+
+        worker::console_log!("{} joined organization {} as {:?}", uid, invite.org_id, invite.role);
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for OrganizationInviteDecline {
+    type Req = <OrganizationInviteDecline as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationInviteTokenRequest) -> ApiResult<()> {
+        let invite = validate_invite(ctx, data).await?;
+
+        OrganizationInvite::delete(&ctx.env, &invite.id).await?;
+        AuthTokenDO::destroy(&ctx.env, &invite.id).await
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for OrganizationSsoDomainAdd {
+    type Req = <OrganizationSsoDomainAdd as ApiBoth>::Req;
+    type Res = <OrganizationSsoDomainAdd as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationSsoDomainAddRequest) -> ApiResult<OrganizationSsoDomainResponse> {
+        let org = ctx.active_org()?;
+        org.require_owner()?;
+
+        let domain = normalize_domain(&data.domain).ok_or("invalid domain")?;
+        check_sso_provider(&ctx.env, &data.provider)?;
+
+        if SsoDomain::load(&ctx.env, &org.id, &domain).await?.is_some() {
+            return Err("this domain was already added".into());
+        }
+
+        // nothing is enforced until it's verified, so claiming any domain here is harmless
+        SsoDomain::insert(&ctx.env, &domain, &org.id, &data.provider, &generate_sso_verification_token()).await?;
+        worker::console_log!("{} added sso domain {} to organization {}", ctx.uid_unchecked(), domain, org.id);
+
+        let info = SsoDomain::load(&ctx.env, &org.id, &domain).await?.ok_or("sso domain was not inserted")?.into();
+
+        Ok(OrganizationSsoDomainResponse {
+            info
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for OrganizationSsoDomainList {
+    type Res = <OrganizationSsoDomainList as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<OrganizationSsoDomainListResponse> {
+        let org = ctx.active_org()?;
+        org.require_owner()?;
+
+        let domains = SsoDomain::list_by_org(&ctx.env, &org.id).await?;
+
+        Ok(OrganizationSsoDomainListResponse {
+            domains: domains.into_iter().map(|domain| domain.into()).collect()
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiBothExt for OrganizationSsoDomainVerify {
+    type Req = <OrganizationSsoDomainVerify as ApiBoth>::Req;
+    type Res = <OrganizationSsoDomainVerify as ApiBoth>::Res;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationSsoDomainRequest) -> ApiResult<OrganizationSsoDomainResponse> {
+        let org = ctx.active_org()?;
+        org.require_owner()?;
+
+        let domain = normalize_domain(&data.domain).ok_or("invalid domain")?;
+        let sso_domain = SsoDomain::load(&ctx.env, &org.id, &domain).await?.ok_or("no such domain")?;
+
+        if sso_domain.verified_at.is_none() {
+            if SsoDomain::load_verified(&ctx.env, &domain).await?.is_some() {
+                return Err("this domain is already claimed by another organization".into());
+            }
+
+            if !has_txt_record(&sso_domain.txt_record_name(), &sso_domain.txt_record_value()).await? {
+                return Err("the TXT record wasn't found, it can take a while for DNS changes to show up".into());
+            }
+
+            SsoDomain::set_verified(&ctx.env, &org.id, &domain, now_ms()).await?;
+            worker::console_log!("{} verified sso domain {} for organization {}", ctx.uid_unchecked(), domain, org.id);
+        }
+
+        let info = SsoDomain::load(&ctx.env, &org.id, &domain).await?.ok_or("no such domain")?.into();
+
+        Ok(OrganizationSsoDomainResponse {
+            info
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiReqExt for OrganizationSsoDomainRemove {
+    type Req = <OrganizationSsoDomainRemove as ApiReq>::Req;
+
+    async fn handle(ctx: &ApiContext, data: OrganizationSsoDomainRequest) -> ApiResult<()> {
+        let org = ctx.active_org()?;
+        org.require_owner()?;
+
+        let domain = normalize_domain(&data.domain).ok_or("invalid domain")?;
+        if !SsoDomain::delete(&ctx.env, &org.id, &domain).await? {
+            return Err("no such domain".into());
+        }
+
+        worker::console_log!("{} removed sso domain {} from organization {}", ctx.uid_unchecked(), domain, org.id);
+
+        Ok(())
+    }
+}
+
+// the token from the link must be valid, the invite still pending, and for the signed-in user's email
+// the token is kept, only accepting or declining uses it up
+async fn validate_invite(ctx: &ApiContext, data: OrganizationInviteTokenRequest) -> ApiResult<OrganizationInvite> {
+    let OrganizationInviteTokenRequest { oob_token_id, oob_token_key } = data;
+
+    AuthTokenDO::validate(&ctx.env, AuthTokenKind::OrganizationInvite, &oob_token_id, oob_token_key, AuthTokenAfterValidation::Keep).await
+        .map_err(|_| AuthError::InvalidInvite)?;
+
+    let invite = match OrganizationInvite::load(&ctx.env, &oob_token_id).await? {
+        Some(invite) if invite.expires_at > now_ms() => invite,
+        _ => return Err(AuthError::InvalidInvite.into())
+    };
+
+    if !invite.email.eq_ignore_ascii_case(&ctx.user.as_ref().unwrap().account.email) {
+        return Err(AuthError::OrganizationInviteWrongAccount.into());
+    }
+
+    Ok(invite)
+}
@@ -0,0 +1,46 @@
+mod handler;
+
+use shared::user::OrganizationRole;
+use crate::{db::{organization::Membership, user::UserAccount}, prelude::*};
+
+// The organization a request acts on, from the account's active_org_id
+// checked against the membership on every request, so a removed member loses access right away
+#[derive(Debug, Clone)]
+pub struct ActiveOrganization {
+    pub id: String,
+    // the user's role in it, as of this request
+    pub role: OrganizationRole,
+}
+
+impl ActiveOrganization {
+    // None if there's no active organization, or the user isn't a member of it (anymore)
+    pub async fn load(env: &Env, account: &UserAccount) -> ApiResult<Option<Self>> {
+        let org_id = match &account.active_org_id {
+            Some(org_id) => org_id,
+            None => return Ok(None)
+        };
+
+        Ok(Membership::load(env, org_id, &account.id).await?.map(|membership| Self {
+            id: membership.org_id,
+            role: membership.role,
+        }))
+    }
+
+    // Forbidden unless the user's role is allowed to manage members at all
+    pub fn require_manage_members(&self) -> ApiResult<()> {
+        if self.role.can_manage_members() {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden.into())
+        }
+    }
+
+    // Forbidden unless the user owns it, for what affects more than its members (e.g. sso domains)
+    pub fn require_owner(&self) -> ApiResult<()> {
+        if self.role == OrganizationRole::Owner {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden.into())
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use shared::{api::{account::AuthEventKind, recovery_code::{RecoveryCodeRegenerate, RecoveryCodeRegenerateResponse, RecoveryCodeStatus, RecoveryCodeStatusResponse}, ApiRes}, backend::result::ApiResult};
+use crate::{
+    api_ext::ApiResExt, auth::record_auth_event, db::recovery_code::RecoveryCode, ApiContext
+};
+use super::NewRecoveryCodes;
+
+#[async_trait(?Send)]
+impl ApiResExt for RecoveryCodeStatus {
+    type Res = <RecoveryCodeStatus as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<RecoveryCodeStatusResponse> {
+        let status = RecoveryCode::status(&ctx.env, &ctx.uid_unchecked()).await?;
+
+        Ok(RecoveryCodeStatusResponse {
+            remaining: status.remaining,
+            created_at: status.created_at,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl ApiResExt for RecoveryCodeRegenerate {
+    type Res = <RecoveryCodeRegenerate as ApiRes>::Res;
+
+    async fn handle(ctx: &ApiContext) -> ApiResult<RecoveryCodeRegenerateResponse> {
+        let uid = ctx.uid_unchecked();
+        let NewRecoveryCodes { codes, code_hashes } = NewRecoveryCodes::generate(&ctx.env)?;
+
+        RecoveryCode::replace_all(&ctx.env, &uid, &code_hashes).await?;
+        record_auth_event(ctx, AuthEventKind::RecoveryCodesGenerated, Some(&uid), None, None).await;
+
+        Ok(RecoveryCodeRegenerateResponse {
+            codes
+        })
+    }
+}
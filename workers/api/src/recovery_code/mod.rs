@@ -0,0 +1,52 @@
+mod handler;
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use crate::{config::{RECOVERY_CODE_COUNT, RECOVERY_CODE_LENGTH}, prelude::*};
+
+// no 0/o, 1/l/i, since these get written down and typed back in
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+// a fresh set, shown to the user once, with the hashes that get stored
+pub struct NewRecoveryCodes {
+    pub codes: Vec<String>,
+    pub code_hashes: Vec<String>,
+}
+
+impl NewRecoveryCodes {
+    pub fn generate(env: &Env) -> ApiResult<Self> {
+        let mut rng = rand::thread_rng();
+
+        let codes:Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| {
+            let code:String = (0..RECOVERY_CODE_LENGTH)
+                .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+                .collect();
+            // split in half for readability, the dash is ignored when checking
+            let (first, second) = code.split_at(RECOVERY_CODE_LENGTH / 2);
+            format!("{first}-{second}")
+        }).collect();
+
+        let code_hashes = codes.iter().map(|code| hash_recovery_code(env, code)).collect::<ApiResult<Vec<_>>>()?;
+
+        Ok(Self { codes, code_hashes })
+    }
+}
+
+// forgiving of how it was typed in: case, spaces, and dashes don't matter
+// ~49 bits is too little for a plain hash, a leaked db could be brute-forced for every user at once
+// so it's an HMAC with the RECOVERY_CODE_HMAC_KEY secret, which isn't in the db
+pub fn hash_recovery_code(env: &Env, code: &str) -> ApiResult<String> {
+    let normalized:String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    let key = get_secret(env, "RECOVERY_CODE_HMAC_KEY")?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).map_err(|err| ApiError::from(err.to_string()))?;
+    mac.update(normalized.as_bytes());
+
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+}
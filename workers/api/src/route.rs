@@ -1,10 +1,14 @@
-use crate::{api_ext::{ApiBothExt, ApiBothWithExtraExt, ApiEmptyDynRouteWithExtraExt, ApiEmptyExt, ApiReqExt, ApiResExt}, auth::AuthUser, config::API_ROOT_PATH, not_found::NotFoundHandler, prelude::*};
+use crate::{api_ext::{ApiBothExt, ApiBothWithExtraExt, ApiEmptyDynRouteWithExtraExt, ApiEmptyExt, ApiReqExt, ApiResExt}, auth::{check_request_origin, AuthUser}, config::API_ROOT_PATH, not_found::NotFoundHandler, prelude::*};
 use worker::{Context, Env};
-use shared::{api::auth::{AuthCheck, AuthCheckResetPassword, AuthConfirmResetPassword, AuthConfirmVerifyEmail, AuthOpenIdAccessTokenHook, AuthOpenIdConnect, AuthOpenIdFinalizeExec, AuthOpenIdFinalizeQuery, AuthRegister, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendVerifyEmail, AuthSignin, AuthSignout}, backend::route::{AuthRoute, Route}};
+use shared::{api::{auth::{AuthCheck, AuthCheckResetPassword, AuthConfirmMagicLink, AuthConfirmResetPassword, AuthConfirmVerifyEmail, AuthConfirmVerifyEmailCode, AuthIdentities, AuthIdentityUnlink, AuthImpersonationStop, AuthInviteCheck, AuthOpenIdAccessTokenHook, AuthOpenIdConnect, AuthOpenIdFinalizeExec, AuthOpenIdFinalizeQuery, AuthOpenIdLinkConnect, AuthOpenIdLinkFinalize, AuthOpenIdProviders, AuthPasswordParams, AuthRegister, AuthSendMagicLink, AuthSendResetPasswordAny, AuthSendResetPasswordMe, AuthSendVerifyEmail, AuthSignin, AuthSigninRecoveryCode, AuthSignout, AuthTermsAccept, AuthTermsStatus}, account::{AccountDelete, AccountDeleteCancel, AccountExport, AccountProfile, AccountProfileUpdate, AccountSecurityEvents}, admin::{AdminAccountDelete, AdminAccountExport, AdminImpersonationLog, AdminImpersonationStart, AdminRoleGrant, AdminRoleList, AdminRoleRevoke}, api_token::{ApiTokenCreate, ApiTokenList, ApiTokenRevoke}, invite::{InviteCreate, InviteList, InviteRevoke}, recovery_code::{RecoveryCodeRegenerate, RecoveryCodeStatus}, organization::{OrganizationCreate, OrganizationInviteAccept, OrganizationInviteCheck, OrganizationInviteCreate, OrganizationInviteDecline, OrganizationInviteList, OrganizationInviteRevoke, OrganizationList, OrganizationMemberList, OrganizationMemberRemove, OrganizationSsoDomainAdd, OrganizationSsoDomainList, OrganizationSsoDomainRemove, OrganizationSsoDomainVerify, OrganizationSwitch}}, backend::route::{AccountRoute, AdminRoute, ApiTokenRoute, AuthRoute, InviteRoute, OrganizationRoute, RecoveryCodeRoute, Route}};
 
 pub async fn handle_route(req: Request, env: Env, cf_ctx: Context) -> ApiResponse {
     Ok(match Route::try_from_url(&req.url(), API_ROOT_PATH) {
         Some(route) => {
+            if let Err(err) = check_request_origin(&req, &route) {
+                return Ok(err.into());
+            }
+
             let user = match AuthUser::try_new(&env, &req, &route).await {
                 Ok(user) => user,
                 Err(err) => return Ok(err.into())
@@ -23,6 +27,9 @@ pub async fn handle_route(req: Request, env: Env, cf_ctx: Context) -> ApiRespons
                     AuthRoute::Signin => {
                         AuthSignin::router(ctx).await
                     },
+                    AuthRoute::SigninRecoveryCode => {
+                        AuthSigninRecoveryCode::router(ctx).await
+                    },
                     AuthRoute::Signout => {
                         AuthSignout::router(ctx).await
                     },
@@ -35,6 +42,10 @@ pub async fn handle_route(req: Request, env: Env, cf_ctx: Context) -> ApiRespons
                         AuthConfirmVerifyEmail::router(ctx).await
                     },
 
+                    AuthRoute::ConfirmEmailValidationCode => {
+                        AuthConfirmVerifyEmailCode::router(ctx).await
+                    },
+
                     AuthRoute::SendPasswordResetMe => {
                         AuthSendResetPasswordMe::router(ctx).await
                     },
@@ -50,6 +61,21 @@ pub async fn handle_route(req: Request, env: Env, cf_ctx: Context) -> ApiRespons
                         AuthCheckResetPassword::router(ctx).await
                     }
 
+                    AuthRoute::SendMagicLink => {
+                        AuthSendMagicLink::router(ctx).await
+                    },
+                    AuthRoute::PasswordParams => {
+                        AuthPasswordParams::router(ctx).await
+                    },
+
+                    AuthRoute::ConfirmMagicLink => {
+                        AuthConfirmMagicLink::router(ctx).await
+                    },
+
+                    AuthRoute::OpenIdProviders => {
+                        AuthOpenIdProviders::router(ctx).await
+                    }
+
                     AuthRoute::OpenIdConnect=> {
                         AuthOpenIdConnect::router(ctx).await
                     }
@@ -65,6 +91,154 @@ pub async fn handle_route(req: Request, env: Env, cf_ctx: Context) -> ApiRespons
                     AuthRoute::OpenIdFinalizeQuery => {
                         AuthOpenIdFinalizeQuery::router(ctx).await
                     }
+
+                    AuthRoute::OpenIdLinkConnect => {
+                        AuthOpenIdLinkConnect::router(ctx).await
+                    }
+
+                    AuthRoute::OpenIdLinkFinalize => {
+                        AuthOpenIdLinkFinalize::router(ctx).await
+                    }
+
+                    AuthRoute::Identities => {
+                        AuthIdentities::router(ctx).await
+                    }
+
+                    AuthRoute::IdentityUnlink => {
+                        AuthIdentityUnlink::router(ctx).await
+                    },
+                    AuthRoute::ImpersonationStop => {
+                        AuthImpersonationStop::router(ctx).await
+                    },
+                    AuthRoute::InviteCheck => {
+                        AuthInviteCheck::router(ctx).await
+                    },
+                    AuthRoute::TermsStatus => {
+                        AuthTermsStatus::router(ctx).await
+                    },
+                    AuthRoute::TermsAccept => {
+                        AuthTermsAccept::router(ctx).await
+                    }
+                },
+                Route::Account(account_route) => match account_route {
+                    AccountRoute::Delete => {
+                        AccountDelete::router(ctx).await
+                    },
+                    AccountRoute::DeleteCancel => {
+                        AccountDeleteCancel::router(ctx).await
+                    },
+                    AccountRoute::Export => {
+                        AccountExport::router(ctx).await
+                    },
+                    AccountRoute::SecurityEvents => {
+                        AccountSecurityEvents::router(ctx).await
+                    },
+                    AccountRoute::Profile => {
+                        AccountProfile::router(ctx).await
+                    },
+                    AccountRoute::ProfileUpdate => {
+                        AccountProfileUpdate::router(ctx).await
+                    },
+                },
+                Route::Admin(admin_route) => match admin_route {
+                    AdminRoute::AccountDelete => {
+                        AdminAccountDelete::router(ctx).await
+                    },
+                    AdminRoute::AccountExport => {
+                        AdminAccountExport::router(ctx).await
+                    },
+                    AdminRoute::RoleList => {
+                        AdminRoleList::router(ctx).await
+                    },
+                    AdminRoute::RoleGrant => {
+                        AdminRoleGrant::router(ctx).await
+                    },
+                    AdminRoute::RoleRevoke => {
+                        AdminRoleRevoke::router(ctx).await
+                    },
+                    AdminRoute::ImpersonationStart => {
+                        AdminImpersonationStart::router(ctx).await
+                    },
+                    AdminRoute::ImpersonationLog => {
+                        AdminImpersonationLog::router(ctx).await
+                    },
+                },
+                Route::ApiToken(api_token_route) => match api_token_route {
+                    ApiTokenRoute::Create => {
+                        ApiTokenCreate::router(ctx).await
+                    },
+                    ApiTokenRoute::List => {
+                        ApiTokenList::router(ctx).await
+                    },
+                    ApiTokenRoute::Revoke => {
+                        ApiTokenRevoke::router(ctx).await
+                    },
+                },
+                Route::Invite(invite_route) => match invite_route {
+                    InviteRoute::Create => {
+                        InviteCreate::router(ctx).await
+                    },
+                    InviteRoute::List => {
+                        InviteList::router(ctx).await
+                    },
+                    InviteRoute::Revoke => {
+                        InviteRevoke::router(ctx).await
+                    },
+                },
+                Route::RecoveryCode(recovery_code_route) => match recovery_code_route {
+                    RecoveryCodeRoute::Status => {
+                        RecoveryCodeStatus::router(ctx).await
+                    },
+                    RecoveryCodeRoute::Regenerate => {
+                        RecoveryCodeRegenerate::router(ctx).await
+                    },
+                },
+                Route::Organization(organization_route) => match organization_route {
+                    OrganizationRoute::Create => {
+                        OrganizationCreate::router(ctx).await
+                    },
+                    OrganizationRoute::List => {
+                        OrganizationList::router(ctx).await
+                    },
+                    OrganizationRoute::Switch => {
+                        OrganizationSwitch::router(ctx).await
+                    },
+                    OrganizationRoute::MemberList => {
+                        OrganizationMemberList::router(ctx).await
+                    },
+                    OrganizationRoute::MemberRemove => {
+                        OrganizationMemberRemove::router(ctx).await
+                    },
+                    OrganizationRoute::InviteCreate => {
+                        OrganizationInviteCreate::router(ctx).await
+                    },
+                    OrganizationRoute::InviteList => {
+                        OrganizationInviteList::router(ctx).await
+                    },
+                    OrganizationRoute::InviteRevoke => {
+                        OrganizationInviteRevoke::router(ctx).await
+                    },
+                    OrganizationRoute::InviteCheck => {
+                        OrganizationInviteCheck::router(ctx).await
+                    },
+                    OrganizationRoute::InviteAccept => {
+                        OrganizationInviteAccept::router(ctx).await
+                    },
+                    OrganizationRoute::InviteDecline => {
+                        OrganizationInviteDecline::router(ctx).await
+                    },
+                    OrganizationRoute::SsoDomainAdd => {
+                        OrganizationSsoDomainAdd::router(ctx).await
+                    },
+                    OrganizationRoute::SsoDomainList => {
+                        OrganizationSsoDomainList::router(ctx).await
+                    },
+                    OrganizationRoute::SsoDomainVerify => {
+                        OrganizationSsoDomainVerify::router(ctx).await
+                    },
+                    OrganizationRoute::SsoDomainRemove => {
+                        OrganizationSsoDomainRemove::router(ctx).await
+                    },
                 },
             };
 